@@ -0,0 +1,16 @@
+-- Proposals to move a posting to another time
+CREATE TABLE proposals (
+    id SERIAL PRIMARY KEY,
+    -- Posting whose time should change
+    scrim_id INTEGER NOT NULL REFERENCES scrims(id),
+    -- Invite to the posting this proposal answers, if any
+    invite_id INTEGER REFERENCES invites(id),
+    -- Discord user IDs of both sides
+    proposer_id BIGINT NOT NULL,
+    target_id BIGINT NOT NULL,
+    time TIMESTAMPTZ NOT NULL,
+    -- `Pending`, `Accepted` or `Declined`
+    status VARCHAR(255) NOT NULL DEFAULT 'Pending',
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+CREATE INDEX scrims_team_name ON scrims (team_name);
@@ -0,0 +1,3 @@
+-- Posting of the other side whose time changes together with the proposed one. Both postings are
+-- matched with each other once the proposal is accepted.
+ALTER TABLE proposals ADD other_id INTEGER REFERENCES scrims(id);
@@ -0,0 +1,15 @@
+CREATE TABLE guilds (
+    id BIGINT PRIMARY KEY,
+    -- Channel the open postings are shown in, if any
+    board_channel_id BIGINT
+);
+-- Messages the bot posted on the boards, one per guild and open posting
+CREATE TABLE board_messages (
+    guild_id BIGINT NOT NULL REFERENCES guilds(id),
+    scrim_id INTEGER NOT NULL REFERENCES scrims(id),
+    channel_id BIGINT NOT NULL,
+    message_id BIGINT NOT NULL,
+    -- Content the message was last posted with, so it is only edited when something changed
+    content TEXT NOT NULL,
+    PRIMARY KEY (guild_id, scrim_id)
+);
@@ -0,0 +1,13 @@
+-- Filters of `/browse` replies. Their buttons refer to them, so they keep working after restarts.
+CREATE TABLE browse_filters (
+    id SERIAL PRIMARY KEY,
+    creator_id BIGINT NOT NULL,
+    region VARCHAR(255),
+    platform VARCHAR(255),
+    rank_from INTEGER,
+    rank_to INTEGER,
+    from_time TIMESTAMPTZ,
+    to_time TIMESTAMPTZ,
+    team_name VARCHAR(255),
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
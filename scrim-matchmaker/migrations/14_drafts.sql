@@ -0,0 +1,12 @@
+-- Postings waiting for their creator to confirm them. They are moved to `scrims` once confirmed.
+CREATE TABLE scrim_drafts (
+    id SERIAL PRIMARY KEY,
+    creator_id BIGINT NOT NULL,
+    region VARCHAR(255) NOT NULL,
+    platform VARCHAR(255) NOT NULL,
+    rank_from INTEGER NOT NULL,
+    rank_to INTEGER NOT NULL,
+    time TIMESTAMPTZ NOT NULL,
+    team_name VARCHAR(255),
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
@@ -0,0 +1,2 @@
+-- Language picked with `/language`, e.g. `de`. NULL follows the language of the Discord client.
+ALTER TABLE users ADD language VARCHAR(255);
@@ -0,0 +1,6 @@
+-- How times are written out in text, e.g. in select menus. Dates in `<t:...>` tags are formatted
+-- by Discord itself.
+ALTER TABLE users ADD clock VARCHAR(255) NOT NULL DEFAULT 'H24';
+ALTER TABLE users ADD date_order VARCHAR(255) NOT NULL DEFAULT 'DayMonth';
+-- First day of the week, for relative dates like `next friday` or `last week`
+ALTER TABLE users ADD week_start VARCHAR(255) NOT NULL DEFAULT 'Monday';
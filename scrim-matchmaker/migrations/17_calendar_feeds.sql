@@ -0,0 +1,8 @@
+-- Links calendars can subscribe to, one per creator and team
+CREATE TABLE calendar_feeds (
+    token VARCHAR(255) PRIMARY KEY DEFAULT gen_random_uuid()::VARCHAR,
+    creator_id BIGINT NOT NULL,
+    -- Team whose scrims are in the feed. NULL for all scrims of the creator
+    team_name VARCHAR(255)
+);
+CREATE UNIQUE INDEX calendar_feeds_owner ON calendar_feeds (creator_id, LOWER(COALESCE(team_name, '')));
@@ -0,0 +1,22 @@
+-- Previews of `/import`. The calendar file is only read once, so the events it had are kept until
+-- they are posted or the preview is cancelled.
+CREATE TABLE imports (
+    id SERIAL PRIMARY KEY,
+    creator_id BIGINT NOT NULL,
+    region VARCHAR(255) NOT NULL,
+    platform VARCHAR(255) NOT NULL,
+    rank_from INTEGER NOT NULL,
+    rank_to INTEGER NOT NULL,
+    team_name VARCHAR(255),
+    -- Number of events left out because the file had too many
+    skipped INTEGER NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+CREATE TABLE import_events (
+    import_id INTEGER NOT NULL REFERENCES imports(id) ON DELETE CASCADE,
+    -- Index of the event in the preview
+    position INTEGER NOT NULL,
+    time TIMESTAMPTZ NOT NULL,
+    summary TEXT,
+    PRIMARY KEY (import_id, position)
+);
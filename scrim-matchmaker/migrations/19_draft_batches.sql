@@ -0,0 +1,3 @@
+-- First draft of the same `/lfs` for the other drafts posted with it. They are confirmed and
+-- cancelled together using the id of the first one.
+ALTER TABLE scrim_drafts ADD batch_id INTEGER REFERENCES scrim_drafts(id) ON DELETE CASCADE;
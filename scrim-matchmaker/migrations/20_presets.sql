@@ -0,0 +1,11 @@
+-- Named defaults for `/lfs`. Fields a preset leaves out have to be given with the command.
+CREATE TABLE presets (
+    creator_id BIGINT NOT NULL,
+    name VARCHAR(255) NOT NULL,
+    region VARCHAR(255),
+    platform VARCHAR(255),
+    rank_from INTEGER,
+    rank_to INTEGER,
+    team_name VARCHAR(255)
+);
+CREATE UNIQUE INDEX presets_name ON presets (creator_id, LOWER(name));
@@ -0,0 +1,3 @@
+-- Whether scrims of the same creator and team at overlapping times are refused or only warned
+-- about when posting or accepting them in the guild
+ALTER TABLE guilds ADD overlap_policy VARCHAR(255) NOT NULL DEFAULT 'Warn';
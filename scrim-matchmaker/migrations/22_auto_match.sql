@@ -0,0 +1,12 @@
+-- Whether the posting is paired automatically with other postings that opted in
+ALTER TABLE scrims ADD auto_match BOOLEAN NOT NULL DEFAULT FALSE;
+-- When the posting was paired automatically. Either side can decline for a while afterwards
+ALTER TABLE scrims ADD auto_matched_at TIMESTAMPTZ;
+ALTER TABLE scrim_drafts ADD auto_match BOOLEAN NOT NULL DEFAULT FALSE;
+-- Automatic pairings one side declined, so the two postings aren't paired again. The lower id
+-- comes first
+CREATE TABLE declined_auto_matches (
+    scrim_id INTEGER NOT NULL REFERENCES scrims(id),
+    other_id INTEGER NOT NULL REFERENCES scrims(id),
+    PRIMARY KEY (scrim_id, other_id)
+);
@@ -0,0 +1,14 @@
+-- Minutes before a matched scrim at which the user is reminded about it
+ALTER TABLE users ADD reminder_offsets INTEGER[] NOT NULL DEFAULT '{1440, 30}';
+CREATE TABLE sent_reminders (
+    scrim_id INTEGER NOT NULL REFERENCES scrims(id),
+    offset_minutes INTEGER NOT NULL,
+    PRIMARY KEY (scrim_id, offset_minutes)
+);
+-- Every scrim that is matched, together with its partner. Both sides of a match point at each
+-- other, so a pair shows up twice, once from each side. A scrim that only picked another one isn't
+-- matched yet.
+CREATE VIEW matched_scrims AS
+    SELECT s.id, s.creator_id, s.time, o.id AS other_id, o.creator_id AS other_creator_id,
+        s.cancelled OR o.cancelled AS cancelled
+    FROM scrims s JOIN scrims o ON s.match_id = o.id AND o.match_id = s.id;
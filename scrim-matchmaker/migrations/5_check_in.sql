@@ -0,0 +1,5 @@
+ALTER TABLE scrims ADD checked_in BOOLEAN NOT NULL DEFAULT FALSE;
+-- Whether the check-in prompt has been sent to the creator
+ALTER TABLE scrims ADD check_in_prompted BOOLEAN NOT NULL DEFAULT FALSE;
+-- Whether the check-in has been evaluated after the scrim started
+ALTER TABLE scrims ADD check_in_resolved BOOLEAN NOT NULL DEFAULT FALSE;
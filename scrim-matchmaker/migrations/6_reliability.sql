@@ -0,0 +1,15 @@
+-- Set when the creator cancelled less than a day before the start. It stays set if the scrim is
+-- restored afterwards.
+ALTER TABLE scrims ADD late_cancelled BOOLEAN NOT NULL DEFAULT FALSE;
+-- Set when the partner reports that the creator didn't show up
+ALTER TABLE scrims ADD no_show BOOLEAN NOT NULL DEFAULT FALSE;
+-- Share of the matched scrims of every creator that they neither cancelled less than a day before
+-- the start nor missed.
+CREATE VIEW reliability AS
+    SELECT m.creator_id,
+        COUNT(*) AS total,
+        (1 - (COUNT(*) FILTER (WHERE s.no_show OR s.late_cancelled))::FLOAT4 / COUNT(*))::FLOAT4
+            AS score
+    FROM matched_scrims m JOIN scrims s ON s.id = m.id
+    WHERE m.time <= NOW() OR s.cancelled OR s.late_cancelled
+    GROUP BY m.creator_id;
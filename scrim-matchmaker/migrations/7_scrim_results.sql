@@ -0,0 +1,15 @@
+CREATE TABLE scrim_results (
+    -- The scrim of the reporting side. Every side reports on its own.
+    scrim_id INTEGER PRIMARY KEY REFERENCES scrims(id),
+    -- Maps won and lost by the reporting side
+    maps_won INTEGER NOT NULL,
+    maps_lost INTEGER NOT NULL,
+    -- Free-form map results, e.g. `Ilios 2-1, Rialto 3-2`
+    details VARCHAR(1000),
+    -- Rating of the partner
+    rating INTEGER NOT NULL CHECK (rating BETWEEN 1 AND 5),
+    comment VARCHAR(1000),
+    reported_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+-- Whether the creator has been asked to report the result
+ALTER TABLE scrims ADD result_prompted BOOLEAN NOT NULL DEFAULT FALSE;
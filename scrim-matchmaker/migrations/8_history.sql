@@ -0,0 +1,12 @@
+CREATE INDEX scrims_creator_time ON scrims (creator_id, time);
+-- Filters of `/history` replies. Their buttons refer to them, so they keep working after restarts.
+CREATE TABLE history_filters (
+    id SERIAL PRIMARY KEY,
+    creator_id BIGINT NOT NULL,
+    team_name VARCHAR(255),
+    from_time TIMESTAMPTZ,
+    to_time TIMESTAMPTZ,
+    opponent_id BIGINT,
+    opponent_team VARCHAR(255),
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
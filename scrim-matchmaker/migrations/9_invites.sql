@@ -0,0 +1,12 @@
+CREATE TABLE invites (
+    id SERIAL PRIMARY KEY,
+    -- Posting of the inviting side
+    scrim_id INTEGER NOT NULL REFERENCES scrims(id),
+    -- Discord user ID of the invited captain
+    target_id BIGINT NOT NULL,
+    -- Team name the invited side gets when accepting
+    target_team_name VARCHAR(255),
+    -- `Pending`, `Accepted` or `Declined`
+    status VARCHAR(255) NOT NULL DEFAULT 'Pending',
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
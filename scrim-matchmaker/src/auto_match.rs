@@ -0,0 +1,127 @@
+use chrono::Utc;
+use poise::serenity_prelude::{
+    self as serenity, ButtonStyle, ComponentInteraction, CreateActionRow, CreateButton,
+    CreateInteractionResponse, CreateInteractionResponseMessage, CreateMessage,
+};
+
+use crate::{
+    components::ComponentAction,
+    db,
+    i18n::{self, t},
+    lfs::LookingForScrim,
+    reminders::send_dm,
+    render::{write_scrim_meta, write_scrim_with_name},
+    Data, Error,
+};
+
+/// Highest difference as computed by [`db::find_matches_in`] for two postings to be paired
+/// automatically, e.g. the same region and platform with start times 15 minutes apart.
+const MAX_DIFFERENCE: f32 = 150.;
+/// How many of the closest candidates are tried for each posting.
+const CANDIDATES: i64 = 10;
+/// How many minutes both sides have to decline an automatic pairing. Postings starting sooner
+/// aren't paired anymore.
+pub const AUTO_MATCH_GRACE: i32 = 30;
+
+/// Pair open postings that opted into automatic matching with their closest candidate that opted
+/// in too, and tell both creators.
+#[tracing::instrument(err, skip(ctx, db))]
+pub async fn run(ctx: &serenity::Context, db: &sqlx::PgPool) -> Result<(), Error> {
+    for scrim in db::get_auto_match_postings(db, AUTO_MATCH_GRACE).await? {
+        for (difference, other) in db::find_matches_in(db, &scrim, 0, CANDIDATES).await? {
+            if difference > MAX_DIFFERENCE {
+                break;
+            }
+            // Candidates might have been paired or picked since they were found
+            if !other.auto_match || !db::pair_scrims(db, &scrim, &other, AUTO_MATCH_GRACE).await? {
+                continue;
+            }
+            notify(ctx, db, &scrim, &other).await?;
+            notify(ctx, db, &other, &scrim).await?;
+            break;
+        }
+    }
+    Ok(())
+}
+
+/// Tell the creator of `scrim` that it was paired with `other`.
+async fn notify(
+    ctx: &serenity::Context,
+    db: &sqlx::PgPool,
+    scrim: &LookingForScrim,
+    other: &LookingForScrim,
+) -> Result<(), Error> {
+    let locale = i18n::locale_of(db, scrim.creator_id, None).await;
+    let now = Utc::now();
+    let mut opponent = String::new();
+    write_scrim_with_name(other, Some(scrim), true, locale, now, &mut opponent);
+    let deadline = now + chrono::Duration::minutes(AUTO_MATCH_GRACE.into());
+    let content = t!(
+        locale,
+        "auto-match-found",
+        time = scrim.time.timestamp(),
+        opponent = opponent,
+        deadline = deadline.timestamp()
+    );
+
+    let msg = CreateMessage::new()
+        .content(content)
+        .components(vec![CreateActionRow::Buttons(vec![CreateButton::new(
+            ComponentAction::AutoMatch { scrim_id: scrim.id }.custom_id()?,
+        )
+        .style(ButtonStyle::Danger)
+        .label(t!(locale, "button-decline"))])]);
+    if let Err(e) = send_dm(ctx, scrim.creator_id, msg).await {
+        tracing::warn!(
+            "Could not send automatic match to {}: {e}",
+            scrim.creator_id
+        );
+    }
+    Ok(())
+}
+
+/// Handle the decline button of an automatic pairing. Both postings are open again afterwards.
+pub async fn handle_component(
+    ctx: &serenity::Context,
+    data: &Data,
+    i: &ComponentInteraction,
+    scrim_id: i32,
+) -> Result<(), Error> {
+    let user_id = i.user.id.get() as i64;
+    let locale = i18n::component_locale(data, i).await;
+    let other_id = db::decline_auto_match(&data.db, scrim_id, user_id, AUTO_MATCH_GRACE).await?;
+    let content = match other_id {
+        Some(_) => t!(locale, "auto-match-declined"),
+        None => t!(locale, "auto-match-closed"),
+    };
+    i.create_response(
+        ctx,
+        CreateInteractionResponse::UpdateMessage(
+            CreateInteractionResponseMessage::new()
+                .content(content)
+                .components(vec![]),
+        ),
+    )
+    .await?;
+
+    let Some(other_id) = other_id else {
+        return Ok(());
+    };
+    let other = db::get_scrim_in(&data.db, other_id).await?;
+    let locale = i18n::locale_of(&data.db, other.creator_id, None).await;
+    let mut meta = String::new();
+    write_scrim_meta(&other, None, locale, Utc::now(), &mut meta);
+    let content = t!(
+        locale,
+        "auto-match-declined-notice",
+        user = user_id,
+        scrim = meta
+    );
+    if let Err(e) = send_dm(ctx, other.creator_id, CreateMessage::new().content(content)).await {
+        tracing::warn!(
+            "Could not notify {} about declined match: {e}",
+            other.creator_id
+        );
+    }
+    Ok(())
+}
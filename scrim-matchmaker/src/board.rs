@@ -0,0 +1,460 @@
+use chrono::Utc;
+use poise::serenity_prelude::{
+    self as serenity, ButtonStyle, ChannelId, ComponentInteraction, CreateActionRow,
+    CreateAllowedMentions, CreateButton, CreateInteractionResponse,
+    CreateInteractionResponseMessage, CreateMessage, EditMessage, MessageId,
+};
+
+use crate::{
+    components::{Answer, Challenger, ComponentAction},
+    db,
+    i18n::{self, t, Locale, Text},
+    interactions::report_error,
+    lfs::LookingForScrim,
+    overlaps::overlap_warning,
+    reminders::send_dm,
+    render::{scrim_card, write_scrim_meta, write_scrim_with_name, View},
+    Context, Data, Error,
+};
+
+/// How many postings are shown on a board at most.
+const BOARD_SIZE: i64 = 20;
+
+/// Message the bot posted on a board for an open posting.
+#[derive(Debug, Clone)]
+pub struct BoardMessage {
+    pub guild_id: i64,
+    pub scrim_id: i32,
+    pub channel_id: i64,
+    pub message_id: i64,
+    pub content: String,
+}
+
+/// Text describing the posting, stored to detect which messages need to be edited.
+fn board_content(scrim: &LookingForScrim, locale: Locale) -> String {
+    let mut content = format!("### {}\n", t!(locale, "board-title"));
+    write_scrim_with_name(scrim, None, true, locale, Utc::now(), &mut content);
+    content
+}
+
+fn board_message(scrim: &LookingForScrim, locale: Locale) -> Result<CreateMessage, Error> {
+    Ok(View::default()
+        .embed(scrim_card(scrim, locale, Utc::now()))
+        .components(vec![CreateActionRow::Buttons(vec![CreateButton::new(
+            ComponentAction::Challenge { scrim_id: scrim.id }.custom_id()?,
+        )
+        .style(ButtonStyle::Primary)
+        .label(t!(locale, "board-challenge"))])])
+        .message()
+        // Don't ping the creators of all postings
+        .allowed_mentions(CreateAllowedMentions::new()))
+}
+
+#[poise::command(
+    slash_command,
+    guild_only,
+    default_member_permissions = "MANAGE_GUILD",
+    ephemeral
+)]
+#[tracing::instrument(err, skip(ctx), fields(user = ctx.author().id.get()))]
+pub async fn board(
+    ctx: Context<'_>,
+    #[channel_types("Text")] channel: Option<serenity::GuildChannel>,
+) -> Result<(), Error> {
+    let guild_id = ctx.guild_id().ok_or(Text::new("board-guild-only"))?;
+    let channel_id = channel.map(|channel| channel.id.get() as i64);
+    db::set_board_channel(&ctx.data().db, guild_id.get() as i64, channel_id).await?;
+
+    // Fill the board right away so missing permissions show up here
+    let scrims = db::get_open_scrims(&ctx.data().db, BOARD_SIZE).await?;
+    sync_board(
+        ctx.serenity_context(),
+        &ctx.data().db,
+        guild_id.get() as i64,
+        channel_id,
+        &scrims,
+    )
+    .await
+    .map_err(|e| Text::new("board-failed").arg("error", e))?;
+
+    let locale = i18n::locale(ctx).await;
+    match channel_id {
+        Some(channel_id) => {
+            ctx.reply(t!(locale, "board-set", channel = channel_id))
+                .await?
+        }
+        None => ctx.reply(t!(locale, "board-removed")).await?,
+    };
+    Ok(())
+}
+
+/// Bring the boards of all guilds up to date with the open postings.
+#[tracing::instrument(err, skip(ctx, db))]
+pub async fn sync(ctx: &serenity::Context, db: &sqlx::PgPool) -> Result<(), Error> {
+    let scrims = db::get_open_scrims(db, BOARD_SIZE).await?;
+    for (guild_id, channel_id) in db::get_boards(db).await? {
+        if let Err(e) = sync_board(ctx, db, guild_id, channel_id, &scrims).await {
+            tracing::warn!("Could not update board of guild {guild_id}: {e}");
+        }
+    }
+    Ok(())
+}
+
+/// Post, edit and delete the messages on the board of a guild so it shows exactly `scrims`. Without
+/// a channel, all messages are removed. Boards are shared by everyone on a server, so they are
+/// shown in the default language.
+async fn sync_board(
+    ctx: &serenity::Context,
+    db: &sqlx::PgPool,
+    guild_id: i64,
+    channel_id: Option<i64>,
+    scrims: &[LookingForScrim],
+) -> Result<(), Error> {
+    // Remove postings that are matched, cancelled, expired or on an old channel
+    let (existing, stale): (Vec<_>, Vec<_>) = db::get_board_messages(db, guild_id)
+        .await?
+        .into_iter()
+        .partition(|msg| {
+            Some(msg.channel_id) == channel_id && scrims.iter().any(|s| s.id == msg.scrim_id)
+        });
+    for msg in stale {
+        let channel = ChannelId::new(msg.channel_id as u64);
+        if let Err(e) = channel
+            .delete_message(ctx, MessageId::new(msg.message_id as u64))
+            .await
+        {
+            // The message might have been deleted by a moderator
+            tracing::debug!("Could not delete board message: {e}");
+        }
+        db::delete_board_message(db, guild_id, msg.scrim_id).await?;
+    }
+
+    let Some(channel_id) = channel_id else {
+        return Ok(());
+    };
+    let channel = ChannelId::new(channel_id as u64);
+    let locale = Locale::default();
+    for scrim in scrims {
+        let content = board_content(scrim, locale);
+        match existing.iter().find(|msg| msg.scrim_id == scrim.id) {
+            Some(msg) if msg.content == content => {}
+            Some(msg) => {
+                if let Err(e) = channel
+                    .edit_message(
+                        ctx,
+                        MessageId::new(msg.message_id as u64),
+                        EditMessage::new()
+                            .content("")
+                            .embed(scrim_card(scrim, locale, Utc::now())),
+                    )
+                    .await
+                {
+                    // Forget deleted messages so they are posted again on the next update
+                    tracing::debug!("Could not edit board message: {e}");
+                    db::delete_board_message(db, guild_id, msg.scrim_id).await?;
+                    continue;
+                }
+                db::save_board_message(
+                    db,
+                    &BoardMessage {
+                        content,
+                        ..msg.clone()
+                    },
+                )
+                .await?;
+            }
+            None => {
+                let sent = channel
+                    .send_message(ctx, board_message(scrim, locale)?)
+                    .await?;
+                db::save_board_message(
+                    db,
+                    &BoardMessage {
+                        guild_id,
+                        scrim_id: scrim.id,
+                        channel_id,
+                        message_id: sent.id.get() as i64,
+                        content,
+                    },
+                )
+                .await?;
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Handle the challenge buttons on boards.
+pub async fn handle_challenge(
+    ctx: &serenity::Context,
+    data: &Data,
+    i: &ComponentInteraction,
+    scrim_id: i32,
+) -> Result<(), Error> {
+    let locale = i18n::component_locale(data, i).await;
+    let content = match challenge(ctx, data, i.user.id.get() as i64, scrim_id).await {
+        Ok(content) => content.render(locale),
+        Err(e) => i18n::error_text(&e, locale),
+    };
+    i.create_response(
+        ctx,
+        CreateInteractionResponse::Message(
+            CreateInteractionResponseMessage::new()
+                .content(content)
+                .ephemeral(true),
+        ),
+    )
+    .await?;
+    Ok(())
+}
+
+/// Handle the answers to challenges sent in direct messages.
+pub async fn handle_answer(
+    ctx: &serenity::Context,
+    data: &Data,
+    i: &ComponentInteraction,
+    answer: Answer,
+    scrim_id: i32,
+    challenger: Challenger,
+) -> Result<(), Error> {
+    match handle_action(ctx, data, i, answer, scrim_id, challenger).await {
+        Ok(()) => Ok(()),
+        Err(e) => report_error(ctx, data, i, e).await,
+    }
+}
+
+async fn handle_action(
+    ctx: &serenity::Context,
+    data: &Data,
+    i: &ComponentInteraction,
+    answer: Answer,
+    scrim_id: i32,
+    challenger: Challenger,
+) -> Result<(), Error> {
+    let scrim = db::get_scrim_in(&data.db, scrim_id).await?;
+    let locale = i18n::component_locale(data, i).await;
+    if scrim.creator_id != i.user.id.get() as i64 {
+        i.create_response(
+            ctx,
+            CreateInteractionResponse::Message(
+                CreateInteractionResponseMessage::new()
+                    .content(t!(locale, "scrim-not-yours"))
+                    .ephemeral(true),
+            ),
+        )
+        .await?;
+        return Ok(());
+    }
+    let other = match challenger {
+        Challenger::Scrim(other_id) => db::get_scrim_in(&data.db, other_id).await?,
+        Challenger::User(user_id) => challenger_scrim(data, user_id, &scrim).await?,
+    };
+
+    let (content, notice) = match answer {
+        Answer::Accept => {
+            let guild_id = i.guild_id.map(|id| id.get() as i64);
+            let policy = db::get_overlap_policy(&data.db, guild_id).await?;
+            let accepted = match challenger {
+                Challenger::Scrim(_) => {
+                    db::accept_pick(&data.db, scrim.id, other.id, policy).await?
+                }
+                Challenger::User(_) if is_open(&scrim) => Some(
+                    db::create_matched_scrim(&data.db, other.clone(), scrim.id, None, policy)
+                        .await?,
+                ),
+                Challenger::User(_) => None,
+            };
+            if let Some(times) = accepted {
+                let mut opponent = String::new();
+                write_scrim_with_name(
+                    &other,
+                    Some(&scrim),
+                    true,
+                    locale,
+                    Utc::now(),
+                    &mut opponent,
+                );
+                let mut content = t!(locale, "challenge-accepted", opponent = opponent);
+                if !times.is_empty() {
+                    let warning = overlap_warning(&times).render(locale);
+                    content = format!("{warning}\n{content}");
+                }
+                (content, Text::new("challenge-accepted-notice"))
+            } else {
+                withdraw(data, challenger, scrim.id).await?;
+                (
+                    t!(locale, "challenge-unavailable"),
+                    Text::new("challenge-unavailable-notice"),
+                )
+            }
+        }
+        Answer::Decline => {
+            withdraw(data, challenger, scrim.id).await?;
+            (
+                t!(locale, "challenge-declined"),
+                Text::new("challenge-declined-notice"),
+            )
+        }
+    };
+
+    let other_locale = i18n::locale_of(&data.db, other.creator_id, None).await;
+    let mut meta = String::new();
+    write_scrim_meta(&scrim, None, other_locale, Utc::now(), &mut meta);
+    let notification = notice
+        .arg("user", scrim.creator_id)
+        .arg("scrim", meta)
+        .render(other_locale);
+    if let Err(e) = send_dm(
+        ctx,
+        other.creator_id,
+        CreateMessage::new().content(notification),
+    )
+    .await
+    {
+        tracing::warn!("Could not notify {} about challenge: {e}", other.creator_id);
+    }
+
+    i.create_response(
+        ctx,
+        CreateInteractionResponse::UpdateMessage(
+            CreateInteractionResponseMessage::new()
+                .content(content)
+                .components(vec![]),
+        ),
+    )
+    .await?;
+    Ok(())
+}
+
+/// Let the open posting of the challenger go back to looking for scrims. Challengers without a
+/// posting have nothing to withdraw.
+async fn withdraw(data: &Data, challenger: Challenger, scrim_id: i32) -> Result<(), Error> {
+    if let Challenger::Scrim(other_id) = challenger {
+        db::withdraw_pick(&data.db, other_id, scrim_id).await?;
+    }
+    Ok(())
+}
+
+fn is_open(scrim: &LookingForScrim) -> bool {
+    !scrim.cancelled && scrim.match_id.is_none() && scrim.time > Utc::now()
+}
+
+async fn get_challenged(
+    data: &Data,
+    challenger_id: i64,
+    scrim_id: i32,
+) -> Result<LookingForScrim, Error> {
+    let scrim = db::get_scrim_in(&data.db, scrim_id).await?;
+    if scrim.creator_id == challenger_id {
+        return Err(Text::new("challenge-self").into());
+    }
+    if !is_open(&scrim) {
+        return Err(Text::new("scrim-unavailable").into());
+    }
+    Ok(scrim)
+}
+
+/// Copy of `scrim` for `challenger_id`, which isn't saved until the challenge is accepted.
+async fn challenger_scrim(
+    data: &Data,
+    challenger_id: i64,
+    scrim: &LookingForScrim,
+) -> Result<LookingForScrim, Error> {
+    Ok(LookingForScrim {
+        id: 0,
+        creator_id: challenger_id,
+        team_name: db::get_latest_team_name_in(&data.db, challenger_id).await?,
+        match_id: None,
+        cancelled: false,
+        checked_in: false,
+        no_show: false,
+        reliability: None,
+        auto_match: false,
+        ..scrim.clone()
+    })
+}
+
+/// Ask the creator of the posting `scrim_id` to accept playing against `challenger_id`. The
+/// posting of the challenger is created once they accept.
+pub async fn challenge(
+    ctx: &serenity::Context,
+    data: &Data,
+    challenger_id: i64,
+    scrim_id: i32,
+) -> Result<Text, Error> {
+    let scrim = get_challenged(data, challenger_id, scrim_id).await?;
+    if !db::has_timezone(&data.db, challenger_id).await? {
+        return Err(Text::new("timezone-missing").into());
+    }
+    let lfs = challenger_scrim(data, challenger_id, &scrim).await?;
+
+    send_challenge(ctx, data, &lfs, Challenger::User(challenger_id), &scrim).await
+}
+
+/// Let the open posting `own_id` of `challenger_id` pick `scrim_id`, and ask its creator to accept.
+pub async fn challenge_with(
+    ctx: &serenity::Context,
+    data: &Data,
+    challenger_id: i64,
+    own_id: i32,
+    scrim_id: i32,
+) -> Result<Text, Error> {
+    let scrim = get_challenged(data, challenger_id, scrim_id).await?;
+    if !db::pick_scrim(&data.db, own_id, challenger_id, scrim.id).await? {
+        return Err(Text::new("challenge-own-unavailable").into());
+    }
+    let own = db::get_scrim_in(&data.db, own_id).await?;
+
+    send_challenge(ctx, data, &own, Challenger::Scrim(own.id), &scrim).await
+}
+
+/// Ask the creator of `scrim` to accept playing against `lfs` of `challenger`. Returns the reply to
+/// the challenger.
+async fn send_challenge(
+    ctx: &serenity::Context,
+    data: &Data,
+    lfs: &LookingForScrim,
+    challenger: Challenger,
+    scrim: &LookingForScrim,
+) -> Result<Text, Error> {
+    let locale = i18n::locale_of(&data.db, scrim.creator_id, None).await;
+    let mut meta = String::new();
+    write_scrim_meta(scrim, Some(lfs), locale, Utc::now(), &mut meta);
+    let content = t!(
+        locale,
+        "challenge-received",
+        user = lfs.creator_id,
+        scrim = meta
+    );
+    let msg = View::new(content)
+        .embed(scrim_card(lfs, locale, Utc::now()))
+        .components(vec![CreateActionRow::Buttons(vec![
+            CreateButton::new(
+                ComponentAction::ChallengeAnswer {
+                    answer: Answer::Accept,
+                    scrim_id: scrim.id,
+                    challenger,
+                }
+                .custom_id()?,
+            )
+            .style(ButtonStyle::Success)
+            .label(t!(locale, "button-accept")),
+            CreateButton::new(
+                ComponentAction::ChallengeAnswer {
+                    answer: Answer::Decline,
+                    scrim_id: scrim.id,
+                    challenger,
+                }
+                .custom_id()?,
+            )
+            .style(ButtonStyle::Danger)
+            .label(t!(locale, "button-decline")),
+        ])])
+        .message();
+    if let Err(e) = send_dm(ctx, scrim.creator_id, msg).await {
+        tracing::warn!("Could not send challenge to {}: {e}", scrim.creator_id);
+        return Ok(Text::new("challenge-dm-failed").arg("user", scrim.creator_id));
+    }
+
+    Ok(Text::new("challenge-posted").arg("user", scrim.creator_id))
+}
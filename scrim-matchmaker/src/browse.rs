@@ -0,0 +1,265 @@
+use std::ops::Range;
+
+use chrono::{DateTime, Utc};
+use poise::serenity_prelude::{
+    self as serenity, ButtonStyle, ComponentInteraction, ComponentInteractionDataKind,
+    CreateActionRow, CreateButton, CreateInteractionResponse, CreateInteractionResponseMessage,
+    CreateSelectMenu, CreateSelectMenuKind, CreateSelectMenuOption,
+};
+
+use crate::{
+    board,
+    components::{BrowseAction, ComponentAction},
+    db,
+    i18n::{self, t, Locale, Text},
+    interactions::report_error,
+    lfs::{parse_rank_range, parse_time, LookingForScrim, Platform, Region},
+    render::{scrim_card, scrim_label, scrim_title, View},
+    Context, Data, Error,
+};
+
+const PAGE_SIZE: i64 = 5;
+
+#[derive(Debug, Default)]
+pub struct BrowseFilter {
+    pub region: Option<Region>,
+    pub platform: Option<Platform>,
+    /// Only show postings whose rank range overlaps this one.
+    pub range: Option<Range<u32>>,
+    pub from: Option<DateTime<Utc>>,
+    pub to: Option<DateTime<Utc>>,
+    pub team_name: Option<String>,
+}
+
+async fn browse_page(
+    db: &sqlx::PgPool,
+    creator_id: i64,
+    filter_id: i32,
+    filter: &BrowseFilter,
+    page: i64,
+    locale: Locale,
+) -> Result<View, Error> {
+    let page = page.max(0);
+    let (mut scrims, mut total) =
+        db::browse_scrims(db, creator_id, filter, page * PAGE_SIZE, PAGE_SIZE).await?;
+    // Pages past the end have no rows to count, e.g. when postings were taken in the meantime
+    if scrims.is_empty() && page > 0 {
+        total = db::browse_scrims(db, creator_id, filter, 0, 1).await?.1;
+    }
+    if total == 0 {
+        return Ok(View::new(t!(locale, "browse-none")));
+    }
+    let pages = (total + PAGE_SIZE - 1) / PAGE_SIZE;
+    let page = page.min(pages - 1);
+    if scrims.is_empty() {
+        scrims = db::browse_scrims(db, creator_id, filter, page * PAGE_SIZE, PAGE_SIZE)
+            .await?
+            .0;
+    }
+    browse_view(&scrims, page, pages, filter_id, locale, Utc::now())
+}
+
+/// Render one page of postings whose buttons refer to the stored filter `filter_id`.
+fn browse_view(
+    scrims: &[LookingForScrim],
+    page: i64,
+    pages: i64,
+    filter_id: i32,
+    locale: Locale,
+    now: DateTime<Utc>,
+) -> Result<View, Error> {
+    let custom_id = |action| ComponentAction::Browse { filter_id, action }.custom_id();
+
+    let mut view = View::new(format!(
+        "## {}",
+        t!(locale, "browse-title", page = page + 1, pages = pages)
+    ));
+    let mut offer_buttons = Vec::new();
+    for (index, scrim) in scrims.iter().enumerate() {
+        let title = scrim_title(scrim, locale);
+        view = view.embed(scrim_card(scrim, locale, now).title(format!("{}. {title}", index + 1)));
+
+        offer_buttons.push(
+            CreateButton::new(custom_id(BrowseAction::Offer(scrim.id))?)
+                .style(ButtonStyle::Primary)
+                .label(t!(locale, "browse-offer", index = index + 1)),
+        );
+    }
+
+    let buttons = vec![
+        CreateButton::new(custom_id(BrowseAction::Page((page - 1).max(0)))?)
+            .style(ButtonStyle::Secondary)
+            .label(t!(locale, "button-previous"))
+            .disabled(page == 0),
+        CreateButton::new(custom_id(BrowseAction::Page(page + 1))?)
+            .style(ButtonStyle::Secondary)
+            .label(t!(locale, "button-next"))
+            .disabled(page + 1 >= pages),
+    ];
+    let mut components = vec![CreateActionRow::Buttons(buttons)];
+    // Discord rejects empty rows
+    if !offer_buttons.is_empty() {
+        components.push(CreateActionRow::Buttons(offer_buttons));
+    }
+    Ok(view.components(components))
+}
+
+/// Ask which of the open postings of `creator_id` to offer to `scrim_id`, or whether to post a new
+/// one.
+async fn offer_select(
+    db: &sqlx::PgPool,
+    creator_id: i64,
+    filter_id: i32,
+    scrim_id: i32,
+    locale: Locale,
+) -> Result<CreateActionRow, Error> {
+    let prefs = db::get_preferences_of(db, creator_id).await?;
+    let mut options: Vec<_> = db::get_future_scrims_in(db, creator_id)
+        .await?
+        .into_iter()
+        .filter(|scrim| scrim.match_id.is_none())
+        .take(24)
+        .map(|scrim| {
+            CreateSelectMenuOption::new(scrim_label(&scrim, prefs, locale), scrim.id.to_string())
+        })
+        .collect();
+    options.push(CreateSelectMenuOption::new(
+        t!(locale, "browse-post-new"),
+        "new",
+    ));
+
+    Ok(CreateActionRow::SelectMenu(
+        CreateSelectMenu::new(
+            ComponentAction::Browse {
+                filter_id,
+                action: BrowseAction::Pick(scrim_id),
+            }
+            .custom_id()?,
+            CreateSelectMenuKind::String { options },
+        )
+        .placeholder(t!(locale, "browse-offer-placeholder")),
+    ))
+}
+
+async fn offer(
+    ctx: &serenity::Context,
+    data: &Data,
+    i: &ComponentInteraction,
+    scrim_id: i32,
+) -> Result<Text, Error> {
+    let ComponentInteractionDataKind::StringSelect { values } = &i.data.kind else {
+        return Err(Text::new("browse-select").into());
+    };
+    let challenger_id = i.user.id.get() as i64;
+    match values.first().map(String::as_str) {
+        Some("new") => board::challenge(ctx, data, challenger_id, scrim_id).await,
+        Some(own_id) => {
+            let own_id = own_id.parse::<i32>()?;
+            board::challenge_with(ctx, data, challenger_id, own_id, scrim_id).await
+        }
+        None => Err(Text::new("browse-select").into()),
+    }
+}
+
+#[poise::command(slash_command, ephemeral)]
+#[tracing::instrument(err, skip(ctx), fields(user = ctx.author().id.get()))]
+pub async fn browse(
+    ctx: Context<'_>,
+    region: Option<Region>,
+    platform: Option<Platform>,
+    rank: Option<String>,
+    from: Option<String>,
+    to: Option<String>,
+    team_name: Option<String>,
+) -> Result<(), Error> {
+    let locale = i18n::locale(ctx).await;
+    let prefs = if from.is_some() || to.is_some() {
+        Some(db::get_preferences(ctx).await?)
+    } else {
+        None
+    };
+    let filter = BrowseFilter {
+        region,
+        platform,
+        range: rank.map(|rank| parse_rank_range(&rank)).transpose()?,
+        from: from
+            .zip(prefs)
+            .map(|(from, prefs)| parse_time(&from, prefs))
+            .transpose()?,
+        to: to
+            .zip(prefs)
+            .map(|(to, prefs)| parse_time(&to, prefs))
+            .transpose()?,
+        team_name,
+    };
+
+    let db = &ctx.data().db;
+    let creator_id = ctx.author().id.get() as i64;
+    let filter_id = db::create_browse_filter(db, creator_id, &filter).await?;
+    ctx.send(
+        browse_page(db, creator_id, filter_id, &filter, 0, locale)
+            .await?
+            .reply(),
+    )
+    .await?;
+    Ok(())
+}
+
+/// Handle the buttons of `/browse`. The filter is loaded from the database, so they keep working
+/// after restarts.
+pub async fn handle_component(
+    ctx: &serenity::Context,
+    data: &Data,
+    i: &ComponentInteraction,
+    filter_id: i32,
+    action: BrowseAction,
+) -> Result<(), Error> {
+    match handle_action(ctx, data, i, filter_id, action).await {
+        Ok(()) => Ok(()),
+        Err(e) => report_error(ctx, data, i, e).await,
+    }
+}
+
+async fn handle_action(
+    ctx: &serenity::Context,
+    data: &Data,
+    i: &ComponentInteraction,
+    filter_id: i32,
+    action: BrowseAction,
+) -> Result<(), Error> {
+    let db = &data.db;
+    let creator_id = i.user.id.get() as i64;
+    let locale = i18n::component_locale(data, i).await;
+
+    let response = match action {
+        BrowseAction::Page(page) => {
+            let filter = db::get_browse_filter(db, filter_id, creator_id)
+                .await?
+                .ok_or(Text::new("button-expired"))?;
+            let view = browse_page(db, creator_id, filter_id, &filter, page, locale).await?;
+            CreateInteractionResponse::UpdateMessage(view.response())
+        }
+        BrowseAction::Offer(scrim_id) => {
+            let select = offer_select(db, creator_id, filter_id, scrim_id, locale).await?;
+            CreateInteractionResponse::Message(
+                CreateInteractionResponseMessage::new()
+                    .content(t!(locale, "browse-offer-question"))
+                    .components(vec![select])
+                    .ephemeral(true),
+            )
+        }
+        BrowseAction::Pick(scrim_id) => {
+            let content = match offer(ctx, data, i, scrim_id).await {
+                Ok(content) => content.render(locale),
+                Err(e) => i18n::error_text(&e, locale),
+            };
+            CreateInteractionResponse::UpdateMessage(
+                CreateInteractionResponseMessage::new()
+                    .content(content)
+                    .components(vec![]),
+            )
+        }
+    };
+    i.create_response(ctx, response).await?;
+    Ok(())
+}
@@ -0,0 +1,542 @@
+use std::{net::SocketAddr, str::FromStr};
+
+use axum::{
+    extract::{Path, State},
+    http::{header, StatusCode},
+    response::{IntoResponse, Response},
+    routing::get,
+    Router,
+};
+use chrono::{DateTime, NaiveDateTime, Utc};
+use poise::{serenity_prelude::CreateAttachment, CreateReply};
+use tokio::net::TcpListener;
+use tower::limit::GlobalConcurrencyLimitLayer;
+use tower_http::timeout::TimeoutLayer;
+
+use crate::{
+    db,
+    i18n::{self, t, Locale},
+    lfs::{check_team_name, LookingForScrim},
+    results::SCRIM_DURATION,
+    Context, Error,
+};
+
+/// How long answering a feed request may take before it fails.
+const REQUEST_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(10);
+/// Feed requests answered at once, later ones wait for a slot.
+const MAX_FEED_REQUESTS: usize = 16;
+
+/// Whose scrims a calendar shows.
+#[derive(Debug, Clone)]
+pub struct CalendarOwner {
+    pub creator_id: i64,
+    /// Only show the scrims the creator posted with this team name.
+    pub team_name: Option<String>,
+}
+
+/// Escape text for a property value.
+fn escape(text: &str) -> String {
+    text.replace('\\', "\\\\")
+        .replace(';', "\\;")
+        .replace(',', "\\,")
+        .replace('\n', "\\n")
+}
+
+/// Fold a content line so no line is longer than 75 bytes, without splitting characters.
+fn fold(line: &str, out: &mut String) {
+    let mut len = 0;
+    for c in line.chars() {
+        if len + c.len_utf8() > 75 {
+            out.push_str("\r\n ");
+            len = 1;
+        }
+        out.push(c);
+        len += c.len_utf8();
+    }
+    out.push_str("\r\n");
+}
+
+/// Undo [`escape`].
+fn unescape(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut chars = text.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            out.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('n' | 'N') => out.push('\n'),
+            Some(c) => out.push(c),
+            None => {}
+        }
+    }
+    out
+}
+
+/// Content line like `DTSTART;TZID=Europe/Berlin:20240705T200000`.
+struct Property<'a> {
+    /// Name in uppercase.
+    name: String,
+    /// Timezone of a time, the only parameter that is needed.
+    tzid: Option<String>,
+    value: &'a str,
+}
+
+impl<'a> Property<'a> {
+    /// Split a content line. Parameter values may be quoted and contain `:`.
+    fn parse(line: &'a str) -> Option<Self> {
+        let mut quoted = false;
+        let colon = line.char_indices().find_map(|(i, c)| match c {
+            '"' => {
+                quoted = !quoted;
+                None
+            }
+            ':' if !quoted => Some(i),
+            _ => None,
+        })?;
+        let mut parts = line[..colon].split(';');
+        let name = parts.next()?.to_ascii_uppercase();
+        let tzid = parts
+            .filter_map(|param| param.split_once('='))
+            .find(|(key, _)| key.eq_ignore_ascii_case("TZID"))
+            .map(|(_, tzid)| tzid.trim_matches('"').to_owned());
+        Some(Self {
+            name,
+            tzid,
+            value: &line[colon + 1..],
+        })
+    }
+
+    /// Read a time. Dates without a time, like the start of all-day events, are `None`.
+    fn time(&self, zone: chrono_tz::Tz) -> Option<DateTime<Utc>> {
+        if let Some(utc) = self.value.strip_suffix('Z') {
+            return Some(
+                NaiveDateTime::parse_from_str(utc, "%Y%m%dT%H%M%S")
+                    .ok()?
+                    .and_utc(),
+            );
+        }
+        let local = NaiveDateTime::parse_from_str(self.value, "%Y%m%dT%H%M%S").ok()?;
+        // Zones unknown to the tz database, like the Windows names of Outlook, fall back to `zone`
+        let zone = self
+            .tzid
+            .as_deref()
+            .and_then(|tzid| chrono_tz::Tz::from_str(tzid).ok())
+            .unwrap_or(zone);
+        local
+            .and_local_timezone(zone)
+            .earliest()
+            .map(|time| time.with_timezone(&Utc))
+    }
+}
+
+/// Event read from an iCalendar file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CalendarEvent {
+    pub start: DateTime<Utc>,
+    pub summary: Option<String>,
+    pub categories: Vec<String>,
+}
+
+/// Event whose properties are still being read.
+#[derive(Default)]
+struct PartialEvent {
+    start: Option<DateTime<Utc>>,
+    summary: Option<String>,
+    categories: Vec<String>,
+    cancelled: bool,
+}
+
+/// Read the events of an iCalendar file. Times without a timezone are read in `zone`. All-day and
+/// cancelled events are skipped, and recurring events only count once at their first start.
+pub fn parse_events(source: &str, zone: chrono_tz::Tz) -> Vec<CalendarEvent> {
+    // Unfold continuation lines first
+    let mut lines: Vec<String> = Vec::new();
+    for line in source.lines() {
+        match line.strip_prefix([' ', '\t']) {
+            Some(rest) if !lines.is_empty() => lines.last_mut().unwrap().push_str(rest),
+            _ => lines.push(line.to_owned()),
+        }
+    }
+
+    let mut events = Vec::new();
+    let mut event: Option<PartialEvent> = None;
+    for line in &lines {
+        let Some(property) = Property::parse(line) else {
+            continue;
+        };
+        let value = property.value;
+        match (property.name.as_str(), &mut event) {
+            ("BEGIN", None) if value.eq_ignore_ascii_case("VEVENT") => {
+                event = Some(PartialEvent::default());
+            }
+            ("END", Some(_)) if value.eq_ignore_ascii_case("VEVENT") => {
+                let Some(PartialEvent {
+                    start: Some(start),
+                    summary,
+                    categories,
+                    cancelled: false,
+                }) = event.take()
+                else {
+                    continue;
+                };
+                events.push(CalendarEvent {
+                    start,
+                    summary,
+                    categories,
+                });
+            }
+            ("DTSTART", Some(event)) => event.start = property.time(zone),
+            ("SUMMARY", Some(event)) => event.summary = Some(unescape(value)),
+            ("CATEGORIES", Some(event)) => event.categories.extend(
+                // Commas inside a category are escaped
+                value
+                    .replace("\\,", "\u{0}")
+                    .split(',')
+                    .map(|category| unescape(&category.replace('\u{0}', ",")).trim().to_owned()),
+            ),
+            ("STATUS", Some(event)) => event.cancelled = value.eq_ignore_ascii_case("CANCELLED"),
+            _ => {}
+        }
+    }
+    events
+}
+
+fn ics_time(time: DateTime<Utc>) -> String {
+    time.format("%Y%m%dT%H%M%SZ").to_string()
+}
+
+/// Render scrims with their opponents, if matched, as an iCalendar file.
+fn ics(
+    name: &str,
+    scrims: &[(LookingForScrim, Option<LookingForScrim>)],
+    locale: Locale,
+    now: DateTime<Utc>,
+) -> String {
+    let mut lines = vec![
+        "BEGIN:VCALENDAR".to_owned(),
+        "VERSION:2.0".to_owned(),
+        "PRODID:-//scrim-matchmaker//EN".to_owned(),
+        "CALSCALE:GREGORIAN".to_owned(),
+        format!("X-WR-CALNAME:{}", escape(name)),
+    ];
+    for (scrim, opponent) in scrims {
+        let (summary, status) = match opponent {
+            Some(opponent) => (
+                t!(
+                    locale,
+                    "calendar-matched",
+                    opponent = opponent
+                        .team_name
+                        .clone()
+                        .unwrap_or_else(|| t!(locale, "unnamed-team"))
+                ),
+                "CONFIRMED",
+            ),
+            None => (t!(locale, "calendar-open"), "TENTATIVE"),
+        };
+        let mut description = vec![
+            format!("{}: {:?}", t!(locale, "card-region"), scrim.region),
+            format!("{}: {:?}", t!(locale, "card-platform"), scrim.platform),
+            format!("{}: {}", t!(locale, "card-rank"), scrim.range),
+        ];
+        if let Some(team_name) = &scrim.team_name {
+            description.push(format!("{}: {team_name}", t!(locale, "card-team")));
+        }
+        description.push(format!(
+            "{}: {}",
+            t!(locale, "card-status"),
+            match opponent {
+                Some(_) => t!(locale, "status-matched"),
+                None => t!(locale, "status-looking"),
+            }
+        ));
+
+        lines.extend([
+            "BEGIN:VEVENT".to_owned(),
+            format!("UID:scrim-{}@scrim-matchmaker", scrim.id),
+            format!("DTSTAMP:{}", ics_time(now)),
+            format!("DTSTART:{}", ics_time(scrim.time)),
+            format!(
+                "DTEND:{}",
+                ics_time(scrim.time + chrono::Duration::minutes(SCRIM_DURATION.into()))
+            ),
+            format!("SUMMARY:{}", escape(&summary)),
+            format!("DESCRIPTION:{}", escape(&description.join("\n"))),
+            format!("STATUS:{status}"),
+            "END:VEVENT".to_owned(),
+        ]);
+    }
+    lines.push("END:VCALENDAR".to_owned());
+
+    let mut out = String::new();
+    for line in &lines {
+        fold(line, &mut out);
+    }
+    out
+}
+
+/// Calendar of the upcoming scrims of `owner`.
+async fn calendar_of(
+    db: &sqlx::PgPool,
+    owner: &CalendarOwner,
+    locale: Locale,
+) -> Result<(usize, String), Error> {
+    let scrims = db::get_calendar_scrims(db, owner, SCRIM_DURATION).await?;
+    let name = match &owner.team_name {
+        Some(team_name) => team_name.clone(),
+        None => t!(locale, "calendar-name"),
+    };
+    Ok((scrims.len(), ics(&name, &scrims, locale, Utc::now())))
+}
+
+#[poise::command(slash_command, subcommands("export", "feed"), subcommand_required)]
+pub async fn calendar(_ctx: Context<'_>) -> Result<(), Error> {
+    Ok(())
+}
+
+#[poise::command(slash_command, ephemeral)]
+#[tracing::instrument(err, skip(ctx), fields(user = ctx.author().id.get()))]
+pub async fn export(ctx: Context<'_>, team_name: Option<String>) -> Result<(), Error> {
+    let locale = i18n::locale(ctx).await;
+    check_team_name(team_name.as_deref())?;
+    let owner = CalendarOwner {
+        creator_id: ctx.author().id.get() as i64,
+        team_name,
+    };
+    let (count, calendar) = calendar_of(&ctx.data().db, &owner, locale).await?;
+    if count == 0 {
+        ctx.reply(t!(locale, "calendar-empty")).await?;
+        return Ok(());
+    }
+
+    ctx.send(
+        CreateReply::default()
+            .content(t!(locale, "calendar-exported", count = count))
+            .attachment(CreateAttachment::bytes(calendar.into_bytes(), "scrims.ics")),
+    )
+    .await?;
+    Ok(())
+}
+
+#[poise::command(slash_command, ephemeral)]
+#[tracing::instrument(err, skip(ctx), fields(user = ctx.author().id.get()))]
+pub async fn feed(
+    ctx: Context<'_>,
+    team_name: Option<String>,
+    reset: Option<bool>,
+) -> Result<(), Error> {
+    let locale = i18n::locale(ctx).await;
+    let Some(feed_url) = &ctx.data().feed_url else {
+        ctx.reply(t!(locale, "calendar-feed-disabled")).await?;
+        return Ok(());
+    };
+    check_team_name(team_name.as_deref())?;
+    let owner = CalendarOwner {
+        creator_id: ctx.author().id.get() as i64,
+        team_name,
+    };
+    let token = db::get_feed_token(&ctx.data().db, &owner, reset.unwrap_or(false)).await?;
+
+    ctx.reply(t!(
+        locale,
+        "calendar-feed",
+        url = format!("{}/calendar/{token}.ics", feed_url.trim_end_matches('/'))
+    ))
+    .await?;
+    Ok(())
+}
+
+/// Serve the calendar feeds over HTTP at `/calendar/<token>.ics`.
+pub async fn serve(addr: SocketAddr, db: sqlx::PgPool) {
+    let listener = match TcpListener::bind(addr).await {
+        Ok(listener) => listener,
+        Err(e) => {
+            tracing::error!("Could not serve calendar feeds on {addr}: {e}");
+            return;
+        }
+    };
+    let app = Router::new()
+        .route("/calendar/:file", get(feed_file))
+        .layer(TimeoutLayer::new(REQUEST_TIMEOUT))
+        .layer(GlobalConcurrencyLimitLayer::new(MAX_FEED_REQUESTS))
+        .with_state(db);
+    if let Err(e) = axum::serve(listener, app).await {
+        tracing::error!("Calendar feed server stopped: {e}");
+    }
+}
+
+/// Answer a request for the feed `<token>.ics`.
+async fn feed_file(State(db): State<sqlx::PgPool>, Path(file): Path<String>) -> Response {
+    let Some(token) = file.strip_suffix(".ics") else {
+        return StatusCode::NOT_FOUND.into_response();
+    };
+    let owner = match db::get_feed_owner(&db, token).await {
+        Ok(Some(owner)) => owner,
+        Ok(None) => return StatusCode::NOT_FOUND.into_response(),
+        Err(e) => {
+            tracing::error!("Could not look up calendar feed: {e}");
+            return StatusCode::INTERNAL_SERVER_ERROR.into_response();
+        }
+    };
+    let locale = i18n::locale_of(&db, owner.creator_id, None).await;
+    match calendar_of(&db, &owner, locale).await {
+        Ok((_, calendar)) => (
+            [(header::CONTENT_TYPE, "text/calendar; charset=utf-8")],
+            calendar,
+        )
+            .into_response(),
+        Err(e) => {
+            tracing::error!("Could not build calendar feed: {e}");
+            StatusCode::INTERNAL_SERVER_ERROR.into_response()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::TimeZone;
+
+    use super::*;
+    use crate::lfs::{Platform, RankRange, Region};
+
+    fn scrim(id: i32, team_name: Option<&str>) -> LookingForScrim {
+        LookingForScrim {
+            id,
+            creator_id: 10,
+            team_name: team_name.map(str::to_owned),
+            region: Region::EU,
+            platform: Platform::PC,
+            range: RankRange(4000..4500),
+            time: Utc.with_ymd_and_hms(2024, 7, 5, 18, 0, 0).unwrap(),
+            match_id: None,
+            cancelled: false,
+            checked_in: false,
+            no_show: false,
+            reliability: None,
+            auto_match: false,
+        }
+    }
+
+    #[test]
+    fn calendar_lists_scrims() {
+        let now = Utc.with_ymd_and_hms(2024, 7, 1, 12, 0, 0).unwrap();
+        let scrims = [
+            (
+                scrim(1, Some("Alpha")),
+                Some(scrim(2, Some("Beta, the second"))),
+            ),
+            (scrim(3, None), None),
+        ];
+        assert_eq!(
+            ics("Alpha", &scrims, Locale::En, now),
+            "BEGIN:VCALENDAR\r\n\
+            VERSION:2.0\r\n\
+            PRODID:-//scrim-matchmaker//EN\r\n\
+            CALSCALE:GREGORIAN\r\n\
+            X-WR-CALNAME:Alpha\r\n\
+            BEGIN:VEVENT\r\n\
+            UID:scrim-1@scrim-matchmaker\r\n\
+            DTSTAMP:20240701T120000Z\r\n\
+            DTSTART:20240705T180000Z\r\n\
+            DTEND:20240705T200000Z\r\n\
+            SUMMARY:Scrim vs Beta\\, the second\r\n\
+            DESCRIPTION:Region: EU\\nPlatform: PC\\nRank: 4k-4.5k\\nTeam: Alpha\\nStatus: M\r\n \
+            atched\r\n\
+            STATUS:CONFIRMED\r\n\
+            END:VEVENT\r\n\
+            BEGIN:VEVENT\r\n\
+            UID:scrim-3@scrim-matchmaker\r\n\
+            DTSTAMP:20240701T120000Z\r\n\
+            DTSTART:20240705T180000Z\r\n\
+            DTEND:20240705T200000Z\r\n\
+            SUMMARY:Scrim (looking for an opponent)\r\n\
+            DESCRIPTION:Region: EU\\nPlatform: PC\\nRank: 4k-4.5k\\nStatus: Looking\r\n\
+            STATUS:TENTATIVE\r\n\
+            END:VEVENT\r\n\
+            END:VCALENDAR\r\n"
+        );
+    }
+
+    #[test]
+    fn events_are_read() {
+        let source = "BEGIN:VCALENDAR\r\n\
+            BEGIN:VTIMEZONE\r\n\
+            TZID:Europe/Berlin\r\n\
+            END:VTIMEZONE\r\n\
+            BEGIN:VEVENT\r\n\
+            DTSTART;TZID=Europe/Berlin:20240705T200000\r\n\
+            SUMMARY:Scrims\\, maybe\r\n\
+            CATEGORIES:Scrim,Team\\, main\r\n\
+            END:VEVENT\r\n\
+            BEGIN:VEVENT\r\n\
+            DTSTART:20240706T180000Z\r\n\
+            DESCRIPTION;ALTREP=\"https://example.com\":Folded\r\n \
+            description\r\n\
+            END:VEVENT\r\n\
+            BEGIN:VEVENT\r\n\
+            DTSTART:20240707T203000\r\n\
+            END:VEVENT\r\n\
+            BEGIN:VEVENT\r\n\
+            DTSTART;VALUE=DATE:20240708\r\n\
+            SUMMARY:Holiday\r\n\
+            END:VEVENT\r\n\
+            BEGIN:VEVENT\r\n\
+            DTSTART:20240709T180000Z\r\n\
+            STATUS:CANCELLED\r\n\
+            END:VEVENT\r\n\
+            END:VCALENDAR\r\n";
+        let events = parse_events(source, chrono_tz::America::New_York);
+        assert_eq!(
+            events,
+            [
+                CalendarEvent {
+                    start: Utc.with_ymd_and_hms(2024, 7, 5, 18, 0, 0).unwrap(),
+                    summary: Some("Scrims, maybe".to_owned()),
+                    categories: vec!["Scrim".to_owned(), "Team, main".to_owned()],
+                },
+                CalendarEvent {
+                    start: Utc.with_ymd_and_hms(2024, 7, 6, 18, 0, 0).unwrap(),
+                    summary: None,
+                    categories: Vec::new(),
+                },
+                CalendarEvent {
+                    start: Utc.with_ymd_and_hms(2024, 7, 8, 0, 30, 0).unwrap(),
+                    summary: None,
+                    categories: Vec::new(),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn exported_calendars_can_be_read() {
+        let now = Utc.with_ymd_and_hms(2024, 7, 1, 12, 0, 0).unwrap();
+        let mut named = scrim(1, None);
+        named.team_name = Some("A; very, long \\ team name that needs folding".repeat(2));
+        let scrims = [(scrim(2, None), Some(named))];
+        let events = parse_events(&ics("Alpha", &scrims, Locale::En, now), chrono_tz::UTC);
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].start, scrims[0].0.time);
+        assert_eq!(
+            events[0].summary.as_deref(),
+            Some(
+                format!(
+                    "Scrim vs {}",
+                    scrims[0].1.as_ref().unwrap().team_name.as_ref().unwrap()
+                )
+                .as_str()
+            )
+        );
+    }
+
+    #[test]
+    fn long_lines_are_folded_between_characters() {
+        let mut out = String::new();
+        fold(&format!("SUMMARY:{}", "é".repeat(40)), &mut out);
+        let lines: Vec<_> = out.split("\r\n").collect();
+        assert!(lines.iter().all(|line| line.len() <= 75));
+        assert_eq!(lines[1], format!(" {}", "é".repeat(7)));
+    }
+}
@@ -1,121 +1,441 @@
-use std::{time::Duration, vec};
-
-use poise::{
-    serenity_prelude::{
-        self as serenity, futures::StreamExt, ButtonStyle, ComponentInteractionDataKind,
-        CreateActionRow, CreateButton, CreateInteractionResponseMessage, CreateSelectMenu,
-        CreateSelectMenuKind, CreateSelectMenuOption,
-    },
-    CreateReply,
+use std::{collections::HashSet, str::FromStr};
+
+use chrono::{DateTime, Utc};
+use poise::serenity_prelude::{
+    self as serenity, ActionRowComponent, ButtonStyle, ComponentInteraction,
+    ComponentInteractionDataKind, CreateActionRow, CreateButton, CreateEmbed, CreateEmbedFooter,
+    CreateMessage, CreateSelectMenu, CreateSelectMenuKind, CreateSelectMenuOption,
+};
+
+use crate::{
+    components::{CancelAction, ComponentAction},
+    db,
+    i18n::{self, t, Locale, Text},
+    interactions::report_error,
+    lfs::{check_team_name, parse_date, LookingForScrim},
+    preferences::Preferences,
+    reminders::send_dm,
+    render::{scrim_card_with_status, scrim_label, write_scrim_meta, CardStatus, View},
+    Context, Data, Error,
 };
 
-use crate::{db, Context, Error};
+/// How many scrims are listed per page. Discord allows at most 25 options per menu.
+const PAGE_SIZE: usize = 25;
+/// How many scrims are shown as cards. Discord allows at most 10 embeds per message.
+const CARDS: usize = 10;
 
-#[poise::command(
-    slash_command,
-    description_localized(
-        "en-US",
-        "Cancel multiple scrims. This removes them from the matchmaker."
-    ),
-    ephemeral
-)]
-#[tracing::instrument(err, skip(ctx), fields(user = ctx.author().id.get()))]
-pub async fn cancel(ctx: Context<'_>) -> Result<(), Error> {
-    let scrims = db::get_future_scrims(ctx).await?;
-    let tz = db::get_timezone(ctx).await?;
+/// Filter of the scrims listed by `/cancel`. It is part of the custom ids of the components.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct CancelFilter {
+    /// Start of the day to list scrims on.
+    pub day: Option<DateTime<Utc>>,
+    pub team_name: Option<String>,
+}
+
+impl CancelFilter {
+    fn matches(&self, scrim: &LookingForScrim) -> bool {
+        self.day
+            .is_none_or(|day| scrim.time >= day && scrim.time < day + chrono::Duration::days(1))
+            && self.team_name.as_ref().is_none_or(|team_name| {
+                scrim
+                    .team_name
+                    .as_ref()
+                    .is_some_and(|name| name.eq_ignore_ascii_case(team_name))
+            })
+    }
+}
+
+/// Get the upcoming scrims of `creator_id` matching `filter`, soonest first.
+async fn get_scrims(
+    db: &sqlx::PgPool,
+    creator_id: i64,
+    filter: &CancelFilter,
+) -> Result<Vec<LookingForScrim>, Error> {
+    let mut scrims = db::get_future_scrims_in(db, creator_id).await?;
+    scrims.retain(|scrim| filter.matches(scrim));
+    scrims.sort_by_key(|scrim| scrim.time);
+    Ok(scrims)
+}
+
+fn cancel_page(
+    scrims: &[LookingForScrim],
+    selected: &HashSet<i32>,
+    page: usize,
+    filter: &CancelFilter,
+    prefs: Preferences,
+    locale: Locale,
+) -> Result<View, Error> {
+    let pages = scrims.len().div_ceil(PAGE_SIZE);
+    let page = page.min(pages - 1);
+    let shown = &scrims[page * PAGE_SIZE..scrims.len().min((page + 1) * PAGE_SIZE)];
+    let id = |action, page| {
+        ComponentAction::Cancel {
+            action,
+            page,
+            filter: filter.clone(),
+        }
+        .custom_id()
+    };
 
     let cancel_select = CreateSelectMenu::new(
-        "select",
+        id(CancelAction::Select, page)?,
         CreateSelectMenuKind::String {
-            options: scrims
+            options: shown
                 .iter()
-                .enumerate()
-                .map(|(i, scrim)| {
-                    use std::fmt::Write;
-                    let mut info = String::new();
-                    if let Some(team_name) = &scrim.team_name {
-                        write!(&mut info, "{}: ", team_name).unwrap();
-                    }
-                    write!(
-                        &mut info,
-                        "{:?}/{:?} {} on {}",
-                        scrim.region,
-                        scrim.platform,
-                        scrim.range,
-                        scrim.time.with_timezone(&tz).format("%A, %B %d, %H:%M %Z")
+                .map(|scrim| {
+                    CreateSelectMenuOption::new(
+                        scrim_label(scrim, prefs, locale),
+                        scrim.id.to_string(),
                     )
-                    .unwrap();
-                    CreateSelectMenuOption::new(info, i.to_string())
+                    .default_selection(selected.contains(&scrim.id))
                 })
                 .collect(),
         },
     )
-    .max_values(scrims.len() as u8)
+    .max_values(shown.len() as u8)
     .min_values(0);
+    let selected: Vec<_> = shown
+        .iter()
+        .filter(|scrim| selected.contains(&scrim.id))
+        .collect();
+
+    // The selection is only kept in the menu of the shown page, so it can't span pages
+    let mut buttons = Vec::new();
+    if pages > 1 {
+        buttons.push(
+            CreateButton::new(id(CancelAction::Prev, page.saturating_sub(1))?)
+                .style(ButtonStyle::Secondary)
+                .label(t!(locale, "button-previous"))
+                .disabled(page == 0 || !selected.is_empty()),
+        );
+        buttons.push(
+            CreateButton::new(id(CancelAction::Next, page + 1)?)
+                .style(ButtonStyle::Secondary)
+                .label(t!(locale, "button-next"))
+                .disabled(page + 1 >= pages || !selected.is_empty()),
+        );
+    }
+    buttons.push(
+        CreateButton::new(id(CancelAction::Confirm, page)?)
+            .style(ButtonStyle::Danger)
+            .label(t!(locale, "cancel-selected", count = selected.len())),
+    );
+    buttons.push(
+        CreateButton::new(id(CancelAction::All, page)?)
+            .style(ButtonStyle::Danger)
+            .label(t!(locale, "cancel-all", count = scrims.len())),
+    );
+    let unmatched = scrims
+        .iter()
+        .filter(|scrim| scrim.match_id.is_none())
+        .count();
+    if unmatched > 0 && unmatched < scrims.len() {
+        buttons.push(
+            CreateButton::new(id(CancelAction::Unmatched, page)?)
+                .style(ButtonStyle::Danger)
+                .label(t!(locale, "cancel-unmatched", count = unmatched)),
+        );
+    }
+
+    let content = if pages > 1 {
+        t!(locale, "cancel-select-page", page = page + 1, pages = pages)
+    } else {
+        t!(locale, "cancel-select")
+    };
+    Ok(View {
+        content,
+        embeds: cards(&selected, CardStatus::of, locale),
+        components: vec![
+            CreateActionRow::SelectMenu(cancel_select),
+            CreateActionRow::Buttons(buttons),
+        ],
+    })
+}
 
-    let msg_handle = ctx
-        .send(
-            CreateReply::default()
-                .content("Select the scrims you want to cancel:")
-                .components(vec![
-                    CreateActionRow::SelectMenu(cancel_select),
-                    CreateActionRow::Buttons(vec![CreateButton::new("confirm")
-                        .style(ButtonStyle::Danger)
-                        .label("Confirm")]),
-                ]),
+/// Ask to confirm cancelling `scrims` at once. `action` cancels them and `page` is shown when going
+/// back.
+fn confirm_page(
+    scrims: &[&LookingForScrim],
+    action: CancelAction,
+    page: usize,
+    filter: &CancelFilter,
+    locale: Locale,
+) -> Result<View, Error> {
+    let id = |action| {
+        ComponentAction::Cancel {
+            action,
+            page,
+            filter: filter.clone(),
+        }
+        .custom_id()
+    };
+    let (content, label) = if matches!(action, CancelAction::AllConfirmed { .. }) {
+        (
+            t!(locale, "cancel-confirm-all", count = scrims.len()),
+            t!(locale, "cancel-all", count = scrims.len()),
         )
+    } else {
+        (
+            t!(locale, "cancel-confirm-unmatched", count = scrims.len()),
+            t!(locale, "cancel-unmatched", count = scrims.len()),
+        )
+    };
+    Ok(View {
+        content,
+        embeds: cards(scrims, CardStatus::of, locale),
+        components: vec![CreateActionRow::Buttons(vec![
+            CreateButton::new(id(action)?)
+                .style(ButtonStyle::Danger)
+                .label(label),
+            CreateButton::new(id(CancelAction::Back)?)
+                .style(ButtonStyle::Secondary)
+                .label(t!(locale, "cancel-back")),
+        ])],
+    })
+}
+
+#[poise::command(slash_command, ephemeral)]
+#[tracing::instrument(err, skip(ctx), fields(user = ctx.author().id.get()))]
+pub async fn cancel(
+    ctx: Context<'_>,
+    date: Option<String>,
+    team_name: Option<String>,
+) -> Result<(), Error> {
+    let locale = i18n::locale(ctx).await;
+    check_team_name(team_name.as_deref())?;
+    let prefs = db::get_preferences(ctx).await?;
+    let filter = CancelFilter {
+        day: date.map(|date| parse_date(&date, prefs)).transpose()?,
+        team_name,
+    };
+
+    let scrims = get_scrims(&ctx.data().db, ctx.author().id.get() as i64, &filter).await?;
+    if scrims.is_empty() {
+        ctx.reply(if filter.day.is_some() || filter.team_name.is_some() {
+            t!(locale, "cancel-none-matching")
+        } else {
+            t!(locale, "cancel-none")
+        })
         .await?;
+        return Ok(());
+    }
+
+    ctx.send(cancel_page(&scrims, &HashSet::new(), 0, &filter, prefs, locale)?.reply())
+        .await?;
+    Ok(())
+}
 
-    let mut listener = msg_handle
-        .message()
-        .await?
-        .await_component_interaction(ctx)
-        .timeout(Duration::from_secs(30 * 60))
-        .stream();
-
-    let mut to_cancel = Vec::new();
-
-    while let Some(i) = listener.next().await {
-        match &i.data.kind {
-            ComponentInteractionDataKind::StringSelect { values } => {
-                to_cancel.clone_from(values);
-                i.create_response(ctx, serenity::CreateInteractionResponse::Acknowledge)
-                    .await?;
+/// Cards of the first scrims, noting on the last one how many more there are.
+fn cards(
+    scrims: &[&LookingForScrim],
+    status: impl Fn(&LookingForScrim) -> CardStatus,
+    locale: Locale,
+) -> Vec<CreateEmbed> {
+    scrims
+        .iter()
+        .take(CARDS)
+        .enumerate()
+        .map(|(index, scrim)| {
+            let card = scrim_card_with_status(scrim, status(scrim), locale, Utc::now());
+            if index + 1 == CARDS && scrims.len() > CARDS {
+                card.footer(CreateEmbedFooter::new(t!(
+                    locale,
+                    "cancel-more",
+                    count = scrims.len() - CARDS
+                )))
+            } else {
+                card
             }
-            ComponentInteractionDataKind::Button if i.data.custom_id == "confirm" => {
-                if to_cancel.is_empty() {
-                    i.create_response(
-                        ctx,
-                        serenity::CreateInteractionResponse::UpdateMessage(
-                            CreateInteractionResponseMessage::new()
-                                .content("No scrims selected.")
-                                .components(vec![]),
-                        ),
-                    )
-                    .await?;
-                    return Ok(()); // Aborted
-                } else {
-                    for i in to_cancel {
-                        let i = i.parse::<usize>().unwrap();
-                        db::cancel_scrim(ctx, scrims[i].id).await?;
-                    }
-                    i.create_response(
-                        ctx,
-                        serenity::CreateInteractionResponse::UpdateMessage(
-                            CreateInteractionResponseMessage::new()
-                                .content("Scrim(s) cancelled.")
-                                .components(vec![]),
-                        ),
-                    )
-                    .await?;
-                }
-                return Ok(()); // Confirmed
+        })
+        .collect()
+}
+
+/// Values selected in the menu of a message, e.g. the scrims of a `/cancel` message.
+pub fn selected_in<T: FromStr, C: FromIterator<T>>(message: &serenity::Message) -> C {
+    message
+        .components
+        .iter()
+        .flat_map(|row| &row.components)
+        .filter_map(|component| match component {
+            ActionRowComponent::SelectMenu(menu) => Some(menu),
+            _ => None,
+        })
+        .flat_map(|menu| &menu.options)
+        .filter(|option| option.default)
+        .filter_map(|option| option.value.parse().ok())
+        .collect()
+}
+
+/// Tell the partner of the matched `scrim` that it was cancelled.
+async fn notify_partner(
+    ctx: &serenity::Context,
+    db: &sqlx::PgPool,
+    scrim: &LookingForScrim,
+) -> Result<(), Error> {
+    let Some(other_id) = scrim.match_id else {
+        return Ok(());
+    };
+    let other = db::get_scrim_in(db, other_id).await?;
+    if other.match_id != Some(scrim.id) {
+        return Ok(()); // Only picked, not matched
+    }
+    let locale = i18n::locale_of(db, other.creator_id, None).await;
+    let mut meta = String::new();
+    write_scrim_meta(&other, None, locale, Utc::now(), &mut meta);
+    let content = t!(
+        locale,
+        "cancel-partner-notice",
+        user = scrim.creator_id,
+        scrim = meta
+    );
+    if let Err(e) = send_dm(ctx, other.creator_id, CreateMessage::new().content(content)).await {
+        tracing::warn!(
+            "Could not notify {} about cancelled scrim: {e}",
+            other.creator_id
+        );
+    }
+    Ok(())
+}
+
+/// Handle the components of `/cancel`. The filter and page are part of the custom ids and the
+/// selection is kept in the message, so they keep working after restarts.
+pub async fn handle_component(
+    ctx: &serenity::Context,
+    data: &Data,
+    i: &ComponentInteraction,
+    action: CancelAction,
+    page: usize,
+    filter: CancelFilter,
+) -> Result<(), Error> {
+    match handle_action(ctx, data, i, action, page, filter).await {
+        Ok(()) => Ok(()),
+        Err(e) => report_error(ctx, data, i, e).await,
+    }
+}
+
+async fn handle_action(
+    ctx: &serenity::Context,
+    data: &Data,
+    i: &ComponentInteraction,
+    action: CancelAction,
+    page: usize,
+    filter: CancelFilter,
+) -> Result<(), Error> {
+    let user_id = i.user.id.get() as i64;
+    let locale = i18n::component_locale(data, i).await;
+    let scrims = get_scrims(&data.db, user_id, &filter).await?;
+
+    let (selected, to_cancel): (HashSet<i32>, Vec<i32>) = match (&i.data.kind, action) {
+        (ComponentInteractionDataKind::StringSelect { values }, CancelAction::Select) => (
+            values
+                .iter()
+                .filter_map(|value| value.parse().ok())
+                .collect(),
+            Vec::new(),
+        ),
+        (
+            ComponentInteractionDataKind::Button,
+            CancelAction::Prev | CancelAction::Next | CancelAction::Back,
+        ) => (HashSet::new(), Vec::new()),
+        (ComponentInteractionDataKind::Button, CancelAction::Confirm) => {
+            let selected: HashSet<i32> = selected_in(&i.message);
+            let to_cancel = scrims
+                .iter()
+                .filter(|scrim| selected.contains(&scrim.id))
+                .map(|scrim| scrim.id)
+                .collect::<Vec<_>>();
+            if to_cancel.is_empty() {
+                i.create_response(
+                    ctx,
+                    serenity::CreateInteractionResponse::UpdateMessage(
+                        View::new(t!(locale, "cancel-nothing-selected")).response(),
+                    ),
+                )
+                .await?;
+                return Ok(()); // Aborted
             }
-            _ => continue,
+            (selected, to_cancel)
         }
-    }
+        (ComponentInteractionDataKind::Button, CancelAction::All | CancelAction::Unmatched) => {
+            let listed: Vec<_> = scrims
+                .iter()
+                .filter(|scrim| action == CancelAction::All || scrim.match_id.is_none())
+                .collect();
+            // Postings made after the confirmation was shown aren't cancelled with the listed ones
+            let view = match listed.iter().map(|scrim| scrim.id).max() {
+                Some(newest) if action == CancelAction::All => confirm_page(
+                    &listed,
+                    CancelAction::AllConfirmed { newest },
+                    page,
+                    &filter,
+                    locale,
+                )?,
+                Some(newest) => confirm_page(
+                    &listed,
+                    CancelAction::UnmatchedConfirmed { newest },
+                    page,
+                    &filter,
+                    locale,
+                )?,
+                None => View::new(t!(locale, "cancel-none-left")),
+            };
+            i.create_response(
+                ctx,
+                serenity::CreateInteractionResponse::UpdateMessage(view.response()),
+            )
+            .await?;
+            return Ok(());
+        }
+        (ComponentInteractionDataKind::Button, CancelAction::AllConfirmed { newest }) => (
+            HashSet::new(),
+            scrims
+                .iter()
+                .filter(|scrim| scrim.id <= newest)
+                .map(|scrim| scrim.id)
+                .collect(),
+        ),
+        (ComponentInteractionDataKind::Button, CancelAction::UnmatchedConfirmed { newest }) => (
+            HashSet::new(),
+            scrims
+                .iter()
+                .filter(|scrim| scrim.id <= newest && scrim.match_id.is_none())
+                .map(|scrim| scrim.id)
+                .collect(),
+        ),
+        _ => return Err(Text::new("button-expired").into()),
+    };
 
-    // Timeout
-    msg_handle.delete(ctx.into()).await?;
+    let view = if !to_cancel.is_empty() {
+        for scrim in scrims.iter().filter(|scrim| to_cancel.contains(&scrim.id)) {
+            db::cancel_scrim_in(&data.db, scrim.id).await?;
+            notify_partner(ctx, &data.db, scrim).await?;
+        }
+        View {
+            content: if to_cancel.len() == 1 {
+                t!(locale, "cancel-done-one")
+            } else {
+                t!(locale, "cancel-done", count = to_cancel.len())
+            },
+            embeds: cards(
+                &scrims
+                    .iter()
+                    .filter(|scrim| to_cancel.contains(&scrim.id))
+                    .collect::<Vec<_>>(),
+                |_| CardStatus::Cancelled,
+                locale,
+            ),
+            components: Vec::new(),
+        }
+    } else if scrims.is_empty() {
+        View::new(t!(locale, "cancel-none-left"))
+    } else {
+        let prefs = db::get_preferences_of(&data.db, user_id).await?;
+        cancel_page(&scrims, &selected, page, &filter, prefs, locale)?
+    };
+    i.create_response(
+        ctx,
+        serenity::CreateInteractionResponse::UpdateMessage(view.response()),
+    )
+    .await?;
     Ok(())
 }
@@ -0,0 +1,110 @@
+use poise::serenity_prelude as serenity;
+
+use crate::{
+    db,
+    i18n::{self, t, Text},
+    invites::send_invite,
+    lfs::{
+        check_team_name, parse_rank_range, parse_time, LookingForScrim, Platform, RankRange, Region,
+    },
+    overlaps::check_overlaps,
+    Context, Error,
+};
+
+async fn complete_team(ctx: Context<'_>, partial: &str) -> Vec<String> {
+    db::get_team_names(ctx, partial.trim())
+        .await
+        .unwrap_or_default()
+}
+
+#[poise::command(slash_command, ephemeral)]
+#[tracing::instrument(err, skip(ctx), fields(user = ctx.author().id.get()))]
+#[allow(clippy::too_many_arguments)]
+pub async fn challenge(
+    ctx: Context<'_>,
+    region: Region,
+    platform: Platform,
+    range: String,
+    time: String,
+    #[autocomplete = "complete_team"] team: Option<String>,
+    user: Option<serenity::User>,
+    team_name: Option<String>,
+) -> Result<(), Error> {
+    check_team_name(team_name.as_deref())?;
+
+    let (target_id, target_team_name) = match (team, user) {
+        (Some(_), Some(_)) => return Err(Text::new("challenge-both").into()),
+        (None, None) => return Err(Text::new("challenge-none").into()),
+        (Some(team), None) => {
+            let Some(target_id) = db::get_team_creator(ctx, &team).await? else {
+                return Err(Text::new("challenge-unknown-team").arg("team", team).into());
+            };
+            (target_id, Some(team))
+        }
+        (None, Some(user)) => {
+            if user.bot {
+                return Err(Text::new("challenge-bot").into());
+            }
+            let target_id = user.id.get() as i64;
+            (target_id, db::get_latest_team_name(ctx, target_id).await?)
+        }
+    };
+    if target_id == ctx.author().id.get() as i64 {
+        return Err(Text::new("challenge-self").into());
+    }
+
+    let prefs = db::get_preferences(ctx).await?;
+    let lfs = LookingForScrim {
+        id: 0,
+        creator_id: ctx.author().id.get() as i64,
+        team_name,
+        region,
+        platform,
+        range: RankRange(parse_rank_range(&range)?),
+        time: parse_time(&time, prefs)?,
+        match_id: None,
+        cancelled: false,
+        checked_in: false,
+        no_show: false,
+        reliability: None,
+        auto_match: false,
+    };
+    let guild_id = ctx.guild_id().map(|id| id.get() as i64);
+    let warning =
+        check_overlaps(&ctx.data().db, guild_id, std::slice::from_ref(&lfs), false).await?;
+    let policy = db::get_overlap_policy(&ctx.data().db, guild_id).await?;
+    let lfs = db::create_scrims(&ctx.data().db, vec![lfs], policy)
+        .await?
+        .remove(0);
+
+    if let Err(e) = send_invite(
+        ctx.serenity_context(),
+        &ctx.data().db,
+        &lfs,
+        target_id,
+        target_team_name.as_deref(),
+        Text::new("challenge-title"),
+    )
+    .await
+    {
+        db::cancel_scrim(ctx, lfs.id).await?;
+        return Err(e);
+    }
+
+    let locale = i18n::locale(ctx).await;
+    let mut content = t!(
+        locale,
+        "challenge-sent",
+        user = target_id,
+        region = format!("{:?}", lfs.region),
+        platform = format!("{:?}", lfs.platform),
+        range = lfs.range,
+        time = lfs.time.timestamp()
+    );
+    if let Some(warning) = warning {
+        content = format!("{}\n{content}", warning.render(locale));
+    }
+    ctx.reply(content).await?;
+
+    Ok(())
+}
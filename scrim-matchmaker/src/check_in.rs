@@ -0,0 +1,241 @@
+use chrono::{DateTime, Utc};
+use poise::serenity_prelude::{
+    self as serenity, ButtonStyle, ComponentInteraction, CreateActionRow, CreateButton,
+    CreateInteractionResponse, CreateInteractionResponseMessage, CreateMessage,
+};
+
+use crate::{
+    components::ComponentAction,
+    db,
+    i18n::{self, t, Locale},
+    lfs::LookingForScrim,
+    no_show::no_show_button,
+    overlaps::overlap_warning,
+    reminders::send_dm,
+    render::{write_scrim_meta, write_scrim_with_name},
+    Data, Error,
+};
+
+/// How many minutes before the start of a scrim the check-in opens.
+pub const CHECK_IN_WINDOW: i32 = 15;
+/// How many minutes after the start of a scrim a missed check-in is still reported.
+const CHECK_IN_GRACE: i32 = 60;
+
+pub fn check_in_button(scrim_id: i32, locale: Locale) -> Result<CreateButton, Error> {
+    Ok(
+        CreateButton::new(ComponentAction::CheckIn { scrim_id }.custom_id()?)
+            .style(ButtonStyle::Success)
+            .label(t!(locale, "button-check-in")),
+    )
+}
+
+/// Whether the check-in window of the scrim is open at `now`. It opens [`CHECK_IN_WINDOW`] minutes
+/// before the start and closes once the scrim has started.
+pub fn is_open(scrim: &LookingForScrim, now: DateTime<Utc>) -> bool {
+    let until_start = scrim.time - now;
+    !scrim.cancelled
+        && until_start > chrono::Duration::zero()
+        && until_start <= chrono::Duration::minutes(CHECK_IN_WINDOW.into())
+}
+
+/// Ask both sides of scrims that are about to start to check in.
+#[tracing::instrument(err, skip(ctx, db))]
+pub async fn send_prompts(ctx: &serenity::Context, db: &sqlx::PgPool) -> Result<(), Error> {
+    for check_in in db::get_check_ins_to_prompt(db, CHECK_IN_WINDOW).await? {
+        if !db::mark_check_in_prompted(db, check_in.scrim_id).await? || check_in.checked_in {
+            continue;
+        }
+
+        let scrim = db::get_scrim_in(db, check_in.scrim_id).await?;
+        let other = db::get_scrim_in(db, check_in.other_id).await?;
+
+        let locale = i18n::locale_of(db, scrim.creator_id, None).await;
+        let mut opponent = String::new();
+        write_scrim_with_name(
+            &other,
+            Some(&scrim),
+            true,
+            locale,
+            Utc::now(),
+            &mut opponent,
+        );
+        let content = t!(
+            locale,
+            "check-in-prompt",
+            time = scrim.time.timestamp(),
+            opponent = opponent
+        );
+
+        let msg = CreateMessage::new()
+            .content(content)
+            .components(vec![CreateActionRow::Buttons(vec![check_in_button(
+                scrim.id, locale,
+            )?])]);
+        if let Err(e) = send_dm(ctx, scrim.creator_id, msg).await {
+            tracing::warn!(
+                "Could not send check-in prompt to {}: {e}",
+                scrim.creator_id
+            );
+        }
+    }
+
+    Ok(())
+}
+
+/// Notify everyone who checked in while their partner didn't, and offer them open postings close
+/// to the original time instead.
+#[tracing::instrument(err, skip(ctx, db))]
+pub async fn resolve(ctx: &serenity::Context, db: &sqlx::PgPool) -> Result<(), Error> {
+    for check_in in db::get_check_ins_to_resolve(db, CHECK_IN_GRACE).await? {
+        if !db::mark_check_in_resolved(db, check_in.scrim_id).await?
+            || !check_in.checked_in
+            || check_in.other_checked_in
+        {
+            continue;
+        }
+
+        let scrim = db::get_scrim_in(db, check_in.scrim_id).await?;
+        let other = db::get_scrim_in(db, check_in.other_id).await?;
+        let matches = db::find_matches_in(db, &scrim, 0, 5).await?;
+
+        let locale = i18n::locale_of(db, scrim.creator_id, None).await;
+        let (content, components) = missed_check_in_msg(&scrim, &other, &matches, locale)?;
+        let msg = CreateMessage::new().content(content).components(components);
+        if let Err(e) = send_dm(ctx, scrim.creator_id, msg).await {
+            tracing::warn!(
+                "Could not send missed check-in notice to {}: {e}",
+                scrim.creator_id
+            );
+        }
+    }
+
+    Ok(())
+}
+
+fn missed_check_in_msg(
+    scrim: &LookingForScrim,
+    other: &LookingForScrim,
+    matches: &[(f32, LookingForScrim)],
+    locale: Locale,
+) -> Result<(String, Vec<CreateActionRow>), Error> {
+    use std::fmt::Write;
+
+    let now = Utc::now();
+    let mut opponent = String::new();
+    write_scrim_with_name(other, Some(scrim), true, locale, now, &mut opponent);
+    let mut content = t!(locale, "check-in-missed", opponent = opponent);
+    content.push('\n');
+
+    let mut buttons = vec![no_show_button(scrim.id, locale)?];
+    if matches.is_empty() {
+        content.push_str(&t!(locale, "check-in-no-postings"));
+    } else {
+        writeln!(content, "### {}", t!(locale, "check-in-postings")).unwrap();
+        for (match_id, (_, candidate)) in matches.iter().enumerate() {
+            write!(content, "{}. ", match_id + 1).unwrap();
+            write_scrim_with_name(candidate, Some(scrim), true, locale, now, &mut content);
+            content.push('\n');
+
+            buttons.push(
+                CreateButton::new(
+                    ComponentAction::Rematch {
+                        scrim_id: scrim.id,
+                        candidate_id: candidate.id,
+                    }
+                    .custom_id()?,
+                )
+                .style(ButtonStyle::Success)
+                .label(t!(locale, "check-in-play", rank = match_id + 1)),
+            );
+        }
+    }
+
+    Ok((
+        content,
+        buttons
+            .chunks(5)
+            .map(|chunk| CreateActionRow::Buttons(chunk.to_vec()))
+            .collect(),
+    ))
+}
+
+/// Handle the buttons sent by the check-in jobs. With a candidate, the user plays it instead of
+/// the scrim their partner didn't check in for.
+pub async fn handle_component(
+    ctx: &serenity::Context,
+    data: &Data,
+    i: &ComponentInteraction,
+    scrim_id: i32,
+    candidate_id: Option<i32>,
+) -> Result<(), Error> {
+    let locale = i18n::component_locale(data, i).await;
+    let scrim = db::get_scrim_in(&data.db, scrim_id).await?;
+    if scrim.creator_id != i.user.id.get() as i64 {
+        return respond(ctx, i, t!(locale, "scrim-not-yours")).await;
+    }
+
+    match candidate_id {
+        None => {
+            if !is_open(&scrim, Utc::now()) || !db::check_in(&data.db, scrim.id).await? {
+                return respond(ctx, i, t!(locale, "check-in-closed")).await;
+            }
+
+            let mut meta = String::new();
+            write_scrim_meta(&scrim, None, locale, Utc::now(), &mut meta);
+            respond(ctx, i, t!(locale, "check-in-done", scrim = meta)).await
+        }
+        Some(candidate_id) => {
+            let candidate = db::get_scrim_in(&data.db, candidate_id).await?;
+            if candidate.cancelled || candidate.time < Utc::now() || candidate.match_id.is_some() {
+                return respond(ctx, i, t!(locale, "check-in-unavailable")).await;
+            }
+
+            // The original posting has already started, so a new one is created at the time of
+            // the candidate.
+            let lfs = LookingForScrim {
+                id: 0,
+                time: candidate.time,
+                match_id: None,
+                cancelled: false,
+                checked_in: false,
+                no_show: false,
+                ..scrim
+            };
+            let guild_id = i.guild_id.map(|id| id.get() as i64);
+            let policy = db::get_overlap_policy(&data.db, guild_id).await?;
+            let times =
+                match db::create_matched_scrim(&data.db, lfs, candidate.id, Some(scrim_id), policy)
+                    .await
+                {
+                    Ok(times) => times,
+                    Err(e) => return respond(ctx, i, i18n::error_text(&e, locale)).await,
+                };
+
+            let mut opponent = String::new();
+            write_scrim_with_name(&candidate, None, true, locale, Utc::now(), &mut opponent);
+            let mut content = t!(locale, "check-in-rematched", opponent = opponent);
+            if !times.is_empty() {
+                let warning = overlap_warning(&times).render(locale);
+                content = format!("{warning}\n{content}");
+            }
+            respond(ctx, i, content).await
+        }
+    }
+}
+
+async fn respond(
+    ctx: &serenity::Context,
+    i: &ComponentInteraction,
+    content: String,
+) -> Result<(), Error> {
+    i.create_response(
+        ctx,
+        CreateInteractionResponse::UpdateMessage(
+            CreateInteractionResponseMessage::new()
+                .content(content)
+                .components(vec![]),
+        ),
+    )
+    .await?;
+    Ok(())
+}
@@ -0,0 +1,860 @@
+use std::fmt;
+
+use chrono::DateTime;
+
+use crate::{
+    cancel::CancelFilter,
+    lfs::{Platform, Region},
+    Error,
+};
+
+/// First segment of all custom ids. Bump it when the meaning of ids changes, so old buttons are
+/// reported as expired instead of doing something else.
+const VERSION: &str = "v1";
+/// Discord rejects components with longer custom ids.
+const MAX_LEN: usize = 100;
+
+/// Action of a button or select menu, encoded in its custom id.
+///
+/// Actions only contain the ids of the affected rows, so they keep working after restarts.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ComponentAction {
+    /// Confirmation of a `/lfs` draft.
+    Lfs {
+        action: DraftAction,
+        draft_id: i32,
+    },
+    /// Form for new postings. The choices made so far are kept in the custom ids.
+    LfsForm {
+        action: FormAction,
+        region: Option<Region>,
+        platform: Option<Platform>,
+    },
+    Scrims(ScrimsAction),
+    Cancel {
+        action: CancelAction,
+        page: usize,
+        filter: CancelFilter,
+    },
+    CheckIn {
+        scrim_id: i32,
+    },
+    /// Play `candidate_id` instead of the scrim the partner didn't check in for.
+    Rematch {
+        scrim_id: i32,
+        candidate_id: i32,
+    },
+    NoShow {
+        scrim_id: i32,
+    },
+    /// Decline of the automatic pairing of `scrim_id`.
+    AutoMatch {
+        scrim_id: i32,
+    },
+    Report {
+        scrim_id: i32,
+    },
+    Invite {
+        action: InviteAction,
+        invite_id: i32,
+    },
+    Proposal {
+        answer: Answer,
+        proposal_id: i32,
+    },
+    /// Challenge button on a board.
+    Challenge {
+        scrim_id: i32,
+    },
+    /// Answer of the creator of `scrim_id` to the challenge of `challenger`.
+    ChallengeAnswer {
+        answer: Answer,
+        scrim_id: i32,
+        challenger: Challenger,
+    },
+    /// Action on a `/history` reply whose filter is stored as `filter_id`.
+    History {
+        filter_id: i32,
+        action: HistoryAction,
+    },
+    /// Action on a `/browse` reply whose filter is stored as `filter_id`.
+    Browse {
+        filter_id: i32,
+        action: BrowseAction,
+    },
+    /// Action on the `/import` preview `import_id`.
+    Import {
+        import_id: i32,
+        action: ImportAction,
+    },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DraftAction {
+    Confirm,
+    Cancel,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FormAction {
+    /// Show the form.
+    Open,
+    Region,
+    Platform,
+    /// Ask for the remaining fields in a modal.
+    Continue,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Answer {
+    Accept,
+    Decline,
+}
+
+/// Side that challenged a posting on a board.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Challenger {
+    /// Open posting of the challenger, which picked the challenged one.
+    Scrim(i32),
+    /// User without a fitting posting. Their posting is only created once the challenge is
+    /// accepted.
+    User(i64),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum InviteAction {
+    Accept,
+    Decline,
+    Propose,
+}
+
+/// Actions of `/scrims` on the scrim they contain. The scrim to show is the selected value of
+/// `Select`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ScrimsAction {
+    Select,
+    Prev(i32),
+    Next(i32),
+    Refresh(i32),
+    /// Show the candidates from `offset` on.
+    More {
+        scrim_id: i32,
+        offset: i64,
+    },
+    Cancel(i32),
+    Restore(i32),
+    CheckIn(i32),
+    Revoke(i32),
+    Accept {
+        scrim_id: i32,
+        other_id: i32,
+    },
+    /// Propose another time to `other_id`, or to the partner of a matched scrim.
+    Propose {
+        scrim_id: i32,
+        other_id: Option<i32>,
+    },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CancelAction {
+    Select,
+    Prev,
+    Next,
+    Confirm,
+    /// Ask to confirm cancelling all listed scrims.
+    All,
+    /// Cancel the scrims listed for confirmation, which are those matching the filter up to the
+    /// newest one. Later postings stay.
+    AllConfirmed {
+        newest: i32,
+    },
+    /// Ask to confirm cancelling all listed scrims without a match.
+    Unmatched,
+    UnmatchedConfirmed {
+        newest: i32,
+    },
+    /// Leave a confirmation for the list.
+    Back,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HistoryAction {
+    /// Show the page with this index.
+    Page(i64),
+    Rematch(i32),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BrowseAction {
+    /// Show the page with this index.
+    Page(i64),
+    Offer(i32),
+    Pick(i32),
+}
+
+/// Actions on the preview of `/import`. The events to post are the selected values of `Select`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ImportAction {
+    Select,
+    Post,
+    Cancel,
+}
+
+impl DraftAction {
+    fn as_str(self) -> &'static str {
+        match self {
+            Self::Confirm => "confirm",
+            Self::Cancel => "cancel",
+        }
+    }
+
+    fn parse(s: &str) -> Option<Self> {
+        match s {
+            "confirm" => Some(Self::Confirm),
+            "cancel" => Some(Self::Cancel),
+            _ => None,
+        }
+    }
+}
+
+impl FormAction {
+    fn as_str(self) -> &'static str {
+        match self {
+            Self::Open => "open",
+            Self::Region => "region",
+            Self::Platform => "platform",
+            Self::Continue => "continue",
+        }
+    }
+
+    fn parse(s: &str) -> Option<Self> {
+        match s {
+            "open" => Some(Self::Open),
+            "region" => Some(Self::Region),
+            "platform" => Some(Self::Platform),
+            "continue" => Some(Self::Continue),
+            _ => None,
+        }
+    }
+}
+
+impl Answer {
+    fn as_str(self) -> &'static str {
+        match self {
+            Self::Accept => "accept",
+            Self::Decline => "decline",
+        }
+    }
+
+    fn parse(s: &str) -> Option<Self> {
+        match s {
+            "accept" => Some(Self::Accept),
+            "decline" => Some(Self::Decline),
+            _ => None,
+        }
+    }
+}
+
+impl InviteAction {
+    fn as_str(self) -> &'static str {
+        match self {
+            Self::Accept => "accept",
+            Self::Decline => "decline",
+            Self::Propose => "propose",
+        }
+    }
+
+    fn parse(s: &str) -> Option<Self> {
+        match s {
+            "accept" => Some(Self::Accept),
+            "decline" => Some(Self::Decline),
+            "propose" => Some(Self::Propose),
+            _ => None,
+        }
+    }
+}
+
+impl CancelAction {
+    fn as_str(self) -> &'static str {
+        match self {
+            Self::Select => "select",
+            Self::Prev => "prev",
+            Self::Next => "next",
+            Self::Confirm => "confirm",
+            Self::All => "all",
+            Self::AllConfirmed { .. } => "confirm_all",
+            Self::Unmatched => "unmatched",
+            Self::UnmatchedConfirmed { .. } => "confirm_unmatched",
+            Self::Back => "back",
+        }
+    }
+
+    /// Parse an action, given the page it is shown on or, when it is confirmed, the newest
+    /// scrim to cancel.
+    fn parse(s: &str, arg: &str) -> Option<(Self, usize)> {
+        let action = match s {
+            "select" => Self::Select,
+            "prev" => Self::Prev,
+            "next" => Self::Next,
+            "confirm" => Self::Confirm,
+            "all" => Self::All,
+            "confirm_all" => Self::AllConfirmed {
+                newest: arg.parse().ok()?,
+            },
+            "unmatched" => Self::Unmatched,
+            "confirm_unmatched" => Self::UnmatchedConfirmed {
+                newest: arg.parse().ok()?,
+            },
+            "back" => Self::Back,
+            _ => return None,
+        };
+        let page = match action {
+            Self::AllConfirmed { .. } | Self::UnmatchedConfirmed { .. } => 0,
+            _ => arg.parse().ok()?,
+        };
+        Some((action, page))
+    }
+
+    /// The page the action is shown on, or the newest scrim to cancel when it is confirmed. A
+    /// confirmation has no pages, so they share a place in the custom id.
+    fn arg(self, page: usize) -> String {
+        match self {
+            Self::AllConfirmed { newest } | Self::UnmatchedConfirmed { newest } => {
+                newest.to_string()
+            }
+            _ => page.to_string(),
+        }
+    }
+}
+
+impl ImportAction {
+    fn as_str(self) -> &'static str {
+        match self {
+            Self::Select => "select",
+            Self::Post => "post",
+            Self::Cancel => "cancel",
+        }
+    }
+
+    fn parse(s: &str) -> Option<Self> {
+        match s {
+            "select" => Some(Self::Select),
+            "post" => Some(Self::Post),
+            "cancel" => Some(Self::Cancel),
+            _ => None,
+        }
+    }
+}
+
+impl ComponentAction {
+    /// Encode the action as a custom id.
+    pub fn custom_id(&self) -> Result<String, Error> {
+        let id = self.to_string();
+        if id.len() > MAX_LEN {
+            return Err(format!("Component id `{id}` is too long").into());
+        }
+        debug_assert_eq!(Self::parse(&id).as_ref(), Some(self), "{id}");
+        Ok(id)
+    }
+
+    /// Decode a custom id. Returns `None` for ids of other versions or that are malformed.
+    pub fn parse(custom_id: &str) -> Option<Self> {
+        if custom_id.len() > MAX_LEN {
+            return None;
+        }
+        let body = match custom_id.split_once(',')? {
+            (VERSION, body) => body,
+            _ => return None,
+        };
+        let (tag, rest) = body.split_once(',').unwrap_or((body, ""));
+
+        // The team name of the filter comes last as it may contain commas
+        if tag == "cancel" {
+            let mut split = rest.splitn(4, ',');
+            let (action, page) = CancelAction::parse(split.next()?, split.next()?)?;
+            let day = match split.next()?.parse().ok()? {
+                0 => None,
+                day => Some(DateTime::from_timestamp(day, 0)?),
+            };
+            let team_name = split.next()?;
+            return Some(Self::Cancel {
+                action,
+                page,
+                filter: CancelFilter {
+                    day,
+                    team_name: (!team_name.is_empty()).then(|| team_name.to_owned()),
+                },
+            });
+        }
+
+        let args: Vec<&str> = rest.split(',').collect();
+        let action = match (tag, args.as_slice()) {
+            ("lfs", ["form", action, region, platform]) => Self::LfsForm {
+                action: FormAction::parse(action)?,
+                region: optional(region)?,
+                platform: optional(platform)?,
+            },
+            ("lfs", [action, draft_id]) => Self::Lfs {
+                action: DraftAction::parse(action)?,
+                draft_id: draft_id.parse().ok()?,
+            },
+            ("scrims", ["select"]) => Self::Scrims(ScrimsAction::Select),
+            ("scrims", [action, scrim_id, rest @ ..]) => {
+                let scrim_id = scrim_id.parse().ok()?;
+                Self::Scrims(match (*action, rest) {
+                    ("prev", []) => ScrimsAction::Prev(scrim_id),
+                    ("next", []) => ScrimsAction::Next(scrim_id),
+                    ("refresh", []) => ScrimsAction::Refresh(scrim_id),
+                    ("more", [offset]) => ScrimsAction::More {
+                        scrim_id,
+                        offset: offset.parse().ok()?,
+                    },
+                    ("cancel", []) => ScrimsAction::Cancel(scrim_id),
+                    ("restore", []) => ScrimsAction::Restore(scrim_id),
+                    ("check_in", []) => ScrimsAction::CheckIn(scrim_id),
+                    ("revoke", []) => ScrimsAction::Revoke(scrim_id),
+                    ("accept", [other_id]) => ScrimsAction::Accept {
+                        scrim_id,
+                        other_id: other_id.parse().ok()?,
+                    },
+                    ("propose", []) => ScrimsAction::Propose {
+                        scrim_id,
+                        other_id: None,
+                    },
+                    ("propose", [other_id]) => ScrimsAction::Propose {
+                        scrim_id,
+                        other_id: Some(other_id.parse().ok()?),
+                    },
+                    _ => return None,
+                })
+            }
+            ("check_in", [scrim_id]) => Self::CheckIn {
+                scrim_id: scrim_id.parse().ok()?,
+            },
+            ("rematch", [scrim_id, candidate_id]) => Self::Rematch {
+                scrim_id: scrim_id.parse().ok()?,
+                candidate_id: candidate_id.parse().ok()?,
+            },
+            ("no_show", [scrim_id]) => Self::NoShow {
+                scrim_id: scrim_id.parse().ok()?,
+            },
+            ("auto_match", [scrim_id]) => Self::AutoMatch {
+                scrim_id: scrim_id.parse().ok()?,
+            },
+            ("report", [scrim_id]) => Self::Report {
+                scrim_id: scrim_id.parse().ok()?,
+            },
+            ("invite", [action, invite_id]) => Self::Invite {
+                action: InviteAction::parse(action)?,
+                invite_id: invite_id.parse().ok()?,
+            },
+            ("proposal", [answer, proposal_id]) => Self::Proposal {
+                answer: Answer::parse(answer)?,
+                proposal_id: proposal_id.parse().ok()?,
+            },
+            ("board", ["challenge", scrim_id]) => Self::Challenge {
+                scrim_id: scrim_id.parse().ok()?,
+            },
+            ("board", [answer, scrim_id, other_id]) => Self::ChallengeAnswer {
+                answer: Answer::parse(answer)?,
+                scrim_id: scrim_id.parse().ok()?,
+                challenger: Challenger::Scrim(other_id.parse().ok()?),
+            },
+            ("board", [answer, scrim_id, "user", user_id]) => Self::ChallengeAnswer {
+                answer: Answer::parse(answer)?,
+                scrim_id: scrim_id.parse().ok()?,
+                challenger: Challenger::User(user_id.parse().ok()?),
+            },
+            ("history", [filter_id, action, arg]) => Self::History {
+                filter_id: filter_id.parse().ok()?,
+                action: match *action {
+                    "page" => HistoryAction::Page(arg.parse().ok()?),
+                    "rematch" => HistoryAction::Rematch(arg.parse().ok()?),
+                    _ => return None,
+                },
+            },
+            ("browse", [filter_id, action, arg]) => Self::Browse {
+                filter_id: filter_id.parse().ok()?,
+                action: match *action {
+                    "page" => BrowseAction::Page(arg.parse().ok()?),
+                    "offer" => BrowseAction::Offer(arg.parse().ok()?),
+                    "pick" => BrowseAction::Pick(arg.parse().ok()?),
+                    _ => return None,
+                },
+            },
+            ("import", [import_id, action]) => Self::Import {
+                import_id: import_id.parse().ok()?,
+                action: ImportAction::parse(action)?,
+            },
+            _ => return None,
+        };
+        Some(action)
+    }
+}
+
+/// Parse an optional argument which is empty if missing.
+fn optional<T: std::str::FromStr>(s: &str) -> Option<Option<T>> {
+    if s.is_empty() {
+        Some(None)
+    } else {
+        s.parse().ok().map(Some)
+    }
+}
+
+impl fmt::Display for ComponentAction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{VERSION},")?;
+        match self {
+            Self::Lfs { action, draft_id } => write!(f, "lfs,{},{draft_id}", action.as_str()),
+            Self::LfsForm {
+                action,
+                region,
+                platform,
+            } => {
+                write!(f, "lfs,form,{},", action.as_str())?;
+                if let Some(region) = region {
+                    write!(f, "{region:?}")?;
+                }
+                f.write_str(",")?;
+                if let Some(platform) = platform {
+                    write!(f, "{platform:?}")?;
+                }
+                Ok(())
+            }
+            Self::Scrims(action) => match action {
+                ScrimsAction::Select => write!(f, "scrims,select"),
+                ScrimsAction::Prev(id) => write!(f, "scrims,prev,{id}"),
+                ScrimsAction::Next(id) => write!(f, "scrims,next,{id}"),
+                ScrimsAction::Refresh(id) => write!(f, "scrims,refresh,{id}"),
+                ScrimsAction::More { scrim_id, offset } => {
+                    write!(f, "scrims,more,{scrim_id},{offset}")
+                }
+                ScrimsAction::Cancel(id) => write!(f, "scrims,cancel,{id}"),
+                ScrimsAction::Restore(id) => write!(f, "scrims,restore,{id}"),
+                ScrimsAction::CheckIn(id) => write!(f, "scrims,check_in,{id}"),
+                ScrimsAction::Revoke(id) => write!(f, "scrims,revoke,{id}"),
+                ScrimsAction::Accept { scrim_id, other_id } => {
+                    write!(f, "scrims,accept,{scrim_id},{other_id}")
+                }
+                ScrimsAction::Propose {
+                    scrim_id,
+                    other_id: None,
+                } => write!(f, "scrims,propose,{scrim_id}"),
+                ScrimsAction::Propose {
+                    scrim_id,
+                    other_id: Some(other_id),
+                } => write!(f, "scrims,propose,{scrim_id},{other_id}"),
+            },
+            Self::Cancel {
+                action,
+                page,
+                filter,
+            } => write!(
+                f,
+                "cancel,{},{},{},{}",
+                action.as_str(),
+                action.arg(*page),
+                filter.day.map_or(0, |day| day.timestamp()),
+                filter.team_name.as_deref().unwrap_or_default()
+            ),
+            Self::CheckIn { scrim_id } => write!(f, "check_in,{scrim_id}"),
+            Self::Rematch {
+                scrim_id,
+                candidate_id,
+            } => write!(f, "rematch,{scrim_id},{candidate_id}"),
+            Self::NoShow { scrim_id } => write!(f, "no_show,{scrim_id}"),
+            Self::AutoMatch { scrim_id } => write!(f, "auto_match,{scrim_id}"),
+            Self::Report { scrim_id } => write!(f, "report,{scrim_id}"),
+            Self::Invite { action, invite_id } => {
+                write!(f, "invite,{},{invite_id}", action.as_str())
+            }
+            Self::Proposal {
+                answer,
+                proposal_id,
+            } => write!(f, "proposal,{},{proposal_id}", answer.as_str()),
+            Self::Challenge { scrim_id } => write!(f, "board,challenge,{scrim_id}"),
+            Self::ChallengeAnswer {
+                answer,
+                scrim_id,
+                challenger,
+            } => match challenger {
+                Challenger::Scrim(other_id) => {
+                    write!(f, "board,{},{scrim_id},{other_id}", answer.as_str())
+                }
+                Challenger::User(user_id) => {
+                    write!(f, "board,{},{scrim_id},user,{user_id}", answer.as_str())
+                }
+            },
+            Self::History { filter_id, action } => match action {
+                HistoryAction::Page(page) => write!(f, "history,{filter_id},page,{page}"),
+                HistoryAction::Rematch(id) => write!(f, "history,{filter_id},rematch,{id}"),
+            },
+            Self::Browse { filter_id, action } => match action {
+                BrowseAction::Page(page) => write!(f, "browse,{filter_id},page,{page}"),
+                BrowseAction::Offer(id) => write!(f, "browse,{filter_id},offer,{id}"),
+                BrowseAction::Pick(id) => write!(f, "browse,{filter_id},pick,{id}"),
+            },
+            Self::Import { import_id, action } => {
+                write!(f, "import,{import_id},{}", action.as_str())
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn actions_round_trip() {
+        let filter = CancelFilter {
+            day: DateTime::from_timestamp(1_720_000_000, 0),
+            team_name: Some("Alpha".to_owned()),
+        };
+        let actions = [
+            ComponentAction::Lfs {
+                action: DraftAction::Confirm,
+                draft_id: 1,
+            },
+            ComponentAction::Lfs {
+                action: DraftAction::Cancel,
+                draft_id: 1,
+            },
+            ComponentAction::LfsForm {
+                action: FormAction::Open,
+                region: None,
+                platform: None,
+            },
+            ComponentAction::LfsForm {
+                action: FormAction::Region,
+                region: Some(Region::EU),
+                platform: None,
+            },
+            ComponentAction::LfsForm {
+                action: FormAction::Platform,
+                region: None,
+                platform: Some(Platform::Console),
+            },
+            ComponentAction::LfsForm {
+                action: FormAction::Continue,
+                region: Some(Region::NA),
+                platform: Some(Platform::PC),
+            },
+            ComponentAction::Scrims(ScrimsAction::Select),
+            ComponentAction::Scrims(ScrimsAction::Prev(2)),
+            ComponentAction::Scrims(ScrimsAction::Next(2)),
+            ComponentAction::Scrims(ScrimsAction::Refresh(2)),
+            ComponentAction::Scrims(ScrimsAction::More {
+                scrim_id: 2,
+                offset: 10,
+            }),
+            ComponentAction::Scrims(ScrimsAction::Cancel(2)),
+            ComponentAction::Scrims(ScrimsAction::Restore(2)),
+            ComponentAction::Scrims(ScrimsAction::CheckIn(2)),
+            ComponentAction::Scrims(ScrimsAction::Revoke(2)),
+            ComponentAction::Scrims(ScrimsAction::Accept {
+                scrim_id: 2,
+                other_id: 3,
+            }),
+            ComponentAction::Scrims(ScrimsAction::Propose {
+                scrim_id: 2,
+                other_id: None,
+            }),
+            ComponentAction::Scrims(ScrimsAction::Propose {
+                scrim_id: 2,
+                other_id: Some(3),
+            }),
+            ComponentAction::CheckIn { scrim_id: 4 },
+            ComponentAction::Rematch {
+                scrim_id: 4,
+                candidate_id: 5,
+            },
+            ComponentAction::NoShow { scrim_id: 4 },
+            ComponentAction::AutoMatch { scrim_id: 4 },
+            ComponentAction::Report { scrim_id: 4 },
+            ComponentAction::Invite {
+                action: InviteAction::Accept,
+                invite_id: 6,
+            },
+            ComponentAction::Invite {
+                action: InviteAction::Decline,
+                invite_id: 6,
+            },
+            ComponentAction::Invite {
+                action: InviteAction::Propose,
+                invite_id: 6,
+            },
+            ComponentAction::Proposal {
+                answer: Answer::Accept,
+                proposal_id: 7,
+            },
+            ComponentAction::Proposal {
+                answer: Answer::Decline,
+                proposal_id: 7,
+            },
+            ComponentAction::Challenge { scrim_id: 8 },
+            ComponentAction::ChallengeAnswer {
+                answer: Answer::Accept,
+                scrim_id: 8,
+                challenger: Challenger::Scrim(9),
+            },
+            ComponentAction::ChallengeAnswer {
+                answer: Answer::Decline,
+                scrim_id: 8,
+                challenger: Challenger::User(123_456_789_012_345_678),
+            },
+            ComponentAction::History {
+                filter_id: 1,
+                action: HistoryAction::Page(2),
+            },
+            ComponentAction::History {
+                filter_id: 1,
+                action: HistoryAction::Rematch(10),
+            },
+            ComponentAction::Browse {
+                filter_id: 1,
+                action: BrowseAction::Page(0),
+            },
+            ComponentAction::Browse {
+                filter_id: 1,
+                action: BrowseAction::Offer(11),
+            },
+            ComponentAction::Browse {
+                filter_id: 1,
+                action: BrowseAction::Pick(11),
+            },
+            ComponentAction::Import {
+                import_id: 1,
+                action: ImportAction::Select,
+            },
+            ComponentAction::Import {
+                import_id: 1,
+                action: ImportAction::Post,
+            },
+            ComponentAction::Import {
+                import_id: 1,
+                action: ImportAction::Cancel,
+            },
+        ];
+        let cancel_actions = [
+            CancelAction::Select,
+            CancelAction::Prev,
+            CancelAction::Next,
+            CancelAction::Confirm,
+            CancelAction::All,
+            CancelAction::Unmatched,
+            CancelAction::Back,
+        ]
+        .map(|action| (action, 3))
+        .into_iter()
+        .chain([
+            (CancelAction::AllConfirmed { newest: 12 }, 0),
+            (CancelAction::UnmatchedConfirmed { newest: 12 }, 0),
+        ])
+        .map(|(action, page)| ComponentAction::Cancel {
+            action,
+            page,
+            filter: filter.clone(),
+        })
+        .collect::<Vec<_>>();
+        for action in actions.iter().chain(&cancel_actions) {
+            let id = action.custom_id().unwrap();
+            assert_eq!(ComponentAction::parse(&id).as_ref(), Some(action), "{id}");
+        }
+
+        let unfiltered = ComponentAction::Cancel {
+            action: CancelAction::Select,
+            page: 0,
+            filter: CancelFilter::default(),
+        };
+        assert_eq!(unfiltered.custom_id().unwrap(), "v1,cancel,select,0,0,");
+        assert_eq!(
+            ComponentAction::parse("v1,cancel,select,0,0,"),
+            Some(unfiltered)
+        );
+    }
+
+    #[test]
+    fn unversioned_ids_are_rejected() {
+        // Ids of the collectors before versioning, and versioned ids without their version
+        for id in [
+            "confirm",
+            "cancel",
+            "1234,refresh,0",
+            "check_in,4",
+            "board,challenge,8",
+        ] {
+            assert_eq!(ComponentAction::parse(id), None, "{id}");
+        }
+    }
+
+    #[test]
+    fn team_names_may_contain_commas() {
+        let action = ComponentAction::Cancel {
+            action: CancelAction::All,
+            page: 0,
+            filter: CancelFilter {
+                day: None,
+                team_name: Some("Alpha, Beta,,Gamma,".to_owned()),
+            },
+        };
+        let id = action.custom_id().unwrap();
+        assert_eq!(id, "v1,cancel,all,0,0,Alpha, Beta,,Gamma,");
+        assert_eq!(ComponentAction::parse(&id), Some(action));
+    }
+
+    #[test]
+    fn ids_are_limited_to_100_characters() {
+        let action = |team_name: String| ComponentAction::Cancel {
+            action: CancelAction::UnmatchedConfirmed { newest: i32::MAX },
+            page: 0,
+            filter: CancelFilter {
+                day: DateTime::from_timestamp(1_720_000_000, 0),
+                team_name: Some(team_name),
+            },
+        };
+        // Longest id with a team name of the 50 characters allowed
+        let longest = action("x".repeat(50)).custom_id().unwrap();
+        assert!(longest.len() <= MAX_LEN, "{longest}");
+        assert!(action("x".repeat(60)).custom_id().is_err());
+
+        let too_long = format!("v1,cancel,select,0,0,{}", "x".repeat(80));
+        assert!(too_long.len() > MAX_LEN);
+        assert_eq!(ComponentAction::parse(&too_long), None);
+    }
+
+    #[test]
+    fn malformed_ids_are_rejected() {
+        for id in [
+            "",
+            "v1",
+            "v1,",
+            "v2,check_in,4",
+            "v1,unknown,4",
+            "v1,check_in",
+            "v1,check_in,",
+            "v1,check_in,x",
+            "v1,check_in,4,5",
+            "v1,lfs,publish,1",
+            "v1,lfs,form,open,XX,",
+            "v1,lfs,form,open,EU",
+            "v1,scrims,more,2",
+            "v1,scrims,accept,2",
+            "v1,scrims,cancel,2,3",
+            "v1,cancel,remove,0,0,",
+            "v1,cancel,select,x,0,",
+            "v1,cancel,select,0,0",
+            "v1,history,1,rematch",
+            "v1,history,1,page,x",
+            "v1,browse,x,page,0",
+            "v1,browse,1,prev",
+            "v1,import,1,delete",
+            "v1,board,maybe,8,9",
+            "v1,board,accept,8,team,9",
+            "check_in,x",
+        ] {
+            assert_eq!(ComponentAction::parse(id), None, "{id}");
+        }
+    }
+}
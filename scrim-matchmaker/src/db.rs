@@ -1,13 +1,35 @@
 use std::str::FromStr;
 
+use chrono::{DateTime, Utc};
 use poise::serenity_prelude::futures::{StreamExt, TryStreamExt};
 use sqlx::{postgres::PgRow, Row};
 
 use crate::{
+    board::BoardMessage,
+    browse::BrowseFilter,
+    calendar::{CalendarEvent, CalendarOwner},
+    history::HistoryFilter,
+    i18n::{Locale, Text},
+    import::Import,
+    invites::{Invite, InviteStatus},
     lfs::{LookingForScrim, RankRange},
+    overlaps::{self, OverlapPolicy},
+    preferences::{Clock, DateOrder, Preferences, WeekStart},
+    preset::Preset,
+    proposals::Proposal,
+    results::{ScrimResult, SCRIM_DURATION},
     Context, Error,
 };
 
+/// Selects all scrims together with the reliability of their creator.
+const SELECT_SCRIMS: &str = "SELECT scrims.*, reliability.score AS reliability
+    FROM scrims LEFT JOIN reliability ON reliability.creator_id = scrims.creator_id";
+
+/// Excludes postings reserved for someone they invited who hasn't answered yet.
+const NOT_RESERVED: &str = "NOT EXISTS (
+    SELECT 1 FROM invites WHERE invites.scrim_id = scrims.id AND invites.status = 'Pending'
+)";
+
 #[tracing::instrument(err, skip(ctx))]
 pub async fn set_timezone(
     ctx: Context<'_>,
@@ -22,23 +44,92 @@ pub async fn set_timezone(
 
 #[tracing::instrument(err, skip(ctx))]
 pub async fn get_timezone(ctx: Context<'_>) -> Result<chrono_tz::Tz, Error> {
+    get_timezone_of(&ctx.data().db, ctx.author().id.get() as i64).await
+}
+
+#[tracing::instrument(err, skip(db))]
+pub async fn get_timezone_of(db: &sqlx::PgPool, user_id: i64) -> Result<chrono_tz::Tz, Error> {
     let row: (String,) = sqlx::query_as("SELECT timezone FROM users WHERE id = $1")
-        .bind(ctx.author().id.get() as i64)
-        .fetch_one(&ctx.data().db)
+        .bind(user_id)
+        .fetch_one(db)
         .await
-        .map_err(|_| {
-            "You haven't set your timezone yet. Use `/timezone zone:<timezone>` to set it"
-                .to_owned()
-        })?;
-    Ok(chrono_tz::Tz::from_str(&row.0).map_err(|_| "Invalid timezone")?)
+        .map_err(|_| Text::new("timezone-missing"))?;
+    Ok(chrono_tz::Tz::from_str(&row.0).map_err(|_| Text::new("timezone-invalid"))?)
 }
 
 #[tracing::instrument(err, skip(ctx))]
-pub async fn create_scrim(ctx: Context<'_>, lfs: LookingForScrim) -> Result<(), sqlx::Error> {
-    sqlx::query(
+pub async fn set_preferences(
+    ctx: Context<'_>,
+    clock: Option<Clock>,
+    date_order: Option<DateOrder>,
+    week_start: Option<WeekStart>,
+) -> Result<(), Error> {
+    let result = sqlx::query(
+        "UPDATE users SET clock = COALESCE($2, clock), date_order = COALESCE($3, date_order),
+            week_start = COALESCE($4, week_start)
+        WHERE id = $1",
+    )
+    .bind(ctx.author().id.get() as i64)
+    .bind(clock.map(|clock| format!("{clock:?}")))
+    .bind(date_order.map(|order| format!("{order:?}")))
+    .bind(week_start.map(|start| format!("{start:?}")))
+    .execute(&ctx.data().db)
+    .await?;
+    if result.rows_affected() == 0 {
+        return Err(Text::new("timezone-missing").into());
+    }
+    Ok(())
+}
+
+#[tracing::instrument(err, skip(ctx))]
+pub async fn get_preferences(ctx: Context<'_>) -> Result<Preferences, Error> {
+    get_preferences_of(&ctx.data().db, ctx.author().id.get() as i64).await
+}
+
+/// Timezone and display preferences of `user_id`, who must have set their timezone.
+#[tracing::instrument(err, skip(db))]
+pub async fn get_preferences_of(db: &sqlx::PgPool, user_id: i64) -> Result<Preferences, Error> {
+    let row =
+        sqlx::query("SELECT timezone, clock, date_order, week_start FROM users WHERE id = $1")
+            .bind(user_id)
+            .fetch_one(db)
+            .await
+            .map_err(|_| Text::new("timezone-missing"))?;
+    Ok(Preferences {
+        timezone: chrono_tz::Tz::from_str(row.get("timezone"))
+            .map_err(|_| Text::new("timezone-invalid"))?,
+        clock: Clock::from_str(row.get("clock")).unwrap_or_default(),
+        date_order: DateOrder::from_str(row.get("date_order")).unwrap_or_default(),
+        week_start: WeekStart::from_str(row.get("week_start")).unwrap_or_default(),
+    })
+}
+
+#[tracing::instrument(err, skip(db))]
+pub async fn has_timezone(db: &sqlx::PgPool, user_id: i64) -> Result<bool, sqlx::Error> {
+    let row: Option<(i64,)> = sqlx::query_as("SELECT id FROM users WHERE id = $1")
+        .bind(user_id)
+        .fetch_optional(db)
+        .await?;
+    Ok(row.is_some())
+}
+
+#[tracing::instrument(err, skip(ctx))]
+pub async fn create_scrim(ctx: Context<'_>, lfs: LookingForScrim) -> Result<i32, sqlx::Error> {
+    create_scrim_in(&ctx.data().db, lfs).await
+}
+
+/// Insert a new scrim and return its id.
+#[tracing::instrument(err, skip(db))]
+pub async fn create_scrim_in<'e>(
+    db: impl sqlx::PgExecutor<'e>,
+    lfs: LookingForScrim,
+) -> Result<i32, sqlx::Error> {
+    let row: (i32,) = sqlx::query_as(
         "INSERT INTO scrims (
-                creator_id, region, platform, rank_from, rank_to, time, match_id, team_name, cancelled
-            ) VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9)",
+                creator_id, region, platform, rank_from, rank_to, time, match_id, team_name, cancelled,
+                auto_match
+            ) VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10)
+            RETURNING id",
     )
     .bind(lfs.creator_id)
     .bind(format!("{:?}", lfs.region))
@@ -49,66 +140,255 @@ pub async fn create_scrim(ctx: Context<'_>, lfs: LookingForScrim) -> Result<(),
     .bind(lfs.match_id)
     .bind(lfs.team_name)
     .bind(lfs.cancelled)
-    .execute(&ctx.data().db)
+    .bind(lfs.auto_match)
+    .fetch_one(db)
     .await?;
 
-    Ok(())
+    Ok(row.0)
+}
+
+/// Insert several scrims of one creator, all or none, and return them with their ids. Fails if
+/// `policy` refuses overlapping scrims and the postings overlap.
+#[tracing::instrument(err, skip(db))]
+pub async fn create_scrims(
+    db: &sqlx::PgPool,
+    mut postings: Vec<LookingForScrim>,
+    policy: OverlapPolicy,
+) -> Result<Vec<LookingForScrim>, Error> {
+    let mut tx = db.begin().await?;
+    enforce_overlap_policy(&mut tx, &postings, policy).await?;
+    for lfs in &mut postings {
+        lfs.id = create_scrim_in(&mut *tx, lfs.clone()).await?;
+    }
+    tx.commit().await?;
+    Ok(postings)
 }
 
 #[tracing::instrument(err, skip(ctx))]
 pub async fn cancel_scrim(ctx: Context<'_>, id: i32) -> Result<(), sqlx::Error> {
-    sqlx::query("UPDATE scrims SET cancelled = TRUE WHERE id = $1")
-        .bind(id)
-        .execute(&ctx.data().db)
-        .await?;
+    cancel_scrim_in(&ctx.data().db, id).await
+}
+
+/// Cancel scrim `id`, remembering if it was matched and cancelled less than a day before the start.
+#[tracing::instrument(err, skip(db))]
+pub async fn cancel_scrim_in(db: &sqlx::PgPool, id: i32) -> Result<(), sqlx::Error> {
+    sqlx::query(
+        "UPDATE scrims SET cancelled = TRUE,
+            late_cancelled = late_cancelled
+                OR (match_id IS NOT NULL AND time < NOW() + INTERVAL '1 day')
+        WHERE id = $1",
+    )
+    .bind(id)
+    .execute(db)
+    .await?;
     Ok(())
 }
 
 #[tracing::instrument(err, skip(ctx))]
 pub async fn restore_scrim(ctx: Context<'_>, id: i32) -> Result<(), sqlx::Error> {
+    restore_scrim_in(&ctx.data().db, id).await
+}
+
+/// Undo the cancellation of scrim `id`. A late cancellation still counts against the reliability
+/// of the creator.
+#[tracing::instrument(err, skip(db))]
+pub async fn restore_scrim_in(db: &sqlx::PgPool, id: i32) -> Result<(), sqlx::Error> {
     sqlx::query("UPDATE scrims SET cancelled = FALSE WHERE id = $1")
         .bind(id)
-        .execute(&ctx.data().db)
+        .execute(db)
         .await?;
     Ok(())
 }
 
 #[tracing::instrument(err, skip(ctx))]
 pub async fn revoke_scrim(ctx: Context<'_>, id: i32) -> Result<(), sqlx::Error> {
-    sqlx::query("UPDATE scrims SET match_id = NULL WHERE id = $1")
-        .bind(id)
-        .execute(&ctx.data().db)
-        .await?;
+    revoke_scrim_in(&ctx.data().db, id).await
+}
+
+/// Unmatch scrim `id`. Its partner is unmatched as well if it is still matched with it.
+#[tracing::instrument(err, skip(db))]
+pub async fn revoke_scrim_in(db: &sqlx::PgPool, id: i32) -> Result<(), sqlx::Error> {
+    sqlx::query(
+        "UPDATE scrims SET match_id = NULL
+        WHERE id = $1 OR (match_id = $1 AND id = (SELECT match_id FROM scrims WHERE id = $1))",
+    )
+    .bind(id)
+    .execute(db)
+    .await?;
     Ok(())
 }
 
 #[tracing::instrument(err, skip(ctx))]
 pub async fn get_future_scrims(ctx: Context<'_>) -> Result<Vec<LookingForScrim>, sqlx::Error> {
-    sqlx::query("SELECT * FROM scrims WHERE creator_id = $1 AND time >= NOW() AND NOT cancelled")
-        .bind(ctx.author().id.get() as i64)
-        .fetch(&ctx.data().db)
-        .map(|row| row.map(row_to_lfs))
-        .try_collect()
-        .await
+    get_future_scrims_in(&ctx.data().db, ctx.author().id.get() as i64).await
+}
+
+#[tracing::instrument(err, skip(db))]
+pub async fn get_future_scrims_in(
+    db: &sqlx::PgPool,
+    creator_id: i64,
+) -> Result<Vec<LookingForScrim>, sqlx::Error> {
+    sqlx::query(&format!(
+        "{SELECT_SCRIMS} WHERE scrims.creator_id = $1 AND time >= NOW() AND NOT cancelled"
+    ))
+    .bind(creator_id)
+    .fetch(db)
+    .map(|row| row.map(row_to_lfs))
+    .try_collect()
+    .await
 }
 
 pub async fn get_scrim(ctx: Context<'_>, id: i32) -> Result<LookingForScrim, sqlx::Error> {
-    let row = sqlx::query("SELECT * FROM scrims WHERE id = $1")
-        .bind(id)
-        .fetch_one(&ctx.data().db)
+    get_scrim_in(&ctx.data().db, id).await
+}
+
+/// Match scrim `id` with `to` and the other way around and return the times of other matched
+/// scrims of the team of `id` it overlaps with. Fails if either was cancelled, has started or is
+/// matched with another scrim in the meantime, or if `policy` refuses the overlaps of either team.
+#[tracing::instrument(err, skip(db))]
+pub async fn match_scrims_in(
+    db: &sqlx::PgPool,
+    id: i32,
+    to: i32,
+    policy: OverlapPolicy,
+) -> Result<Vec<DateTime<Utc>>, Error> {
+    let mut tx = db.begin().await?;
+    let available = sqlx::query(
+        "SELECT * FROM scrims
+        WHERE ((id = $1 AND (match_id IS NULL OR match_id = $2))
+            OR (id = $2 AND (match_id IS NULL OR match_id = $1)))
+            AND NOT cancelled AND time > NOW()
+        FOR UPDATE",
+    )
+    .bind(id)
+    .bind(to)
+    .fetch_all(&mut *tx)
+    .await?;
+    let (Some(scrim), Some(other)) = pair_of(available, id, Some(to)) else {
+        return Err(Text::new("scrims-unavailable").into());
+    };
+    let times = check_match_overlaps(&mut tx, &scrim, Some(&other), None, policy).await?;
+    sqlx::query(
+        "UPDATE scrims SET match_id = CASE WHEN id = $1 THEN $2 ELSE $1 END
+        WHERE id IN ($1, $2)",
+    )
+    .bind(id)
+    .bind(to)
+    .execute(&mut *tx)
+    .await?;
+    tx.commit().await?;
+    Ok(times)
+}
+
+/// The scrims `id` and `other_id` among `rows`.
+fn pair_of(
+    rows: Vec<PgRow>,
+    id: i32,
+    other_id: Option<i32>,
+) -> (Option<LookingForScrim>, Option<LookingForScrim>) {
+    let (mut scrim, mut other) = (None, None);
+    for lfs in rows.into_iter().map(row_to_lfs) {
+        if lfs.id == id {
+            scrim = Some(lfs);
+        } else if Some(lfs.id) == other_id {
+            other = Some(lfs);
+        }
+    }
+    (scrim, other)
+}
+
+/// Insert a new scrim matched with the open posting `to`, which is matched back, in place of the
+/// scrim `replaced`. Returns the times of other matched scrims of the team it overlaps with. Fails
+/// if `to` isn't open anymore or `policy` refuses the overlaps of either team.
+#[tracing::instrument(err, skip(db))]
+pub async fn create_matched_scrim(
+    db: &sqlx::PgPool,
+    lfs: LookingForScrim,
+    to: i32,
+    replaced: Option<i32>,
+    policy: OverlapPolicy,
+) -> Result<Vec<DateTime<Utc>>, Error> {
+    let mut tx = db.begin().await?;
+    let times = create_matched_scrim_in(&mut tx, lfs, to, replaced, policy).await?;
+    tx.commit().await?;
+    Ok(times)
+}
+
+/// Times of other matched scrims of the team of `lfs` that overlap with it, apart from `replaced`.
+/// Fails if `policy` refuses overlaps of the team or of the team of `other`, which `lfs` is about
+/// to play. Both creators stay locked until the end of the transaction, so scrims matched at the
+/// same time can't miss each other.
+async fn check_match_overlaps(
+    tx: &mut sqlx::PgConnection,
+    lfs: &LookingForScrim,
+    other: Option<&LookingForScrim>,
+    replaced: Option<i32>,
+    policy: OverlapPolicy,
+) -> Result<Vec<DateTime<Utc>>, Error> {
+    let creators: Vec<i64> = std::iter::once(lfs)
+        .chain(other)
+        .map(|scrim| scrim.creator_id)
+        .collect();
+    sqlx::query("SELECT pg_advisory_xact_lock(id) FROM UNNEST($1::BIGINT[]) id ORDER BY id")
+        .bind(creators)
+        .execute(&mut *tx)
         .await?;
-    Ok(row_to_lfs(row))
+    let times: Vec<_> = get_overlapping_scrims(&mut *tx, lfs, SCRIM_DURATION, true)
+        .await?
+        .into_iter()
+        .filter(|scrim| Some(scrim.id) != replaced)
+        .map(|scrim| scrim.time)
+        .collect();
+    if policy != OverlapPolicy::Block {
+        return Ok(times);
+    }
+    if !times.is_empty() {
+        return Err(overlaps::overlap_blocked(&times).into());
+    }
+    if let Some(other) = other {
+        if !get_overlapping_scrims(&mut *tx, other, SCRIM_DURATION, true)
+            .await?
+            .is_empty()
+        {
+            return Err(Text::new("overlap-partner-blocked").into());
+        }
+    }
+    Ok(times)
 }
 
-/// Match two scrims together. Only the first one with is updated, the second one is left as is.
-/// If the second scrim is available, it will be returned.
-pub async fn match_scrims(ctx: Context<'_>, id: i32, to: i32) -> Result<(), sqlx::Error> {
+/// Insert `lfs` matched with the open posting `to` and match `to` back. Returns the times of other
+/// matched scrims of its team it overlaps with, apart from `replaced`.
+async fn create_matched_scrim_in(
+    tx: &mut sqlx::PgConnection,
+    lfs: LookingForScrim,
+    to: i32,
+    replaced: Option<i32>,
+    policy: OverlapPolicy,
+) -> Result<Vec<DateTime<Utc>>, Error> {
+    let Some(other) = sqlx::query(
+        "SELECT * FROM scrims
+        WHERE id = $1 AND match_id IS NULL AND NOT cancelled AND time > NOW()
+        FOR UPDATE",
+    )
+    .bind(to)
+    .map(row_to_lfs)
+    .fetch_optional(&mut *tx)
+    .await?
+    else {
+        return Err(Text::new("scrim-unavailable").into());
+    };
+    let times = check_match_overlaps(tx, &lfs, Some(&other), replaced, policy).await?;
+    let lfs = LookingForScrim {
+        match_id: Some(to),
+        ..lfs
+    };
+    let id = create_scrim_in(&mut *tx, lfs).await?;
     sqlx::query("UPDATE scrims SET match_id = $2 WHERE id = $1")
-        .bind(id)
         .bind(to)
-        .execute(&ctx.data().db)
+        .bind(id)
+        .execute(&mut *tx)
         .await?;
-    Ok(())
+    Ok(times)
 }
 
 fn row_to_lfs(row: PgRow) -> LookingForScrim {
@@ -124,6 +404,10 @@ fn row_to_lfs(row: PgRow) -> LookingForScrim {
         time: row.get("time"),
         match_id: row.get("match_id"),
         cancelled: row.get("cancelled"),
+        checked_in: row.get("checked_in"),
+        no_show: row.get("no_show"),
+        reliability: row.try_get("reliability").ok().flatten(),
+        auto_match: row.get("auto_match"),
     }
 }
 
@@ -131,26 +415,41 @@ fn row_to_lfs(row: PgRow) -> LookingForScrim {
 pub async fn find_matches(
     ctx: Context<'_>,
     lfs: &LookingForScrim,
+    offset: i64,
+    limit: i64,
+) -> Result<Vec<(f32, LookingForScrim)>, sqlx::Error> {
+    find_matches_in(&ctx.data().db, lfs, offset, limit).await
+}
+
+#[tracing::instrument(err, skip(db))]
+pub async fn find_matches_in(
+    db: &sqlx::PgPool,
+    lfs: &LookingForScrim,
+    offset: i64,
+    limit: i64,
 ) -> Result<Vec<(f32, LookingForScrim)>, sqlx::Error> {
     let rank_weight = 1;
     let time_weight = 1. / 3600. * 500.;
     let region_weight = 500;
     let platform_weight = 200;
-    sqlx::query(
-        "SELECT *,
+    let reliability_weight = 1000;
+    sqlx::query(&format!(
+        "SELECT scrims.*, reliability.score AS reliability,
         (
             ABS((rank_from + rank_to) / 2 - $1) * $2 +
             ABS(EXTRACT(epoch FROM time - $3)) * $4 +
             (region != $5)::INT * $6 +
             (platform != $7)::INT * $8 +
+            (1 - COALESCE(reliability.score, 1)) * $11 +
 
             (NOT match_id IS NULL AND match_id = 7)::INT * -10000000
         )::FLOAT4 AS difference
-        FROM scrims
-        WHERE creator_id != $10 AND time >= NOW() AND NOT cancelled AND (match_id IS NULL OR match_id = $9)
-        ORDER BY difference ASC LIMIT 5
-        ",
-    )
+        FROM scrims LEFT JOIN reliability ON reliability.creator_id = scrims.creator_id
+        WHERE scrims.creator_id != $10 AND time >= NOW() AND NOT cancelled AND (match_id IS NULL OR match_id = $9)
+            AND {NOT_RESERVED}
+        ORDER BY difference ASC LIMIT $12 OFFSET $13
+        "
+    ))
     .bind(((lfs.range.0.start + lfs.range.0.end) / 2) as i32)
     .bind(rank_weight)
     .bind(lfs.time)
@@ -161,8 +460,1673 @@ pub async fn find_matches(
     .bind(platform_weight)
     .bind(lfs.id)
     .bind(lfs.creator_id)
-    .fetch(&ctx.data().db)
+    .bind(reliability_weight)
+    .bind(limit)
+    .bind(offset)
+    .fetch(db)
     .map(|row| row.map(|row| (row.get("difference"), row_to_lfs(row))))
     .try_collect()
     .await
 }
+
+#[tracing::instrument(err, skip(ctx))]
+pub async fn set_reminder_offsets(ctx: Context<'_>, offsets: &[i32]) -> Result<(), Error> {
+    let result = sqlx::query("UPDATE users SET reminder_offsets = $2 WHERE id = $1")
+        .bind(ctx.author().id.get() as i64)
+        .bind(offsets)
+        .execute(&ctx.data().db)
+        .await?;
+    if result.rows_affected() == 0 {
+        return Err(Text::new("timezone-missing").into());
+    }
+    Ok(())
+}
+
+#[tracing::instrument(err, skip(ctx))]
+pub async fn set_language(ctx: Context<'_>, language: Option<Locale>) -> Result<(), Error> {
+    let result = sqlx::query("UPDATE users SET language = $2 WHERE id = $1")
+        .bind(ctx.author().id.get() as i64)
+        .bind(language.map(Locale::code))
+        .execute(&ctx.data().db)
+        .await?;
+    if result.rows_affected() == 0 {
+        return Err(Text::new("timezone-missing").into());
+    }
+    Ok(())
+}
+
+/// Language picked by `user_id`, if any.
+#[tracing::instrument(err, skip(db))]
+pub async fn get_language(db: &sqlx::PgPool, user_id: i64) -> Result<Option<Locale>, sqlx::Error> {
+    let row: Option<(Option<String>,)> = sqlx::query_as("SELECT language FROM users WHERE id = $1")
+        .bind(user_id)
+        .fetch_optional(db)
+        .await?;
+    Ok(row.and_then(|(code,)| Locale::from_code(&code?)))
+}
+
+#[tracing::instrument(err, skip(ctx))]
+pub async fn get_reminder_offsets(ctx: Context<'_>) -> Result<Vec<i32>, sqlx::Error> {
+    let row: (Vec<i32>,) = sqlx::query_as("SELECT reminder_offsets FROM users WHERE id = $1")
+        .bind(ctx.author().id.get() as i64)
+        .fetch_optional(&ctx.data().db)
+        .await?
+        .unwrap_or_else(|| (vec![24 * 60, 30],));
+    Ok(row.0)
+}
+
+#[derive(Debug, sqlx::FromRow)]
+pub struct DueReminder {
+    pub scrim_id: i32,
+    pub other_id: i32,
+    pub offset_minutes: i32,
+}
+
+/// All reminders whose offset has been reached but that haven't been sent yet.
+#[tracing::instrument(err, skip(db))]
+pub async fn get_due_reminders(db: &sqlx::PgPool) -> Result<Vec<DueReminder>, sqlx::Error> {
+    sqlx::query_as(
+        "SELECT m.id AS scrim_id, m.other_id, r.offset_minutes
+        FROM matched_scrims m
+        JOIN users u ON u.id = m.creator_id
+        CROSS JOIN LATERAL UNNEST(u.reminder_offsets) AS r(offset_minutes)
+        WHERE NOT m.cancelled AND m.time > NOW()
+            AND m.time - make_interval(mins => r.offset_minutes) <= NOW()
+            AND NOT EXISTS (
+                SELECT 1 FROM sent_reminders s
+                WHERE s.scrim_id = m.id AND s.offset_minutes = r.offset_minutes
+            )
+        ORDER BY m.id, r.offset_minutes",
+    )
+    .fetch_all(db)
+    .await
+}
+
+/// Mark a reminder as sent. Returns `false` if it had already been marked before.
+#[tracing::instrument(err, skip(db))]
+pub async fn mark_reminder_sent(
+    db: &sqlx::PgPool,
+    scrim_id: i32,
+    offset_minutes: i32,
+) -> Result<bool, sqlx::Error> {
+    let result = sqlx::query(
+        "INSERT INTO sent_reminders (scrim_id, offset_minutes) VALUES ($1, $2) ON CONFLICT DO NOTHING",
+    )
+    .bind(scrim_id)
+    .bind(offset_minutes)
+    .execute(db)
+    .await?;
+    Ok(result.rows_affected() == 1)
+}
+
+pub async fn get_scrim_in(db: &sqlx::PgPool, id: i32) -> Result<LookingForScrim, sqlx::Error> {
+    let row = sqlx::query(&format!("{SELECT_SCRIMS} WHERE id = $1"))
+        .bind(id)
+        .fetch_one(db)
+        .await?;
+    Ok(row_to_lfs(row))
+}
+
+#[derive(Debug, sqlx::FromRow)]
+pub struct CheckIn {
+    pub scrim_id: i32,
+    pub other_id: i32,
+    pub checked_in: bool,
+    pub other_checked_in: bool,
+}
+
+/// Matched scrims that start within `window_minutes` and whose creator hasn't been asked to check
+/// in yet.
+#[tracing::instrument(err, skip(db))]
+pub async fn get_check_ins_to_prompt(
+    db: &sqlx::PgPool,
+    window_minutes: i32,
+) -> Result<Vec<CheckIn>, sqlx::Error> {
+    sqlx::query_as(
+        "SELECT m.id AS scrim_id, m.other_id, s.checked_in, o.checked_in AS other_checked_in
+        FROM matched_scrims m
+        JOIN scrims s ON s.id = m.id
+        JOIN scrims o ON o.id = m.other_id
+        WHERE NOT m.cancelled AND NOT s.check_in_prompted
+            AND m.time > NOW() AND m.time <= NOW() + make_interval(mins => $1)",
+    )
+    .bind(window_minutes)
+    .fetch_all(db)
+    .await
+}
+
+/// Matched scrims that started within the last `grace_minutes` and whose check-in hasn't been
+/// evaluated yet.
+#[tracing::instrument(err, skip(db))]
+pub async fn get_check_ins_to_resolve(
+    db: &sqlx::PgPool,
+    grace_minutes: i32,
+) -> Result<Vec<CheckIn>, sqlx::Error> {
+    sqlx::query_as(
+        "SELECT m.id AS scrim_id, m.other_id, s.checked_in, o.checked_in AS other_checked_in
+        FROM matched_scrims m
+        JOIN scrims s ON s.id = m.id
+        JOIN scrims o ON o.id = m.other_id
+        WHERE NOT m.cancelled AND NOT s.check_in_resolved
+            AND m.time <= NOW() AND m.time > NOW() - make_interval(mins => $1)",
+    )
+    .bind(grace_minutes)
+    .fetch_all(db)
+    .await
+}
+
+/// Returns `false` if the prompt had already been marked as sent.
+#[tracing::instrument(err, skip(db))]
+pub async fn mark_check_in_prompted(db: &sqlx::PgPool, id: i32) -> Result<bool, sqlx::Error> {
+    let result = sqlx::query(
+        "UPDATE scrims SET check_in_prompted = TRUE WHERE id = $1 AND NOT check_in_prompted",
+    )
+    .bind(id)
+    .execute(db)
+    .await?;
+    Ok(result.rows_affected() == 1)
+}
+
+/// Returns `false` if the check-in had already been marked as resolved.
+#[tracing::instrument(err, skip(db))]
+pub async fn mark_check_in_resolved(db: &sqlx::PgPool, id: i32) -> Result<bool, sqlx::Error> {
+    let result = sqlx::query(
+        "UPDATE scrims SET check_in_resolved = TRUE WHERE id = $1 AND NOT check_in_resolved",
+    )
+    .bind(id)
+    .execute(db)
+    .await?;
+    Ok(result.rows_affected() == 1)
+}
+
+/// Check in for scrim `id`. Returns `false` if it isn't matched.
+#[tracing::instrument(err, skip(db))]
+pub async fn check_in(db: &sqlx::PgPool, id: i32) -> Result<bool, sqlx::Error> {
+    let result = sqlx::query(
+        "UPDATE scrims SET checked_in = TRUE
+        WHERE id = $1 AND EXISTS (SELECT 1 FROM matched_scrims WHERE id = $1 AND NOT cancelled)",
+    )
+    .bind(id)
+    .execute(db)
+    .await?;
+    Ok(result.rows_affected() == 1)
+}
+
+/// Matched scrims of the author that started within the last `days` days and weren't cancelled,
+/// together with their partner.
+#[tracing::instrument(err, skip(ctx))]
+pub async fn get_recent_matches(
+    ctx: Context<'_>,
+    days: i32,
+) -> Result<Vec<(LookingForScrim, LookingForScrim)>, sqlx::Error> {
+    let ids: Vec<(i32, i32)> = sqlx::query_as(
+        "SELECT id, other_id FROM matched_scrims
+        WHERE creator_id = $1 AND NOT cancelled
+            AND time <= NOW() AND time > NOW() - make_interval(days => $2)
+        ORDER BY time DESC",
+    )
+    .bind(ctx.author().id.get() as i64)
+    .bind(days)
+    .fetch_all(&ctx.data().db)
+    .await?;
+
+    let mut matches = Vec::with_capacity(ids.len());
+    for (id, other_id) in ids {
+        matches.push((get_scrim(ctx, id).await?, get_scrim(ctx, other_id).await?));
+    }
+    Ok(matches)
+}
+
+/// Report that the partner of a scrim of `reporter_id` didn't show up. Partners who checked in
+/// can't be reported.
+#[tracing::instrument(err, skip(db))]
+pub async fn report_no_show(
+    db: &sqlx::PgPool,
+    reporter_id: i64,
+    scrim_id: i32,
+) -> Result<(), Error> {
+    let partner: Option<(bool,)> = sqlx::query_as(
+        "WITH partner AS (
+            SELECT other_id FROM matched_scrims
+            WHERE id = $1 AND creator_id = $2 AND NOT cancelled AND time <= NOW()
+        )
+        UPDATE scrims SET no_show = no_show OR NOT checked_in
+        WHERE id = (SELECT other_id FROM partner)
+        RETURNING checked_in",
+    )
+    .bind(scrim_id)
+    .bind(reporter_id)
+    .fetch_optional(db)
+    .await?;
+    match partner {
+        None => Err(Text::new("no-show-not-allowed").into()),
+        Some((true,)) => Err(Text::new("no-show-checked-in").into()),
+        Some((false,)) => Ok(()),
+    }
+}
+
+/// Matched scrims that ended within the last `grace_minutes` and whose creator hasn't been asked for
+/// the result yet. Returns the ids of the scrims and their partners.
+#[tracing::instrument(err, skip(db))]
+pub async fn get_results_to_prompt(
+    db: &sqlx::PgPool,
+    duration_minutes: i32,
+    grace_minutes: i32,
+) -> Result<Vec<(i32, i32)>, sqlx::Error> {
+    sqlx::query_as(
+        "SELECT m.id, m.other_id
+        FROM matched_scrims m
+        JOIN scrims s ON s.id = m.id
+        WHERE NOT m.cancelled AND NOT s.result_prompted AND NOT s.no_show
+            AND m.time + make_interval(mins => $1) <= NOW()
+            AND m.time + make_interval(mins => $1 + $2) > NOW()",
+    )
+    .bind(duration_minutes)
+    .bind(grace_minutes)
+    .fetch_all(db)
+    .await
+}
+
+/// Returns `false` if the prompt had already been marked as sent.
+#[tracing::instrument(err, skip(db))]
+pub async fn mark_result_prompted(db: &sqlx::PgPool, id: i32) -> Result<bool, sqlx::Error> {
+    let result = sqlx::query(
+        "UPDATE scrims SET result_prompted = TRUE WHERE id = $1 AND NOT result_prompted",
+    )
+    .bind(id)
+    .execute(db)
+    .await?;
+    Ok(result.rows_affected() == 1)
+}
+
+/// Save the result of a scrim of `reporter_id`, replacing an earlier report.
+#[tracing::instrument(err, skip(db))]
+pub async fn save_result(
+    db: &sqlx::PgPool,
+    reporter_id: i64,
+    scrim_id: i32,
+    result: &ScrimResult,
+) -> Result<(), Error> {
+    let query_result = sqlx::query(
+        "INSERT INTO scrim_results (scrim_id, maps_won, maps_lost, details, rating, comment)
+        SELECT $1, $3, $4, $5, $6, $7
+        WHERE EXISTS (
+            SELECT 1 FROM matched_scrims
+            WHERE id = $1 AND creator_id = $2 AND NOT cancelled AND time <= NOW()
+        )
+        ON CONFLICT (scrim_id) DO UPDATE SET
+            maps_won = $3, maps_lost = $4, details = $5, rating = $6, comment = $7,
+            reported_at = NOW()",
+    )
+    .bind(scrim_id)
+    .bind(reporter_id)
+    .bind(result.maps_won)
+    .bind(result.maps_lost)
+    .bind(&result.details)
+    .bind(result.rating)
+    .bind(&result.comment)
+    .execute(db)
+    .await?;
+    if query_result.rows_affected() == 0 {
+        return Err(Text::new("report-not-allowed").into());
+    }
+    Ok(())
+}
+
+/// Id of a scrim and of its partner, if it has one.
+pub type ScrimPair = (i32, Option<i32>);
+
+/// A page of past and cancelled scrims of `creator_id` matching `filter`, newest first. Returns the
+/// ids of the scrims and their partners, together with the total number of matching scrims.
+#[tracing::instrument(err, skip(db))]
+pub async fn get_history(
+    db: &sqlx::PgPool,
+    creator_id: i64,
+    filter: &HistoryFilter,
+    offset: i64,
+    limit: i64,
+) -> Result<(Vec<ScrimPair>, i64), sqlx::Error> {
+    let rows: Vec<(i32, Option<i32>, i64)> = sqlx::query_as(
+        "SELECT s.id, m.other_id, COUNT(*) OVER () AS total
+        FROM scrims s
+        LEFT JOIN matched_scrims m ON m.id = s.id
+        LEFT JOIN scrims o ON o.id = m.other_id
+        WHERE (m.id IS NOT NULL OR s.cancelled) AND (s.time < NOW() OR s.cancelled)
+            AND s.creator_id = $1 AND ($2::VARCHAR IS NULL OR LOWER(s.team_name) = LOWER($2))
+            AND ($3::TIMESTAMPTZ IS NULL OR s.time >= $3)
+            AND ($4::TIMESTAMPTZ IS NULL OR s.time < $4)
+            AND ($5::BIGINT IS NULL OR o.creator_id = $5)
+            AND ($6::VARCHAR IS NULL OR o.team_name ILIKE '%' || $6 || '%')
+        ORDER BY s.time DESC
+        LIMIT $7 OFFSET $8",
+    )
+    .bind(creator_id)
+    .bind(&filter.team_name)
+    .bind(filter.from)
+    .bind(filter.to)
+    .bind(filter.opponent_id)
+    .bind(&filter.opponent_team)
+    .bind(limit)
+    .bind(offset)
+    .fetch_all(db)
+    .await?;
+
+    let total = rows.first().map_or(0, |row| row.2);
+    Ok((
+        rows.into_iter()
+            .map(|(id, other_id, _)| (id, other_id))
+            .collect(),
+        total,
+    ))
+}
+
+/// Store the filter of a `/history` reply of `creator_id` and return its id.
+#[tracing::instrument(err, skip(db))]
+pub async fn create_history_filter(
+    db: &sqlx::PgPool,
+    creator_id: i64,
+    filter: &HistoryFilter,
+) -> Result<i32, sqlx::Error> {
+    sqlx::query_scalar(
+        "INSERT INTO history_filters
+        (creator_id, team_name, from_time, to_time, opponent_id, opponent_team)
+        VALUES ($1, $2, $3, $4, $5, $6) RETURNING id",
+    )
+    .bind(creator_id)
+    .bind(&filter.team_name)
+    .bind(filter.from)
+    .bind(filter.to)
+    .bind(filter.opponent_id)
+    .bind(&filter.opponent_team)
+    .fetch_one(db)
+    .await
+}
+
+/// Filter `id` of a `/history` reply of `creator_id`, if it still exists.
+#[tracing::instrument(err, skip(db))]
+pub async fn get_history_filter(
+    db: &sqlx::PgPool,
+    id: i32,
+    creator_id: i64,
+) -> Result<Option<HistoryFilter>, sqlx::Error> {
+    let row = sqlx::query("SELECT * FROM history_filters WHERE id = $1 AND creator_id = $2")
+        .bind(id)
+        .bind(creator_id)
+        .fetch_optional(db)
+        .await?;
+    Ok(row.map(|row| HistoryFilter {
+        team_name: row.get("team_name"),
+        from: row.get("from_time"),
+        to: row.get("to_time"),
+        opponent_id: row.get("opponent_id"),
+        opponent_team: row.get("opponent_team"),
+    }))
+}
+
+/// Delete the filters of replies that are too old to be used anymore.
+#[tracing::instrument(err, skip(db))]
+pub async fn delete_stale_filters(db: &sqlx::PgPool) -> Result<(), sqlx::Error> {
+    sqlx::query("DELETE FROM history_filters WHERE created_at < NOW() - INTERVAL '7 days'")
+        .execute(db)
+        .await?;
+    sqlx::query("DELETE FROM browse_filters WHERE created_at < NOW() - INTERVAL '7 days'")
+        .execute(db)
+        .await?;
+    Ok(())
+}
+
+#[tracing::instrument(err, skip(db))]
+pub async fn get_result(
+    db: &sqlx::PgPool,
+    scrim_id: i32,
+) -> Result<Option<ScrimResult>, sqlx::Error> {
+    let row = sqlx::query(
+        "SELECT maps_won, maps_lost, details, rating, comment FROM scrim_results WHERE scrim_id = $1",
+    )
+    .bind(scrim_id)
+    .fetch_optional(db)
+    .await?;
+    Ok(row.map(|row| ScrimResult {
+        maps_won: row.get("maps_won"),
+        maps_lost: row.get("maps_lost"),
+        details: row.get("details"),
+        rating: row.get("rating"),
+        comment: row.get("comment"),
+    }))
+}
+
+/// Past scrims between two creators.
+#[derive(Debug, sqlx::FromRow)]
+pub struct HeadToHead {
+    pub played: i64,
+    pub last: Option<chrono::DateTime<chrono::Utc>>,
+}
+
+#[tracing::instrument(err, skip(ctx))]
+pub async fn get_head_to_head(
+    ctx: Context<'_>,
+    creator_id: i64,
+    other_creator_id: i64,
+) -> Result<HeadToHead, sqlx::Error> {
+    get_head_to_head_in(&ctx.data().db, creator_id, other_creator_id).await
+}
+
+#[tracing::instrument(err, skip(db))]
+pub async fn get_head_to_head_in(
+    db: &sqlx::PgPool,
+    creator_id: i64,
+    other_creator_id: i64,
+) -> Result<HeadToHead, sqlx::Error> {
+    sqlx::query_as(
+        "SELECT COUNT(DISTINCT LEAST(id, other_id)) AS played, MAX(time) AS last
+        FROM matched_scrims
+        WHERE ((creator_id = $1 AND other_creator_id = $2) OR (creator_id = $2 AND other_creator_id = $1))
+            AND NOT cancelled AND time < NOW()",
+    )
+    .bind(creator_id)
+    .bind(other_creator_id)
+    .fetch_one(db)
+    .await
+}
+
+#[tracing::instrument(err, skip(db))]
+pub async fn create_invite(
+    db: &sqlx::PgPool,
+    scrim_id: i32,
+    target_id: i64,
+    target_team_name: Option<&str>,
+) -> Result<i32, sqlx::Error> {
+    let row: (i32,) = sqlx::query_as(
+        "INSERT INTO invites (scrim_id, target_id, target_team_name) VALUES ($1, $2, $3) RETURNING id",
+    )
+    .bind(scrim_id)
+    .bind(target_id)
+    .bind(target_team_name)
+    .fetch_one(db)
+    .await?;
+    Ok(row.0)
+}
+
+#[tracing::instrument(err, skip(db))]
+pub async fn get_invite(db: &sqlx::PgPool, id: i32) -> Result<Invite, sqlx::Error> {
+    let row = sqlx::query("SELECT * FROM invites WHERE id = $1")
+        .bind(id)
+        .fetch_one(db)
+        .await?;
+    Ok(Invite {
+        id: row.get("id"),
+        scrim_id: row.get("scrim_id"),
+        target_id: row.get("target_id"),
+        target_team_name: row.get("target_team_name"),
+        status: row.get::<&str, _>("status").parse().unwrap(),
+    })
+}
+
+/// Answer a pending invite. Returns `false` if it had already been answered.
+#[tracing::instrument(err, skip(db))]
+pub async fn answer_invite<'e>(
+    db: impl sqlx::PgExecutor<'e>,
+    id: i32,
+    status: InviteStatus,
+) -> Result<bool, sqlx::Error> {
+    let result = sqlx::query("UPDATE invites SET status = $2 WHERE id = $1 AND status = $3")
+        .bind(id)
+        .bind(format!("{status:?}"))
+        .bind(format!("{:?}", InviteStatus::Pending))
+        .execute(db)
+        .await?;
+    Ok(result.rows_affected() == 1)
+}
+
+/// Accept an invite by creating the posting of the invited side and matching both postings with
+/// each other. Returns the times of other matched scrims of the invited team the new posting
+/// overlaps with. Fails if `policy` refuses the overlaps of either team.
+#[tracing::instrument(err, skip(db))]
+pub async fn accept_invite(
+    db: &sqlx::PgPool,
+    invite: &Invite,
+    lfs: LookingForScrim,
+    policy: OverlapPolicy,
+) -> Result<Vec<DateTime<Utc>>, Error> {
+    let mut tx = db.begin().await?;
+    let times = accept_invite_in(&mut tx, invite, lfs, policy).await?;
+    tx.commit().await?;
+    Ok(times)
+}
+
+async fn accept_invite_in(
+    tx: &mut sqlx::PgConnection,
+    invite: &Invite,
+    lfs: LookingForScrim,
+    policy: OverlapPolicy,
+) -> Result<Vec<DateTime<Utc>>, Error> {
+    if !answer_invite(&mut *tx, invite.id, InviteStatus::Accepted).await? {
+        return Err(Text::new("invite-answered").into());
+    }
+    create_matched_scrim_in(tx, lfs, invite.scrim_id, None, policy).await
+}
+
+/// Team names used in postings containing `partial`.
+#[tracing::instrument(err, skip(ctx))]
+pub async fn get_team_names(ctx: Context<'_>, partial: &str) -> Result<Vec<String>, sqlx::Error> {
+    sqlx::query_scalar(
+        "SELECT team_name FROM scrims
+        WHERE team_name ILIKE '%' || $1 || '%'
+        GROUP BY team_name ORDER BY MAX(time) DESC LIMIT 25",
+    )
+    .bind(partial)
+    .fetch_all(&ctx.data().db)
+    .await
+}
+
+/// The creator who most recently posted with a team name.
+#[tracing::instrument(err, skip(ctx))]
+pub async fn get_team_creator(
+    ctx: Context<'_>,
+    team_name: &str,
+) -> Result<Option<i64>, sqlx::Error> {
+    sqlx::query_scalar(
+        "SELECT creator_id FROM scrims WHERE LOWER(team_name) = LOWER($1)
+        ORDER BY time DESC LIMIT 1",
+    )
+    .bind(team_name)
+    .fetch_optional(&ctx.data().db)
+    .await
+}
+
+/// The team name a creator most recently posted with.
+#[tracing::instrument(err, skip(ctx))]
+pub async fn get_latest_team_name(
+    ctx: Context<'_>,
+    creator_id: i64,
+) -> Result<Option<String>, sqlx::Error> {
+    get_latest_team_name_in(&ctx.data().db, creator_id).await
+}
+
+/// Latest posting of `creator_id`, only among those with `team_name` if given.
+#[tracing::instrument(err, skip(db))]
+pub async fn get_latest_posting(
+    db: &sqlx::PgPool,
+    creator_id: i64,
+    team_name: Option<&str>,
+) -> Result<Option<LookingForScrim>, sqlx::Error> {
+    let row = sqlx::query(&format!(
+        "{SELECT_SCRIMS} WHERE scrims.creator_id = $1
+            AND ($2::VARCHAR IS NULL OR scrims.team_name ILIKE $2)
+        ORDER BY time DESC LIMIT 1"
+    ))
+    .bind(creator_id)
+    .bind(team_name)
+    .fetch_optional(db)
+    .await?;
+    Ok(row.map(row_to_lfs))
+}
+
+#[tracing::instrument(err, skip(db))]
+pub async fn get_latest_team_name_in(
+    db: &sqlx::PgPool,
+    creator_id: i64,
+) -> Result<Option<String>, sqlx::Error> {
+    let row: Option<(Option<String>,)> = sqlx::query_as(
+        "SELECT team_name FROM scrims WHERE creator_id = $1 ORDER BY time DESC LIMIT 1",
+    )
+    .bind(creator_id)
+    .fetch_optional(db)
+    .await?;
+    Ok(row.and_then(|row| row.0))
+}
+
+#[tracing::instrument(err, skip(db))]
+pub async fn create_proposal(db: &sqlx::PgPool, proposal: &Proposal) -> Result<i32, sqlx::Error> {
+    sqlx::query_scalar(
+        "INSERT INTO proposals (scrim_id, other_id, invite_id, proposer_id, target_id, time)
+        VALUES ($1, $2, $3, $4, $5, $6) RETURNING id",
+    )
+    .bind(proposal.scrim_id)
+    .bind(proposal.other_id)
+    .bind(proposal.invite_id)
+    .bind(proposal.proposer_id)
+    .bind(proposal.target_id)
+    .bind(proposal.time)
+    .fetch_one(db)
+    .await
+}
+
+#[tracing::instrument(err, skip(db))]
+pub async fn get_proposal(db: &sqlx::PgPool, id: i32) -> Result<Proposal, sqlx::Error> {
+    let row = sqlx::query("SELECT * FROM proposals WHERE id = $1")
+        .bind(id)
+        .fetch_one(db)
+        .await?;
+    Ok(Proposal {
+        id: row.get("id"),
+        scrim_id: row.get("scrim_id"),
+        other_id: row.get("other_id"),
+        invite_id: row.get("invite_id"),
+        proposer_id: row.get("proposer_id"),
+        target_id: row.get("target_id"),
+        time: row.get("time"),
+        status: row.get::<&str, _>("status").parse().unwrap(),
+    })
+}
+
+/// Answer a pending proposal. Returns `false` if it had already been answered.
+#[tracing::instrument(err, skip(db))]
+pub async fn answer_proposal<'e>(
+    db: impl sqlx::PgExecutor<'e>,
+    id: i32,
+    status: InviteStatus,
+) -> Result<bool, sqlx::Error> {
+    let result = sqlx::query("UPDATE proposals SET status = $2 WHERE id = $1 AND status = $3")
+        .bind(id)
+        .bind(format!("{status:?}"))
+        .bind(format!("{:?}", InviteStatus::Pending))
+        .execute(db)
+        .await?;
+    Ok(result.rows_affected() == 1)
+}
+
+/// Move a scrim to `time`. Reminders, the check-in and the result prompt are sent again for the
+/// new time.
+#[tracing::instrument(err, skip(db))]
+pub async fn set_scrim_time<'e>(
+    db: impl sqlx::PgExecutor<'e>,
+    id: i32,
+    time: chrono::DateTime<chrono::Utc>,
+) -> Result<(), sqlx::Error> {
+    sqlx::query(
+        "WITH sent AS (DELETE FROM sent_reminders WHERE scrim_id = $1)
+        UPDATE scrims SET time = $2, check_in_prompted = FALSE, check_in_resolved = FALSE,
+            result_prompted = FALSE
+        WHERE id = $1",
+    )
+    .bind(id)
+    .bind(time)
+    .execute(db)
+    .await?;
+    Ok(())
+}
+
+/// Accept a pending proposal and move its posting to the proposed time. If the proposal involves
+/// the posting of the other side, it is moved as well and both are matched with each other. If it
+/// answers an invite, the invite is accepted with the given posting of the invited side. Returns
+/// the times of other matched scrims of the team of the target the match overlaps with. Fails if
+/// the postings aren't available anymore, the proposed time has passed or `policy` refuses the
+/// overlaps of either team.
+#[tracing::instrument(err, skip(db))]
+pub async fn accept_proposal(
+    db: &sqlx::PgPool,
+    proposal: &Proposal,
+    invited: Option<(Invite, LookingForScrim)>,
+    policy: OverlapPolicy,
+) -> Result<Vec<DateTime<Utc>>, Error> {
+    let mut tx = db.begin().await?;
+    if !answer_proposal(&mut *tx, proposal.id, InviteStatus::Accepted).await? {
+        return Err(Text::new("proposal-answered").into());
+    }
+
+    // The postings must still be free for each other, or open if the other side has none yet
+    let available = sqlx::query(
+        "SELECT * FROM scrims
+        WHERE ((id = $1 AND (match_id IS NULL OR match_id = $2))
+            OR (id = $2 AND (match_id IS NULL OR match_id = $1)))
+            AND NOT cancelled AND time > NOW() AND $3 > NOW()
+        FOR UPDATE",
+    )
+    .bind(proposal.scrim_id)
+    .bind(proposal.other_id)
+    .bind(proposal.time)
+    .fetch_all(&mut *tx)
+    .await?;
+    let (scrim, other) = pair_of(available, proposal.scrim_id, proposal.other_id);
+    let Some(scrim) = scrim else {
+        return Err(Text::new("scrims-unavailable").into());
+    };
+    if proposal.other_id.is_some() && other.is_none() {
+        return Err(Text::new("scrims-unavailable").into());
+    }
+    set_scrim_time(&mut *tx, proposal.scrim_id, proposal.time).await?;
+
+    let mut times = Vec::new();
+    if let Some(other) = other {
+        let moved = |lfs| LookingForScrim {
+            time: proposal.time,
+            ..lfs
+        };
+        let (scrim, other) = (moved(scrim), moved(other));
+        // The target owns the proposed posting only when it answers a candidate of theirs
+        let (target, partner) = if scrim.creator_id == proposal.target_id {
+            (&scrim, &other)
+        } else {
+            (&other, &scrim)
+        };
+        times = check_match_overlaps(&mut tx, target, Some(partner), None, policy).await?;
+        set_scrim_time(&mut *tx, other.id, proposal.time).await?;
+        sqlx::query(
+            "UPDATE scrims SET match_id = CASE WHEN id = $1 THEN $2 ELSE $1 END
+            WHERE id IN ($1, $2)",
+        )
+        .bind(scrim.id)
+        .bind(other.id)
+        .execute(&mut *tx)
+        .await?;
+    }
+    if let Some((invite, lfs)) = invited {
+        times = accept_invite_in(&mut tx, &invite, lfs, policy).await?;
+    }
+
+    tx.commit().await?;
+    Ok(times)
+}
+
+#[tracing::instrument(err, skip(db))]
+pub async fn set_board_channel(
+    db: &sqlx::PgPool,
+    guild_id: i64,
+    channel_id: Option<i64>,
+) -> Result<(), sqlx::Error> {
+    sqlx::query(
+        "INSERT INTO guilds (id, board_channel_id) VALUES ($1, $2)
+        ON CONFLICT (id) DO UPDATE SET board_channel_id = $2",
+    )
+    .bind(guild_id)
+    .bind(channel_id)
+    .execute(db)
+    .await?;
+    Ok(())
+}
+
+#[tracing::instrument(err, skip(db))]
+pub async fn set_overlap_policy(
+    db: &sqlx::PgPool,
+    guild_id: i64,
+    policy: OverlapPolicy,
+) -> Result<(), sqlx::Error> {
+    sqlx::query(
+        "INSERT INTO guilds (id, overlap_policy) VALUES ($1, $2)
+        ON CONFLICT (id) DO UPDATE SET overlap_policy = $2",
+    )
+    .bind(guild_id)
+    .bind(format!("{policy:?}"))
+    .execute(db)
+    .await?;
+    Ok(())
+}
+
+/// How overlapping scrims are handled in a guild. Outside of guilds they are only warned about.
+#[tracing::instrument(err, skip(db))]
+pub async fn get_overlap_policy(
+    db: &sqlx::PgPool,
+    guild_id: Option<i64>,
+) -> Result<OverlapPolicy, sqlx::Error> {
+    let Some(guild_id) = guild_id else {
+        return Ok(OverlapPolicy::default());
+    };
+    let policy: Option<String> =
+        sqlx::query_scalar("SELECT overlap_policy FROM guilds WHERE id = $1")
+            .bind(guild_id)
+            .fetch_optional(db)
+            .await?;
+    Ok(policy
+        .and_then(|policy| OverlapPolicy::from_str(&policy).ok())
+        .unwrap_or_default())
+}
+
+/// Get the board channel of all guilds that have a board or still have messages on an old one.
+#[tracing::instrument(err, skip(db))]
+pub async fn get_boards(db: &sqlx::PgPool) -> Result<Vec<(i64, Option<i64>)>, sqlx::Error> {
+    sqlx::query_as(
+        "SELECT id, board_channel_id FROM guilds
+        WHERE board_channel_id IS NOT NULL
+            OR EXISTS (SELECT 1 FROM board_messages WHERE guild_id = guilds.id)",
+    )
+    .fetch_all(db)
+    .await
+}
+
+/// Get the open postings that nobody has been matched with yet, soonest first.
+#[tracing::instrument(err, skip(db))]
+pub async fn get_open_scrims(
+    db: &sqlx::PgPool,
+    limit: i64,
+) -> Result<Vec<LookingForScrim>, sqlx::Error> {
+    sqlx::query(&format!(
+        "{SELECT_SCRIMS} WHERE time >= NOW() AND NOT cancelled AND match_id IS NULL AND {NOT_RESERVED}
+        ORDER BY time LIMIT $1"
+    ))
+    .bind(limit)
+    .fetch(db)
+    .map(|row| row.map(row_to_lfs))
+    .try_collect()
+    .await
+}
+
+/// A page of open postings of others than `creator_id` matching `filter`, soonest first, together
+/// with the total number of matching postings.
+#[tracing::instrument(err, skip(db))]
+pub async fn browse_scrims(
+    db: &sqlx::PgPool,
+    creator_id: i64,
+    filter: &BrowseFilter,
+    offset: i64,
+    limit: i64,
+) -> Result<(Vec<LookingForScrim>, i64), sqlx::Error> {
+    let rows = sqlx::query(&format!(
+        "SELECT scrims.*, reliability.score AS reliability, COUNT(*) OVER () AS total
+        FROM scrims LEFT JOIN reliability ON reliability.creator_id = scrims.creator_id
+        WHERE scrims.creator_id != $1 AND time >= NOW() AND NOT cancelled AND match_id IS NULL
+            AND {NOT_RESERVED}
+            AND ($2::VARCHAR IS NULL OR region = $2)
+            AND ($3::VARCHAR IS NULL OR platform = $3)
+            AND ($4::INT IS NULL OR (rank_to >= $4 AND rank_from <= $5))
+            AND ($6::TIMESTAMPTZ IS NULL OR time >= $6)
+            AND ($7::TIMESTAMPTZ IS NULL OR time < $7)
+            AND ($8::VARCHAR IS NULL OR team_name ILIKE '%' || $8 || '%')
+        ORDER BY time
+        LIMIT $9 OFFSET $10"
+    ))
+    .bind(creator_id)
+    .bind(filter.region.as_ref().map(|region| format!("{region:?}")))
+    .bind(
+        filter
+            .platform
+            .as_ref()
+            .map(|platform| format!("{platform:?}")),
+    )
+    .bind(filter.range.as_ref().map(|range| range.start as i32))
+    .bind(filter.range.as_ref().map(|range| range.end as i32))
+    .bind(filter.from)
+    .bind(filter.to)
+    .bind(&filter.team_name)
+    .bind(limit)
+    .bind(offset)
+    .fetch_all(db)
+    .await?;
+
+    let total = rows.first().map_or(0, |row| row.get("total"));
+    Ok((rows.into_iter().map(row_to_lfs).collect(), total))
+}
+
+/// Store the filter of a `/browse` reply of `creator_id` and return its id.
+#[tracing::instrument(err, skip(db))]
+pub async fn create_browse_filter(
+    db: &sqlx::PgPool,
+    creator_id: i64,
+    filter: &BrowseFilter,
+) -> Result<i32, sqlx::Error> {
+    sqlx::query_scalar(
+        "INSERT INTO browse_filters
+        (creator_id, region, platform, rank_from, rank_to, from_time, to_time, team_name)
+        VALUES ($1, $2, $3, $4, $5, $6, $7, $8) RETURNING id",
+    )
+    .bind(creator_id)
+    .bind(filter.region.as_ref().map(|region| format!("{region:?}")))
+    .bind(
+        filter
+            .platform
+            .as_ref()
+            .map(|platform| format!("{platform:?}")),
+    )
+    .bind(filter.range.as_ref().map(|range| range.start as i32))
+    .bind(filter.range.as_ref().map(|range| range.end as i32))
+    .bind(filter.from)
+    .bind(filter.to)
+    .bind(&filter.team_name)
+    .fetch_one(db)
+    .await
+}
+
+/// Filter `id` of a `/browse` reply of `creator_id`, if it still exists.
+#[tracing::instrument(err, skip(db))]
+pub async fn get_browse_filter(
+    db: &sqlx::PgPool,
+    id: i32,
+    creator_id: i64,
+) -> Result<Option<BrowseFilter>, sqlx::Error> {
+    let row = sqlx::query("SELECT * FROM browse_filters WHERE id = $1 AND creator_id = $2")
+        .bind(id)
+        .bind(creator_id)
+        .fetch_optional(db)
+        .await?;
+    Ok(row.map(|row| BrowseFilter {
+        region: row
+            .get::<Option<&str>, _>("region")
+            .and_then(|region| region.parse().ok()),
+        platform: row
+            .get::<Option<&str>, _>("platform")
+            .and_then(|platform| platform.parse().ok()),
+        range: row
+            .get::<Option<i32>, _>("rank_from")
+            .zip(row.get::<Option<i32>, _>("rank_to"))
+            .map(|(from, to)| from as u32..to as u32),
+        from: row.get("from_time"),
+        to: row.get("to_time"),
+        team_name: row.get("team_name"),
+    }))
+}
+
+#[tracing::instrument(err, skip(db))]
+pub async fn get_board_messages(
+    db: &sqlx::PgPool,
+    guild_id: i64,
+) -> Result<Vec<BoardMessage>, sqlx::Error> {
+    sqlx::query("SELECT * FROM board_messages WHERE guild_id = $1")
+        .bind(guild_id)
+        .fetch(db)
+        .map(|row| {
+            row.map(|row| BoardMessage {
+                guild_id: row.get("guild_id"),
+                scrim_id: row.get("scrim_id"),
+                channel_id: row.get("channel_id"),
+                message_id: row.get("message_id"),
+                content: row.get("content"),
+            })
+        })
+        .try_collect()
+        .await
+}
+
+#[tracing::instrument(err, skip(db))]
+pub async fn save_board_message(db: &sqlx::PgPool, msg: &BoardMessage) -> Result<(), sqlx::Error> {
+    sqlx::query(
+        "INSERT INTO board_messages (guild_id, scrim_id, channel_id, message_id, content)
+        VALUES ($1, $2, $3, $4, $5)
+        ON CONFLICT (guild_id, scrim_id)
+        DO UPDATE SET channel_id = $3, message_id = $4, content = $5",
+    )
+    .bind(msg.guild_id)
+    .bind(msg.scrim_id)
+    .bind(msg.channel_id)
+    .bind(msg.message_id)
+    .bind(&msg.content)
+    .execute(db)
+    .await?;
+    Ok(())
+}
+
+#[tracing::instrument(err, skip(db))]
+pub async fn delete_board_message(
+    db: &sqlx::PgPool,
+    guild_id: i64,
+    scrim_id: i32,
+) -> Result<(), sqlx::Error> {
+    sqlx::query("DELETE FROM board_messages WHERE guild_id = $1 AND scrim_id = $2")
+        .bind(guild_id)
+        .bind(scrim_id)
+        .execute(db)
+        .await?;
+    Ok(())
+}
+
+/// Match the open posting `id` with `to` if `to` still picked it. Returns the times of other
+/// matched scrims of the team of `id` it overlaps with, or `None` if they weren't available
+/// anymore. Fails if `policy` refuses the overlaps of either team.
+#[tracing::instrument(err, skip(db))]
+pub async fn accept_pick(
+    db: &sqlx::PgPool,
+    id: i32,
+    to: i32,
+    policy: OverlapPolicy,
+) -> Result<Option<Vec<DateTime<Utc>>>, Error> {
+    let mut tx = db.begin().await?;
+    let available = sqlx::query(
+        "SELECT * FROM scrims
+        WHERE ((id = $1 AND match_id IS NULL) OR (id = $2 AND match_id = $1))
+            AND NOT cancelled AND time > NOW()
+        FOR UPDATE",
+    )
+    .bind(id)
+    .bind(to)
+    .fetch_all(&mut *tx)
+    .await?;
+    let (Some(scrim), Some(other)) = pair_of(available, id, Some(to)) else {
+        return Ok(None);
+    };
+    let times = check_match_overlaps(&mut tx, &scrim, Some(&other), None, policy).await?;
+    sqlx::query("UPDATE scrims SET match_id = $2 WHERE id = $1")
+        .bind(id)
+        .bind(to)
+        .execute(&mut *tx)
+        .await?;
+    tx.commit().await?;
+    Ok(Some(times))
+}
+
+/// Let the open posting `id` of `creator_id` pick `to`. Returns whether the posting was still open.
+#[tracing::instrument(err, skip(db))]
+pub async fn pick_scrim(
+    db: &sqlx::PgPool,
+    id: i32,
+    creator_id: i64,
+    to: i32,
+) -> Result<bool, sqlx::Error> {
+    let result = sqlx::query(
+        "UPDATE scrims SET match_id = $3
+        WHERE id = $1 AND creator_id = $2 AND match_id IS NULL AND NOT cancelled AND time >= NOW()",
+    )
+    .bind(id)
+    .bind(creator_id)
+    .bind(to)
+    .execute(db)
+    .await?;
+    Ok(result.rows_affected() > 0)
+}
+
+/// Withdraw the pick of posting `id`, if it still picked `from`.
+#[tracing::instrument(err, skip(db))]
+pub async fn withdraw_pick(db: &sqlx::PgPool, id: i32, from: i32) -> Result<(), sqlx::Error> {
+    sqlx::query("UPDATE scrims SET match_id = NULL WHERE id = $1 AND match_id = $2")
+        .bind(id)
+        .bind(from)
+        .execute(db)
+        .await?;
+    Ok(())
+}
+
+/// Store postings until their creator confirms them and return the id of the first draft, which
+/// stands for all of them. Fails if there are no postings.
+#[tracing::instrument(err, skip(db))]
+pub async fn create_drafts(db: &sqlx::PgPool, postings: &[LookingForScrim]) -> Result<i32, Error> {
+    let mut tx = db.begin().await?;
+    let mut batch_id = None;
+    for lfs in postings {
+        let id = sqlx::query_scalar(
+            "INSERT INTO scrim_drafts
+            (creator_id, region, platform, rank_from, rank_to, time, team_name, batch_id, auto_match)
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9) RETURNING id",
+        )
+        .bind(lfs.creator_id)
+        .bind(format!("{:?}", lfs.region))
+        .bind(format!("{:?}", lfs.platform))
+        .bind(lfs.range.0.start as i32)
+        .bind(lfs.range.0.end as i32)
+        .bind(lfs.time)
+        .bind(&lfs.team_name)
+        .bind(batch_id)
+        .bind(lfs.auto_match)
+        .fetch_one(&mut *tx)
+        .await?;
+        batch_id.get_or_insert(id);
+    }
+    let batch_id = batch_id.ok_or(Text::new("time-missing"))?;
+    tx.commit().await?;
+    Ok(batch_id)
+}
+
+/// Turn the drafts of `creator_id` created together with draft `id` into postings, all or none.
+/// Returns nothing if the drafts don't exist anymore. Fails if `policy` refuses overlapping scrims
+/// and the postings overlap.
+#[tracing::instrument(err, skip(db))]
+pub async fn confirm_drafts(
+    db: &sqlx::PgPool,
+    id: i32,
+    creator_id: i64,
+    policy: OverlapPolicy,
+) -> Result<Vec<LookingForScrim>, Error> {
+    let mut tx = db.begin().await?;
+    let rows = sqlx::query(
+        "DELETE FROM scrim_drafts WHERE (id = $1 OR batch_id = $1) AND creator_id = $2
+        RETURNING *",
+    )
+    .bind(id)
+    .bind(creator_id)
+    .fetch_all(&mut *tx)
+    .await?;
+    let mut postings = Vec::with_capacity(rows.len());
+    for row in rows {
+        postings.push(LookingForScrim {
+            id: 0,
+            creator_id,
+            team_name: row.get("team_name"),
+            region: row.get::<&str, _>("region").parse().unwrap(),
+            platform: row.get::<&str, _>("platform").parse().unwrap(),
+            range: RankRange(
+                row.get::<i32, _>("rank_from") as u32..row.get::<i32, _>("rank_to") as u32,
+            ),
+            time: row.get("time"),
+            match_id: None,
+            cancelled: false,
+            checked_in: false,
+            no_show: false,
+            reliability: None,
+            auto_match: row.get("auto_match"),
+        });
+    }
+    enforce_overlap_policy(&mut tx, &postings, policy).await?;
+    for lfs in &mut postings {
+        lfs.id = create_scrim_in(&mut *tx, lfs.clone()).await?;
+    }
+    tx.commit().await?;
+    postings.sort_by_key(|lfs| lfs.time);
+    Ok(postings)
+}
+
+/// Delete the drafts of `creator_id` created together with draft `id`. Returns whether they still
+/// existed.
+#[tracing::instrument(err, skip(db))]
+pub async fn delete_drafts(
+    db: &sqlx::PgPool,
+    id: i32,
+    creator_id: i64,
+) -> Result<bool, sqlx::Error> {
+    let result = sqlx::query(
+        "DELETE FROM scrim_drafts WHERE (id = $1 OR batch_id = $1) AND creator_id = $2",
+    )
+    .bind(id)
+    .bind(creator_id)
+    .execute(db)
+    .await?;
+    Ok(result.rows_affected() > 0)
+}
+
+/// Delete drafts that were never confirmed, either because they are old or their time passed.
+#[tracing::instrument(err, skip(db))]
+pub async fn delete_stale_drafts(db: &sqlx::PgPool) -> Result<(), sqlx::Error> {
+    sqlx::query(
+        "DELETE FROM scrim_drafts WHERE created_at < NOW() - INTERVAL '1 day' OR time < NOW()",
+    )
+    .execute(db)
+    .await?;
+    Ok(())
+}
+
+/// Store the preview of an import and return its id.
+#[tracing::instrument(err, skip(db, import))]
+pub async fn create_import(db: &sqlx::PgPool, import: &Import) -> Result<i32, sqlx::Error> {
+    let mut tx = db.begin().await?;
+    let template = &import.template;
+    let id: i32 = sqlx::query_scalar(
+        "INSERT INTO imports
+        (creator_id, region, platform, rank_from, rank_to, team_name, skipped)
+        VALUES ($1, $2, $3, $4, $5, $6, $7) RETURNING id",
+    )
+    .bind(template.creator_id)
+    .bind(format!("{:?}", template.region))
+    .bind(format!("{:?}", template.platform))
+    .bind(template.range.0.start as i32)
+    .bind(template.range.0.end as i32)
+    .bind(&template.team_name)
+    .bind(import.skipped as i32)
+    .fetch_one(&mut *tx)
+    .await?;
+    for (position, event) in import.events.iter().enumerate() {
+        sqlx::query(
+            "INSERT INTO import_events (import_id, position, time, summary)
+            VALUES ($1, $2, $3, $4)",
+        )
+        .bind(id)
+        .bind(position as i32)
+        .bind(event.start)
+        .bind(&event.summary)
+        .execute(&mut *tx)
+        .await?;
+    }
+    tx.commit().await?;
+    Ok(id)
+}
+
+/// Preview `id` of an import of `creator_id`, if it wasn't posted or cancelled yet.
+#[tracing::instrument(err, skip(db))]
+pub async fn get_import(
+    db: &sqlx::PgPool,
+    id: i32,
+    creator_id: i64,
+) -> Result<Option<Import>, sqlx::Error> {
+    let Some(row) = sqlx::query("SELECT * FROM imports WHERE id = $1 AND creator_id = $2")
+        .bind(id)
+        .bind(creator_id)
+        .fetch_optional(db)
+        .await?
+    else {
+        return Ok(None);
+    };
+    let events = sqlx::query(
+        "SELECT time, summary FROM import_events WHERE import_id = $1 ORDER BY position",
+    )
+    .bind(id)
+    .fetch(db)
+    .map(|row| {
+        row.map(|row| CalendarEvent {
+            start: row.get("time"),
+            summary: row.get("summary"),
+            categories: Vec::new(),
+        })
+    })
+    .try_collect()
+    .await?;
+    Ok(Some(Import {
+        template: LookingForScrim {
+            id: 0,
+            creator_id,
+            team_name: row.get("team_name"),
+            region: row.get::<&str, _>("region").parse().unwrap(),
+            platform: row.get::<&str, _>("platform").parse().unwrap(),
+            range: RankRange(
+                row.get::<i32, _>("rank_from") as u32..row.get::<i32, _>("rank_to") as u32,
+            ),
+            time: chrono::Utc::now(),
+            match_id: None,
+            cancelled: false,
+            checked_in: false,
+            no_show: false,
+            reliability: None,
+            auto_match: false,
+        },
+        events,
+        skipped: row.get::<i32, _>("skipped") as usize,
+    }))
+}
+
+/// Post the events of import `id` of `creator_id` as `postings` and remove the preview, all or
+/// none. Returns nothing if the preview doesn't exist anymore. Fails if `policy` refuses
+/// overlapping scrims and the postings overlap.
+#[tracing::instrument(err, skip(db))]
+pub async fn post_import(
+    db: &sqlx::PgPool,
+    id: i32,
+    creator_id: i64,
+    mut postings: Vec<LookingForScrim>,
+    policy: OverlapPolicy,
+) -> Result<Option<Vec<LookingForScrim>>, Error> {
+    let mut tx = db.begin().await?;
+    let result = sqlx::query("DELETE FROM imports WHERE id = $1 AND creator_id = $2")
+        .bind(id)
+        .bind(creator_id)
+        .execute(&mut *tx)
+        .await?;
+    if result.rows_affected() == 0 {
+        return Ok(None);
+    }
+    enforce_overlap_policy(&mut tx, &postings, policy).await?;
+    for lfs in &mut postings {
+        lfs.id = create_scrim_in(&mut *tx, lfs.clone()).await?;
+    }
+    tx.commit().await?;
+    Ok(Some(postings))
+}
+
+/// Delete the preview `id` of an import of `creator_id`.
+#[tracing::instrument(err, skip(db))]
+pub async fn delete_import(db: &sqlx::PgPool, id: i32, creator_id: i64) -> Result<(), sqlx::Error> {
+    sqlx::query("DELETE FROM imports WHERE id = $1 AND creator_id = $2")
+        .bind(id)
+        .bind(creator_id)
+        .execute(db)
+        .await?;
+    Ok(())
+}
+
+/// Delete previews of imports that were never used.
+#[tracing::instrument(err, skip(db))]
+pub async fn delete_stale_imports(db: &sqlx::PgPool) -> Result<(), sqlx::Error> {
+    sqlx::query("DELETE FROM imports WHERE created_at < NOW() - INTERVAL '1 day'")
+        .execute(db)
+        .await?;
+    Ok(())
+}
+
+/// Scrims of `owner` that haven't ended yet, assuming they take `duration_minutes`, with their
+/// opponents if matched.
+#[tracing::instrument(err, skip(db))]
+pub async fn get_calendar_scrims(
+    db: &sqlx::PgPool,
+    owner: &CalendarOwner,
+    duration_minutes: i32,
+) -> Result<Vec<(LookingForScrim, Option<LookingForScrim>)>, sqlx::Error> {
+    let ids: Vec<(i32, Option<i32>)> = sqlx::query_as(
+        "SELECT s.id, m.other_id FROM scrims s
+        LEFT JOIN LATERAL (
+            SELECT other_id FROM matched_scrims WHERE id = s.id AND NOT cancelled LIMIT 1
+        ) m ON TRUE
+        WHERE s.creator_id = $1
+            AND ($2::VARCHAR IS NULL OR LOWER(s.team_name) = LOWER($2))
+            AND NOT s.cancelled AND s.time > NOW() - make_interval(mins => $3)
+        ORDER BY s.time",
+    )
+    .bind(owner.creator_id)
+    .bind(&owner.team_name)
+    .bind(duration_minutes)
+    .fetch_all(db)
+    .await?;
+
+    let mut scrims = Vec::with_capacity(ids.len());
+    for (id, other_id) in ids {
+        let other = match other_id {
+            Some(other_id) => Some(get_scrim_in(db, other_id).await?),
+            None => None,
+        };
+        scrims.push((get_scrim_in(db, id).await?, other));
+    }
+    Ok(scrims)
+}
+
+/// Token of the calendar feed of `owner`. A new token is created if there is none yet or `reset`
+/// is set, which makes the old link stop working.
+#[tracing::instrument(err, skip(db))]
+pub async fn get_feed_token(
+    db: &sqlx::PgPool,
+    owner: &CalendarOwner,
+    reset: bool,
+) -> Result<String, sqlx::Error> {
+    let mut tx = db.begin().await?;
+    let existing: Option<(String,)> = sqlx::query_as(
+        "SELECT token FROM calendar_feeds
+        WHERE creator_id = $1 AND LOWER(COALESCE(team_name, '')) = LOWER(COALESCE($2, ''))",
+    )
+    .bind(owner.creator_id)
+    .bind(&owner.team_name)
+    .fetch_optional(&mut *tx)
+    .await?;
+    if let Some((token,)) = existing {
+        if !reset {
+            return Ok(token);
+        }
+        sqlx::query("DELETE FROM calendar_feeds WHERE token = $1")
+            .bind(token)
+            .execute(&mut *tx)
+            .await?;
+    }
+
+    let (token,): (String,) = sqlx::query_as(
+        "INSERT INTO calendar_feeds (creator_id, team_name) VALUES ($1, $2) RETURNING token",
+    )
+    .bind(owner.creator_id)
+    .bind(&owner.team_name)
+    .fetch_one(&mut *tx)
+    .await?;
+    tx.commit().await?;
+    Ok(token)
+}
+
+#[tracing::instrument(err, skip(db, token))]
+pub async fn get_feed_owner(
+    db: &sqlx::PgPool,
+    token: &str,
+) -> Result<Option<CalendarOwner>, sqlx::Error> {
+    let row: Option<(i64, Option<String>)> =
+        sqlx::query_as("SELECT creator_id, team_name FROM calendar_feeds WHERE token = $1")
+            .bind(token)
+            .fetch_optional(db)
+            .await?;
+    Ok(row.map(|(creator_id, team_name)| CalendarOwner {
+        creator_id,
+        team_name,
+    }))
+}
+
+fn row_to_preset(row: PgRow) -> Preset {
+    let rank_from: Option<i32> = row.get("rank_from");
+    let rank_to: Option<i32> = row.get("rank_to");
+    Preset {
+        name: row.get("name"),
+        region: row
+            .get::<Option<&str>, _>("region")
+            .map(|region| region.parse().unwrap()),
+        platform: row
+            .get::<Option<&str>, _>("platform")
+            .map(|platform| platform.parse().unwrap()),
+        range: rank_from
+            .zip(rank_to)
+            .map(|(from, to)| RankRange(from as u32..to as u32)),
+        team_name: row.get("team_name"),
+    }
+}
+
+/// Presets of `creator_id`, sorted by name.
+#[tracing::instrument(err, skip(db))]
+pub async fn get_presets(db: &sqlx::PgPool, creator_id: i64) -> Result<Vec<Preset>, sqlx::Error> {
+    sqlx::query("SELECT * FROM presets WHERE creator_id = $1 ORDER BY LOWER(name)")
+        .bind(creator_id)
+        .map(row_to_preset)
+        .fetch_all(db)
+        .await
+}
+
+/// Preset of `creator_id` by its name, ignoring case.
+#[tracing::instrument(err, skip(db))]
+pub async fn get_preset(
+    db: &sqlx::PgPool,
+    creator_id: i64,
+    name: &str,
+) -> Result<Option<Preset>, sqlx::Error> {
+    sqlx::query("SELECT * FROM presets WHERE creator_id = $1 AND LOWER(name) = LOWER($2)")
+        .bind(creator_id)
+        .bind(name)
+        .map(row_to_preset)
+        .fetch_optional(db)
+        .await
+}
+
+/// Names of the presets of the author containing `partial`, for autocompletion.
+#[tracing::instrument(err, skip(ctx))]
+pub async fn get_preset_names(ctx: Context<'_>, partial: &str) -> Result<Vec<String>, sqlx::Error> {
+    sqlx::query_scalar(
+        "SELECT name FROM presets WHERE creator_id = $1 AND name ILIKE '%' || $2 || '%'
+        ORDER BY LOWER(name) LIMIT 25",
+    )
+    .bind(ctx.author().id.get() as i64)
+    .bind(partial)
+    .fetch_all(&ctx.data().db)
+    .await
+}
+
+/// Save a preset of `creator_id`, replacing the one with the same name.
+#[tracing::instrument(err, skip(db))]
+pub async fn save_preset(
+    db: &sqlx::PgPool,
+    creator_id: i64,
+    preset: &Preset,
+) -> Result<(), sqlx::Error> {
+    sqlx::query(
+        "INSERT INTO presets (creator_id, name, region, platform, rank_from, rank_to, team_name)
+        VALUES ($1, $2, $3, $4, $5, $6, $7)
+        ON CONFLICT (creator_id, LOWER(name)) DO UPDATE SET name = $2, region = $3,
+        platform = $4, rank_from = $5, rank_to = $6, team_name = $7",
+    )
+    .bind(creator_id)
+    .bind(&preset.name)
+    .bind(preset.region.map(|region| format!("{region:?}")))
+    .bind(preset.platform.map(|platform| format!("{platform:?}")))
+    .bind(preset.range.as_ref().map(|range| range.0.start as i32))
+    .bind(preset.range.as_ref().map(|range| range.0.end as i32))
+    .bind(&preset.team_name)
+    .execute(db)
+    .await?;
+    Ok(())
+}
+
+/// Delete a preset of `creator_id` by its name. Returns whether it existed.
+#[tracing::instrument(err, skip(db))]
+pub async fn delete_preset(
+    db: &sqlx::PgPool,
+    creator_id: i64,
+    name: &str,
+) -> Result<bool, sqlx::Error> {
+    let result =
+        sqlx::query("DELETE FROM presets WHERE creator_id = $1 AND LOWER(name) = LOWER($2)")
+            .bind(creator_id)
+            .bind(name)
+            .execute(db)
+            .await?;
+    Ok(result.rows_affected() > 0)
+}
+
+/// Refuse new postings of one creator that overlap with each other or with other scrims, if
+/// `policy` says so. The creator is locked until the transaction ends, so postings confirmed at the
+/// same time can't overlap either.
+async fn enforce_overlap_policy(
+    tx: &mut sqlx::PgConnection,
+    postings: &[LookingForScrim],
+    policy: OverlapPolicy,
+) -> Result<(), Error> {
+    let Some(first) = postings.first() else {
+        return Ok(());
+    };
+    if policy != OverlapPolicy::Block {
+        return Ok(());
+    }
+    sqlx::query("SELECT pg_advisory_xact_lock($1)")
+        .bind(first.creator_id)
+        .execute(&mut *tx)
+        .await?;
+    let times = overlaps::overlapping_times(tx, postings, false).await?;
+    if !times.is_empty() {
+        return Err(overlaps::overlap_blocked(&times).into());
+    }
+    Ok(())
+}
+
+/// Scrims of the same creator and team as `lfs` that overlap with it, assuming scrims take
+/// `duration_minutes`. With `matched_only`, only scrims that found an opponent count.
+#[tracing::instrument(err, skip(db))]
+pub async fn get_overlapping_scrims<'e>(
+    db: impl sqlx::PgExecutor<'e>,
+    lfs: &LookingForScrim,
+    duration_minutes: i32,
+    matched_only: bool,
+) -> Result<Vec<LookingForScrim>, sqlx::Error> {
+    sqlx::query(&format!(
+        "{SELECT_SCRIMS}
+        WHERE scrims.creator_id = $1
+            AND LOWER(COALESCE(scrims.team_name, '')) = LOWER(COALESCE($2, ''))
+            AND scrims.id <> $3 AND NOT scrims.cancelled
+            AND scrims.time > $4 - make_interval(mins => $5)
+            AND scrims.time < $4 + make_interval(mins => $5)
+            AND (NOT $6 OR EXISTS (
+                SELECT 1 FROM matched_scrims m WHERE m.id = scrims.id AND NOT m.cancelled
+            ))
+        ORDER BY scrims.time"
+    ))
+    .bind(lfs.creator_id)
+    .bind(&lfs.team_name)
+    .bind(lfs.id)
+    .bind(lfs.time)
+    .bind(duration_minutes)
+    .bind(matched_only)
+    .map(row_to_lfs)
+    .fetch_all(db)
+    .await
+}
+
+/// Open postings that opted into automatic matching and start after `grace_minutes`.
+#[tracing::instrument(err, skip(db))]
+pub async fn get_auto_match_postings(
+    db: &sqlx::PgPool,
+    grace_minutes: i32,
+) -> Result<Vec<LookingForScrim>, sqlx::Error> {
+    sqlx::query(&format!(
+        "{SELECT_SCRIMS}
+        WHERE scrims.auto_match AND NOT scrims.cancelled AND scrims.match_id IS NULL
+            AND scrims.time > NOW() + make_interval(mins => $1)
+            AND NOT EXISTS (SELECT 1 FROM scrims o WHERE o.match_id = scrims.id)
+        ORDER BY scrims.time"
+    ))
+    .bind(grace_minutes)
+    .map(row_to_lfs)
+    .fetch_all(db)
+    .await
+}
+
+/// Match two postings that opted into automatic matching with each other. Returns whether both
+/// were still open and starting after `grace_minutes`, hadn't been declined as a pair before and
+/// don't overlap with other matched scrims of their teams.
+#[tracing::instrument(err, skip(db))]
+pub async fn pair_scrims(
+    db: &sqlx::PgPool,
+    scrim: &LookingForScrim,
+    other: &LookingForScrim,
+    grace_minutes: i32,
+) -> Result<bool, sqlx::Error> {
+    let (id, other_id) = (scrim.id, other.id);
+    let mut tx = db.begin().await?;
+    sqlx::query("SELECT id FROM scrims WHERE id IN ($1, $2) FOR UPDATE")
+        .bind(id)
+        .bind(other_id)
+        .execute(&mut *tx)
+        .await?;
+    let open: i64 = sqlx::query_scalar(
+        "SELECT COUNT(*) FROM scrims s
+        WHERE s.id IN ($1, $2) AND s.auto_match AND NOT s.cancelled AND s.match_id IS NULL
+            AND s.time > NOW() + make_interval(mins => $3)
+            AND NOT EXISTS (SELECT 1 FROM scrims o WHERE o.match_id = s.id)
+            AND NOT EXISTS (
+                SELECT 1 FROM declined_auto_matches
+                WHERE scrim_id = LEAST($1, $2) AND other_id = GREATEST($1, $2)
+            )",
+    )
+    .bind(id)
+    .bind(other_id)
+    .bind(grace_minutes)
+    .fetch_one(&mut *tx)
+    .await?;
+    if open != 2 {
+        return Ok(false);
+    }
+    // Guilds of postings aren't known and nobody confirms the pairing, so overlaps are always
+    // refused
+    sqlx::query("SELECT pg_advisory_xact_lock(id) FROM UNNEST($1::BIGINT[]) id ORDER BY id")
+        .bind([scrim.creator_id, other.creator_id])
+        .execute(&mut *tx)
+        .await?;
+    for lfs in [scrim, other] {
+        if !overlaps::overlapping_times(&mut tx, std::slice::from_ref(lfs), true)
+            .await?
+            .is_empty()
+        {
+            return Ok(false);
+        }
+    }
+    sqlx::query(
+        "UPDATE scrims SET match_id = CASE WHEN id = $1 THEN $2 ELSE $1 END,
+            auto_matched_at = NOW()
+        WHERE id IN ($1, $2)",
+    )
+    .bind(id)
+    .bind(other_id)
+    .execute(&mut *tx)
+    .await?;
+    tx.commit().await?;
+    Ok(true)
+}
+
+/// Undo the automatic pairing of posting `id` of `creator_id` if it happened less than
+/// `grace_minutes` ago, and keep the two postings from being paired again. Returns the id of the
+/// other posting if the pairing was undone.
+#[tracing::instrument(err, skip(db))]
+pub async fn decline_auto_match(
+    db: &sqlx::PgPool,
+    id: i32,
+    creator_id: i64,
+    grace_minutes: i32,
+) -> Result<Option<i32>, sqlx::Error> {
+    let mut tx = db.begin().await?;
+    let other_id: Option<i32> = sqlx::query_scalar(
+        "SELECT s.match_id FROM scrims s JOIN scrims o ON o.id = s.match_id AND o.match_id = s.id
+        WHERE s.id = $1 AND s.creator_id = $2
+            AND s.auto_matched_at > NOW() - make_interval(mins => $3)
+        FOR UPDATE",
+    )
+    .bind(id)
+    .bind(creator_id)
+    .bind(grace_minutes)
+    .fetch_optional(&mut *tx)
+    .await?;
+    let Some(other_id) = other_id else {
+        return Ok(None);
+    };
+    sqlx::query("UPDATE scrims SET match_id = NULL, auto_matched_at = NULL WHERE id IN ($1, $2)")
+        .bind(id)
+        .bind(other_id)
+        .execute(&mut *tx)
+        .await?;
+    sqlx::query(
+        "INSERT INTO declined_auto_matches (scrim_id, other_id)
+        VALUES (LEAST($1, $2), GREATEST($1, $2)) ON CONFLICT DO NOTHING",
+    )
+    .bind(id)
+    .bind(other_id)
+    .execute(&mut *tx)
+    .await?;
+    tx.commit().await?;
+    Ok(Some(other_id))
+}
@@ -0,0 +1,318 @@
+use chrono::{DateTime, Utc};
+use poise::{
+    serenity_prelude::{
+        self as serenity, ButtonStyle, ComponentInteraction, CreateActionRow, CreateButton,
+        CreateInteractionResponse, CreateInteractionResponseMessage,
+    },
+    CreateReply,
+};
+
+use crate::{
+    components::{ComponentAction, HistoryAction},
+    db,
+    i18n::{self, t, Locale, Text},
+    interactions::report_error,
+    invites::send_invite,
+    lfs::{check_team_name, parse_days, LookingForScrim},
+    overlaps::check_overlaps,
+    render::write_scrim_with_name,
+    results::ScrimResult,
+    Context, Data, Error,
+};
+
+const PAGE_SIZE: i64 = 5;
+
+#[derive(Debug, Default)]
+pub struct HistoryFilter {
+    /// Only show the scrims the author posted with this team name.
+    pub team_name: Option<String>,
+    pub from: Option<DateTime<Utc>>,
+    pub to: Option<DateTime<Utc>>,
+    pub opponent_id: Option<i64>,
+    pub opponent_team: Option<String>,
+}
+
+struct HistoryEntry {
+    scrim: LookingForScrim,
+    other: Option<LookingForScrim>,
+    result: Option<ScrimResult>,
+}
+
+fn write_entry(index: usize, entry: &HistoryEntry, locale: Locale, content: &mut String) {
+    use std::fmt::Write;
+
+    write!(
+        content,
+        "{}. <t:{}:F> ",
+        index + 1,
+        entry.scrim.time.timestamp()
+    )
+    .unwrap();
+    if let Some(team_name) = &entry.scrim.team_name {
+        write!(content, "**{team_name}** ").unwrap();
+    }
+    write!(
+        content,
+        "{:?}/{:?}",
+        entry.scrim.region, entry.scrim.platform
+    )
+    .unwrap();
+
+    if let Some(other) = &entry.other {
+        write!(content, " {} ", t!(locale, "history-versus")).unwrap();
+        write_scrim_with_name(other, Some(&entry.scrim), true, locale, Utc::now(), content);
+    }
+    content.push('\n');
+
+    content.push_str("  ");
+    if entry.scrim.cancelled {
+        content.push_str(&t!(locale, "history-cancelled"));
+    } else if entry.other.as_ref().is_some_and(|other| other.no_show) {
+        content.push_str(&t!(locale, "history-opponent-no-show"));
+    } else if entry.scrim.no_show {
+        content.push_str(&t!(locale, "history-no-show"));
+    } else if let Some(result) = &entry.result {
+        let outcome = match result.maps_won.cmp(&result.maps_lost) {
+            std::cmp::Ordering::Greater => t!(locale, "history-won"),
+            std::cmp::Ordering::Less => t!(locale, "history-lost"),
+            std::cmp::Ordering::Equal => t!(locale, "history-draw"),
+        };
+        content.push_str(&t!(
+            locale,
+            "history-result",
+            outcome = outcome,
+            won = result.maps_won,
+            lost = result.maps_lost,
+            rating = result.rating
+        ));
+        if let Some(details) = &result.details {
+            write!(content, " ({details})").unwrap();
+        }
+    } else {
+        content.push_str(&t!(locale, "history-no-result"));
+    }
+    content.push('\n');
+}
+
+async fn history_page(
+    db: &sqlx::PgPool,
+    creator_id: i64,
+    filter_id: i32,
+    filter: &HistoryFilter,
+    page: i64,
+    locale: Locale,
+) -> Result<(String, Vec<CreateActionRow>), Error> {
+    let page = page.max(0);
+    let (mut ids, mut total) =
+        db::get_history(db, creator_id, filter, page * PAGE_SIZE, PAGE_SIZE).await?;
+    // Pages past the end have no rows to count, e.g. when the history changed since it was shown
+    if ids.is_empty() && page > 0 {
+        total = db::get_history(db, creator_id, filter, 0, 1).await?.1;
+    }
+    if total == 0 {
+        return Ok((t!(locale, "history-none"), Vec::new()));
+    }
+    let pages = (total + PAGE_SIZE - 1) / PAGE_SIZE;
+    let page = page.min(pages - 1);
+    if ids.is_empty() {
+        ids = db::get_history(db, creator_id, filter, page * PAGE_SIZE, PAGE_SIZE)
+            .await?
+            .0;
+    }
+    let custom_id = |action| ComponentAction::History { filter_id, action }.custom_id();
+
+    let mut content = format!(
+        "## {}\n",
+        t!(locale, "history-title", page = page + 1, pages = pages)
+    );
+    let mut rematch_buttons = Vec::new();
+    for (index, (id, other_id)) in ids.into_iter().enumerate() {
+        let other = if let Some(other_id) = other_id {
+            Some(db::get_scrim_in(db, other_id).await?)
+        } else {
+            None
+        };
+        let entry = HistoryEntry {
+            scrim: db::get_scrim_in(db, id).await?,
+            other,
+            result: db::get_result(db, id).await?,
+        };
+        write_entry(index, &entry, locale, &mut content);
+
+        if entry.other.is_some() {
+            rematch_buttons.push(
+                CreateButton::new(custom_id(HistoryAction::Rematch(id))?)
+                    .style(ButtonStyle::Primary)
+                    .label(t!(locale, "history-rematch", rank = index + 1)),
+            );
+        }
+    }
+
+    let buttons = vec![
+        CreateButton::new(custom_id(HistoryAction::Page((page - 1).max(0)))?)
+            .style(ButtonStyle::Secondary)
+            .label(t!(locale, "button-previous"))
+            .disabled(page == 0),
+        CreateButton::new(custom_id(HistoryAction::Page(page + 1))?)
+            .style(ButtonStyle::Secondary)
+            .label(t!(locale, "button-next"))
+            .disabled(page + 1 >= pages),
+    ];
+    let mut components = vec![CreateActionRow::Buttons(buttons)];
+    if !rematch_buttons.is_empty() {
+        components.push(CreateActionRow::Buttons(rematch_buttons));
+    }
+    Ok((content, components))
+}
+
+/// Post a new scrim like an old one of `creator_id` at the same time of the week and invite the old
+/// opponent to it. Overlaps are handled by the policy of the guild `guild_id`.
+async fn rematch(
+    ctx: &serenity::Context,
+    db: &sqlx::PgPool,
+    guild_id: Option<i64>,
+    creator_id: i64,
+    scrim_id: i32,
+    locale: Locale,
+) -> Result<String, Error> {
+    let old = db::get_scrim_in(db, scrim_id).await?;
+    if old.creator_id != creator_id {
+        return Err(Text::new("scrim-not-yours").into());
+    }
+    let Some(other_id) = old.match_id else {
+        return Err(Text::new("history-not-matched").into());
+    };
+    let other = db::get_scrim_in(db, other_id).await?;
+
+    let now = Utc::now();
+    let weeks = (now - old.time).num_weeks() + 1;
+    let lfs = LookingForScrim {
+        id: 0,
+        time: old.time + chrono::Duration::weeks(weeks.max(1)),
+        match_id: None,
+        cancelled: false,
+        checked_in: false,
+        no_show: false,
+        reliability: None,
+        auto_match: false,
+        ..old
+    };
+    let warning = check_overlaps(db, guild_id, std::slice::from_ref(&lfs), false).await?;
+    let policy = db::get_overlap_policy(db, guild_id).await?;
+    let lfs = db::create_scrims(db, vec![lfs], policy).await?.remove(0);
+
+    send_invite(
+        ctx,
+        db,
+        &lfs,
+        other.creator_id,
+        other.team_name.as_deref(),
+        Text::new("history-rematch-title"),
+    )
+    .await?;
+
+    let mut content = t!(
+        locale,
+        "history-rematch-posted",
+        time = lfs.time.timestamp(),
+        user = other.creator_id
+    );
+    if let Some(warning) = warning {
+        content = format!("{}\n{content}", warning.render(locale));
+    }
+    Ok(content)
+}
+
+#[poise::command(slash_command, ephemeral)]
+#[tracing::instrument(err, skip(ctx), fields(user = ctx.author().id.get()))]
+pub async fn history(
+    ctx: Context<'_>,
+    team_name: Option<String>,
+    from: Option<String>,
+    to: Option<String>,
+    opponent: Option<serenity::User>,
+    opponent_team: Option<String>,
+) -> Result<(), Error> {
+    let locale = i18n::locale(ctx).await;
+    check_team_name(team_name.as_deref())?;
+    check_team_name(opponent_team.as_deref())?;
+    let prefs = db::get_preferences(ctx).await?;
+    let filter = HistoryFilter {
+        team_name,
+        from: from
+            .map(|from| parse_days(&from, prefs))
+            .transpose()?
+            .map(|days| days.start),
+        to: to
+            .map(|to| parse_days(&to, prefs))
+            .transpose()?
+            .map(|days| days.end),
+        opponent_id: opponent.map(|opponent| opponent.id.get() as i64),
+        opponent_team,
+    };
+
+    let db = &ctx.data().db;
+    let creator_id = ctx.author().id.get() as i64;
+    let filter_id = db::create_history_filter(db, creator_id, &filter).await?;
+    let (content, components) = history_page(db, creator_id, filter_id, &filter, 0, locale).await?;
+    ctx.send(
+        CreateReply::default()
+            .content(content)
+            .components(components),
+    )
+    .await?;
+    Ok(())
+}
+
+/// Handle the buttons of `/history`. The filter is loaded from the database, so they keep working
+/// after restarts.
+pub async fn handle_component(
+    ctx: &serenity::Context,
+    data: &Data,
+    i: &ComponentInteraction,
+    filter_id: i32,
+    action: HistoryAction,
+) -> Result<(), Error> {
+    match handle_action(ctx, data, i, filter_id, action).await {
+        Ok(()) => Ok(()),
+        Err(e) => report_error(ctx, data, i, e).await,
+    }
+}
+
+async fn handle_action(
+    ctx: &serenity::Context,
+    data: &Data,
+    i: &ComponentInteraction,
+    filter_id: i32,
+    action: HistoryAction,
+) -> Result<(), Error> {
+    let db = &data.db;
+    let creator_id = i.user.id.get() as i64;
+    let locale = i18n::component_locale(data, i).await;
+
+    let response = match action {
+        HistoryAction::Page(page) => {
+            let filter = db::get_history_filter(db, filter_id, creator_id)
+                .await?
+                .ok_or(Text::new("button-expired"))?;
+            let (content, components) =
+                history_page(db, creator_id, filter_id, &filter, page, locale).await?;
+            CreateInteractionResponse::UpdateMessage(
+                CreateInteractionResponseMessage::new()
+                    .content(content)
+                    .components(components),
+            )
+        }
+        HistoryAction::Rematch(scrim_id) => {
+            let guild_id = i.guild_id.map(|id| id.get() as i64);
+            let content = rematch(ctx, db, guild_id, creator_id, scrim_id, locale).await?;
+            CreateInteractionResponse::Message(
+                CreateInteractionResponseMessage::new()
+                    .content(content)
+                    .ephemeral(true),
+            )
+        }
+    };
+    i.create_response(ctx, response).await?;
+    Ok(())
+}
@@ -0,0 +1,393 @@
+use std::{collections::HashMap, fmt::Display, sync::OnceLock};
+
+use poise::{serenity_prelude as serenity, ChoiceParameter};
+
+use crate::{db, Context, Data, Error};
+
+/// Languages the bot is translated to. Messages missing in a catalogue fall back to English.
+#[derive(ChoiceParameter, Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Locale {
+    #[default]
+    #[name = "English"]
+    En,
+    #[name = "Deutsch"]
+    De,
+    #[name = "Français"]
+    Fr,
+}
+
+impl Locale {
+    const ALL: [Self; 3] = [Self::En, Self::De, Self::Fr];
+
+    /// Code stored in the database.
+    pub fn code(self) -> &'static str {
+        match self {
+            Self::En => "en",
+            Self::De => "de",
+            Self::Fr => "fr",
+        }
+    }
+
+    pub fn from_code(code: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|locale| locale.code() == code)
+    }
+
+    /// Language of a Discord locale like `en-US` or `de`.
+    pub fn from_discord(locale: &str) -> Option<Self> {
+        Self::from_code(locale.split('-').next()?)
+    }
+
+    /// Discord locales command descriptions are registered for.
+    fn discord_locales(self) -> &'static [&'static str] {
+        match self {
+            Self::En => &["en-US", "en-GB"],
+            Self::De => &["de"],
+            Self::Fr => &["fr"],
+        }
+    }
+
+    fn source(self) -> &'static str {
+        match self {
+            Self::En => include_str!("../locales/en.txt"),
+            Self::De => include_str!("../locales/de.txt"),
+            Self::Fr => include_str!("../locales/fr.txt"),
+        }
+    }
+}
+
+/// Malformed line in a catalogue.
+#[derive(Debug)]
+pub struct CatalogueError {
+    locale: Locale,
+    line: usize,
+    reason: &'static str,
+}
+
+impl Display for CatalogueError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "line {} of the {} catalogue: {}",
+            self.line,
+            self.locale.code(),
+            self.reason
+        )
+    }
+}
+
+impl std::error::Error for CatalogueError {}
+
+/// Parse a catalogue. Each message is a `key = value` line, indented lines continue the value of
+/// the previous message and lines starting with `#` are comments. Values may contain placeables
+/// like `{ $name }`.
+fn parse_catalogue(locale: Locale) -> Result<HashMap<&'static str, String>, CatalogueError> {
+    let mut messages = HashMap::new();
+    let mut last = None;
+    for (i, line) in locale.source().lines().enumerate() {
+        let error = |reason| CatalogueError {
+            locale,
+            line: i + 1,
+            reason,
+        };
+        if line.trim().is_empty() || line.starts_with('#') {
+            continue;
+        }
+        if line.starts_with(' ') {
+            let value: &mut String = last
+                .and_then(|key| messages.get_mut(key))
+                .ok_or_else(|| error("continuation line without message"))?;
+            if !value.is_empty() {
+                value.push('\n');
+            }
+            value.push_str(line.trim());
+            continue;
+        }
+        let (key, value) = line
+            .split_once('=')
+            .ok_or_else(|| error("message without `=`"))?;
+        let key = key.trim();
+        if messages.insert(key, value.trim().to_owned()).is_some() {
+            return Err(error("duplicate message"));
+        }
+        last = Some(key);
+    }
+    Ok(messages)
+}
+
+static CATALOGUES: OnceLock<Vec<HashMap<&'static str, String>>> = OnceLock::new();
+
+fn parse_catalogues() -> Result<Vec<HashMap<&'static str, String>>, CatalogueError> {
+    Locale::ALL.into_iter().map(parse_catalogue).collect()
+}
+
+/// Parse the catalogues, so a malformed one stops the bot at startup.
+pub fn load() -> Result<(), CatalogueError> {
+    let catalogues = parse_catalogues()?;
+    // Already loaded if a message was looked up before
+    let _ = CATALOGUES.set(catalogues);
+    Ok(())
+}
+
+fn catalogue(locale: Locale) -> &'static HashMap<&'static str, String> {
+    let catalogues = CATALOGUES.get_or_init(|| {
+        parse_catalogues().unwrap_or_else(|e| {
+            tracing::error!("Failed to load the catalogues: {e}");
+            vec![HashMap::new(); Locale::ALL.len()]
+        })
+    });
+    &catalogues[locale as usize]
+}
+
+/// Look up the message `key` and fill in its placeables.
+pub fn text(locale: Locale, key: &str, args: &[(&str, String)]) -> String {
+    let Some(message) = catalogue(locale)
+        .get(key)
+        .or_else(|| catalogue(Locale::En).get(key))
+    else {
+        tracing::error!("Missing message `{key}`");
+        return key.to_owned();
+    };
+    let mut message = message.clone();
+    for (name, value) in args {
+        message = message.replace(&format!("{{ ${name} }}"), value);
+    }
+    message
+}
+
+/// Look up a message like `t!(locale, "key", name = value)`.
+macro_rules! t {
+    ($locale:expr, $key:literal $(, $name:ident = $value:expr)* $(,)?) => {
+        $crate::i18n::text(
+            $locale,
+            $key,
+            &[$((stringify!($name), ::std::string::ToString::to_string(&$value))),*],
+        )
+    };
+}
+pub(crate) use t;
+
+/// Message that is localized once the language of its reader is known, e.g. an error returned
+/// by a command. Displays in English.
+#[derive(Debug, Clone)]
+pub struct Text {
+    key: &'static str,
+    args: Vec<(&'static str, String)>,
+}
+
+impl Text {
+    pub fn new(key: &'static str) -> Self {
+        Self {
+            key,
+            args: Vec::new(),
+        }
+    }
+
+    pub fn arg(mut self, name: &'static str, value: impl Display) -> Self {
+        self.args.push((name, value.to_string()));
+        self
+    }
+
+    pub fn render(&self, locale: Locale) -> String {
+        text(locale, self.key, &self.args)
+    }
+}
+
+impl Display for Text {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.render(Locale::En))
+    }
+}
+
+impl std::error::Error for Text {}
+
+/// Localize an error for the user, if it is a [`Text`].
+pub fn error_text(error: &Error, locale: Locale) -> String {
+    match error.downcast_ref::<Text>() {
+        Some(text) => text.render(locale),
+        None => error.to_string(),
+    }
+}
+
+/// Language of `user_id`: the language they picked with `/language`, otherwise the language of
+/// their Discord client if it is known and translated.
+pub async fn locale_of(db: &sqlx::PgPool, user_id: i64, discord_locale: Option<&str>) -> Locale {
+    match db::get_language(db, user_id).await {
+        Ok(Some(locale)) => return locale,
+        Ok(None) => {}
+        Err(e) => tracing::warn!("Failed to get the language of {user_id}: {e}"),
+    }
+    discord_locale
+        .and_then(Locale::from_discord)
+        .unwrap_or_default()
+}
+
+/// Language of the author of a command.
+pub async fn locale(ctx: Context<'_>) -> Locale {
+    locale_of(
+        &ctx.data().db,
+        ctx.author().id.get() as i64,
+        Some(&ctx.interaction.locale),
+    )
+    .await
+}
+
+/// Language of the user of a component.
+pub async fn component_locale(data: &Data, i: &serenity::ComponentInteraction) -> Locale {
+    locale_of(&data.db, i.user.id.get() as i64, Some(&i.locale)).await
+}
+
+/// Fill in the descriptions of the commands and their parameters from the catalogues. They are
+/// looked up as `cmd-<command>` and `cmd-<command>-<parameter>`, and subcommands continue the key
+/// of their parent like `cmd-<command>-<subcommand>`.
+pub fn localize_commands(commands: &mut [poise::Command<Data, Error>]) {
+    localize_commands_under("cmd", commands);
+}
+
+fn localize_commands_under(prefix: &str, commands: &mut [poise::Command<Data, Error>]) {
+    fn localize(
+        key: &str,
+        description: &mut Option<String>,
+        localizations: &mut HashMap<String, String>,
+    ) {
+        *description = Some(text(Locale::En, key, &[]));
+        for locale in Locale::ALL {
+            let Some(message) = catalogue(locale).get(key) else {
+                continue;
+            };
+            for discord_locale in locale.discord_locales() {
+                localizations.insert((*discord_locale).to_owned(), message.clone());
+            }
+        }
+    }
+
+    for command in commands {
+        let key = format!("{prefix}-{}", command.name);
+        localize(
+            &key,
+            &mut command.description,
+            &mut command.description_localizations,
+        );
+        for parameter in &mut command.parameters {
+            localize(
+                &format!("{key}-{}", parameter.name),
+                &mut parameter.description,
+                &mut parameter.description_localizations,
+            );
+        }
+        localize_commands_under(&key, &mut command.subcommands);
+    }
+}
+
+#[poise::command(slash_command, ephemeral)]
+#[tracing::instrument(err, skip(ctx), fields(user = ctx.author().id.get()))]
+pub async fn language(ctx: Context<'_>, language: Option<Locale>) -> Result<(), Error> {
+    db::set_language(ctx, language).await?;
+    let locale = locale(ctx).await;
+    let reply = match language {
+        Some(_) => t!(locale, "language-set"),
+        None => t!(locale, "language-reset"),
+    };
+    ctx.reply(reply).await?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Placeables of a message, sorted.
+    fn placeables(message: &str) -> Vec<&str> {
+        let mut names: Vec<_> = message
+            .split("{ $")
+            .skip(1)
+            .filter_map(|rest| rest.split_once(" }").map(|(name, _)| name))
+            .collect();
+        names.sort_unstable();
+        names
+    }
+
+    #[test]
+    fn catalogues_load() {
+        if let Err(e) = load() {
+            panic!("{e}");
+        }
+    }
+
+    #[test]
+    fn catalogues_match_english() {
+        let english = catalogue(Locale::En);
+        for locale in Locale::ALL {
+            for (key, message) in catalogue(locale) {
+                let Some(original) = english.get(key) else {
+                    panic!("{} has unknown message `{key}`", locale.code());
+                };
+                assert_eq!(
+                    placeables(message),
+                    placeables(original),
+                    "placeables of `{key}` differ in {}",
+                    locale.code()
+                );
+            }
+            for key in english.keys() {
+                assert!(
+                    catalogue(locale).contains_key(key),
+                    "{} is missing `{key}`",
+                    locale.code()
+                );
+            }
+        }
+    }
+
+    /// Every message used in the source exists in English.
+    #[test]
+    fn used_messages_exist() {
+        let dir = concat!(env!("CARGO_MANIFEST_DIR"), "/src");
+        for entry in std::fs::read_dir(dir).unwrap() {
+            let path = entry.unwrap().path();
+            if path.extension() != Some("rs".as_ref()) || path.ends_with("i18n.rs") {
+                continue;
+            }
+            let source = std::fs::read_to_string(&path).unwrap();
+            let calls = ["t!(", "Text::new("]
+                .iter()
+                .flat_map(|m| source.match_indices(m))
+                // Skip other macros ending in `t!`, like `format!`
+                .filter(|(i, _)| {
+                    !source[..*i].ends_with(|c: char| c.is_alphanumeric() || c == '_')
+                });
+            for (i, m) in calls {
+                let mut args = &source[i + m.len()..];
+                if m == "t!(" {
+                    args = args.split_once(',').unwrap().1;
+                }
+                // Keys chosen at runtime couldn't be checked
+                let Some(rest) = args.trim_start().strip_prefix('"') else {
+                    panic!("{} uses a message key that isn't a literal", path.display());
+                };
+                let key = rest.split('"').next().unwrap();
+                assert!(
+                    catalogue(Locale::En).contains_key(key),
+                    "{} uses missing message `{key}`",
+                    path.display()
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn commands_are_described() {
+        fn check(prefix: &str, commands: &[poise::Command<Data, Error>]) {
+            let english = catalogue(Locale::En);
+            for command in commands {
+                let key = format!("{prefix}-{}", command.name);
+                assert!(english.contains_key(key.as_str()), "missing `{key}`");
+                for parameter in &command.parameters {
+                    let key = format!("{key}-{}", parameter.name);
+                    assert!(english.contains_key(key.as_str()), "missing `{key}`");
+                }
+                check(&key, &command.subcommands);
+            }
+        }
+        check("cmd", &crate::commands());
+    }
+}
@@ -0,0 +1,308 @@
+use std::collections::BTreeSet;
+
+use chrono::Utc;
+use poise::serenity_prelude::{
+    self as serenity, ButtonStyle, ComponentInteraction, ComponentInteractionDataKind,
+    CreateActionRow, CreateButton, CreateInteractionResponse, CreateSelectMenu,
+    CreateSelectMenuKind, CreateSelectMenuOption,
+};
+
+use crate::{
+    calendar::{parse_events, CalendarEvent},
+    cancel::selected_in,
+    components::{ComponentAction, ImportAction},
+    db,
+    i18n::{self, t, Locale, Text},
+    interactions::report_error,
+    lfs::{check_team_name, parse_rank_range, LookingForScrim, Platform, RankRange, Region},
+    overlaps::check_overlaps,
+    preferences::Preferences,
+    render::View,
+    Context, Data, Error,
+};
+
+/// Most events one import can post, as select menus have at most 25 options.
+const MAX_EVENTS: usize = 25;
+/// Largest calendar file accepted, in bytes.
+const MAX_SIZE: u32 = 1024 * 1024;
+
+/// Shorten `text` to at most `max` characters.
+fn truncate(mut text: String, max: usize) -> String {
+    if text.chars().count() > max {
+        text = text.chars().take(max - 1).collect();
+        text.push('…');
+    }
+    text
+}
+
+/// Label of an event in the select menu.
+fn event_label(event: &CalendarEvent, prefs: Preferences, locale: Locale) -> String {
+    let mut label = prefs.long(event.start, locale);
+    if let Some(summary) = &event.summary {
+        label.push_str(&format!(" – {summary}"));
+    }
+    truncate(label, 100)
+}
+
+/// Line of an event in the preview. It is kept short, so the preview of all events stays within
+/// the length of a message.
+fn event_line(event: &CalendarEvent, prefs: Preferences) -> String {
+    let mut line = prefs.short(event.start);
+    if let Some(summary) = &event.summary {
+        line.push_str(&format!(" – {}", truncate(summary.clone(), 30)));
+    }
+    line
+}
+
+/// Preview of an import, stored until its events are posted.
+pub struct Import {
+    /// Posting each event is posted like, at the start of the event.
+    pub template: LookingForScrim,
+    pub events: Vec<CalendarEvent>,
+    /// Number of events left out because the file had too many.
+    pub skipped: usize,
+}
+
+/// Preview of the postings import `import_id` would create, with the events in `selected` checked.
+fn preview(
+    import: &Import,
+    selected: &BTreeSet<usize>,
+    prefs: Preferences,
+    locale: Locale,
+    import_id: i32,
+) -> Result<View, Error> {
+    use std::fmt::Write;
+
+    let Import {
+        template,
+        events,
+        skipped,
+    } = import;
+    let custom_id = |action| ComponentAction::Import { import_id, action }.custom_id();
+
+    let mut content = format!("## {}\n", t!(locale, "import-title", count = events.len()));
+    writeln!(
+        content,
+        "{}",
+        t!(
+            locale,
+            "import-template",
+            team = template
+                .team_name
+                .clone()
+                .unwrap_or_else(|| t!(locale, "unnamed-team")),
+            region = format!("{:?}", template.region),
+            platform = format!("{:?}", template.platform),
+            range = template.range
+        )
+    )
+    .unwrap();
+    for (index, event) in events.iter().enumerate() {
+        let mark = if selected.contains(&index) {
+            "☑"
+        } else {
+            "☐"
+        };
+        writeln!(content, "{mark} {}", event_line(event, prefs)).unwrap();
+    }
+    if *skipped > 0 {
+        writeln!(
+            content,
+            "-# {}",
+            t!(locale, "import-skipped", count = skipped)
+        )
+        .unwrap();
+    }
+
+    let options = events
+        .iter()
+        .enumerate()
+        .map(|(index, event)| {
+            CreateSelectMenuOption::new(event_label(event, prefs, locale), index.to_string())
+                .default_selection(selected.contains(&index))
+        })
+        .collect();
+    Ok(View::new(content).components(vec![
+        CreateActionRow::SelectMenu(
+            CreateSelectMenu::new(
+                custom_id(ImportAction::Select)?,
+                CreateSelectMenuKind::String { options },
+            )
+            .placeholder(t!(locale, "import-select"))
+            .min_values(0)
+            .max_values(events.len() as u8),
+        ),
+        CreateActionRow::Buttons(vec![
+            CreateButton::new(custom_id(ImportAction::Post)?)
+                .style(ButtonStyle::Success)
+                .label(t!(locale, "import-post", count = selected.len()))
+                .disabled(selected.is_empty()),
+            CreateButton::new(custom_id(ImportAction::Cancel)?)
+                .style(ButtonStyle::Danger)
+                .label(t!(locale, "button-cancel")),
+        ]),
+    ]))
+}
+
+#[poise::command(slash_command, ephemeral)]
+#[tracing::instrument(err, skip(ctx, file), fields(user = ctx.author().id.get()))]
+pub async fn import(
+    ctx: Context<'_>,
+    file: serenity::Attachment,
+    category: Option<String>,
+    region: Option<Region>,
+    platform: Option<Platform>,
+    range: Option<String>,
+    team_name: Option<String>,
+) -> Result<(), Error> {
+    let locale = i18n::locale(ctx).await;
+    check_team_name(team_name.as_deref())?;
+    if file.size > MAX_SIZE || !file.filename.to_lowercase().ends_with(".ics") {
+        return Err(Text::new("import-invalid-file").into());
+    }
+    let creator_id = ctx.author().id.get() as i64;
+    let prefs = db::get_preferences(ctx).await?;
+
+    // Whatever isn't given is taken from the latest posting of the team
+    let latest = db::get_latest_posting(&ctx.data().db, creator_id, team_name.as_deref()).await?;
+    let missing = || Text::new("import-missing-defaults");
+    let template = LookingForScrim {
+        id: 0,
+        creator_id,
+        team_name: team_name.or_else(|| latest.as_ref()?.team_name.clone()),
+        region: region
+            .or(latest.as_ref().map(|latest| latest.region))
+            .ok_or_else(missing)?,
+        platform: platform
+            .or(latest.as_ref().map(|latest| latest.platform))
+            .ok_or_else(missing)?,
+        range: match range {
+            Some(range) => RankRange(parse_rank_range(&range)?),
+            None => latest.map(|latest| latest.range).ok_or_else(missing)?,
+        },
+        time: Utc::now(),
+        match_id: None,
+        cancelled: false,
+        checked_in: false,
+        no_show: false,
+        reliability: None,
+        auto_match: false,
+    };
+
+    let source =
+        String::from_utf8(file.download().await?).map_err(|_| Text::new("import-invalid-file"))?;
+    let now = Utc::now();
+    let mut events: Vec<_> = parse_events(&source, prefs.timezone)
+        .into_iter()
+        .filter(|event| event.start > now)
+        .filter(|event| {
+            category.as_ref().is_none_or(|category| {
+                event
+                    .categories
+                    .iter()
+                    .any(|c| c.eq_ignore_ascii_case(category))
+            })
+        })
+        .collect();
+    if events.is_empty() {
+        ctx.reply(t!(locale, "import-none")).await?;
+        return Ok(());
+    }
+    events.sort_by_key(|event| event.start);
+    let skipped = events.len().saturating_sub(MAX_EVENTS);
+    events.truncate(MAX_EVENTS);
+
+    let import = Import {
+        template,
+        events,
+        skipped,
+    };
+    let import_id = db::create_import(&ctx.data().db, &import).await?;
+    let selected = (0..import.events.len()).collect();
+    ctx.send(preview(&import, &selected, prefs, locale, import_id)?.reply())
+        .await?;
+    Ok(())
+}
+
+/// Handle the components of the preview of `/import`. The events are loaded from the database and
+/// the selection is kept in the message, so they keep working after restarts.
+pub async fn handle_component(
+    ctx: &serenity::Context,
+    data: &Data,
+    i: &ComponentInteraction,
+    import_id: i32,
+    action: ImportAction,
+) -> Result<(), Error> {
+    match handle_action(ctx, data, i, import_id, action).await {
+        Ok(()) => Ok(()),
+        Err(e) => report_error(ctx, data, i, e).await,
+    }
+}
+
+async fn handle_action(
+    ctx: &serenity::Context,
+    data: &Data,
+    i: &ComponentInteraction,
+    import_id: i32,
+    action: ImportAction,
+) -> Result<(), Error> {
+    let db = &data.db;
+    let creator_id = i.user.id.get() as i64;
+    let locale = i18n::component_locale(data, i).await;
+
+    let expired = || Text::new("button-expired");
+    let view = match action {
+        ImportAction::Select => {
+            let import = db::get_import(db, import_id, creator_id)
+                .await?
+                .ok_or_else(expired)?;
+            let selected = match &i.data.kind {
+                ComponentInteractionDataKind::StringSelect { values } => {
+                    values.iter().filter_map(|v| v.parse().ok()).collect()
+                }
+                _ => BTreeSet::new(),
+            };
+            let prefs = db::get_preferences_of(db, creator_id).await?;
+            preview(&import, &selected, prefs, locale, import_id)?
+        }
+        ImportAction::Post => {
+            let import = db::get_import(db, import_id, creator_id)
+                .await?
+                .ok_or_else(expired)?;
+            let selected: BTreeSet<usize> = selected_in(&i.message);
+            // Events might have started while the preview was open
+            let now = Utc::now();
+            let postings = selected
+                .iter()
+                .filter_map(|&index| import.events.get(index))
+                .filter(|event| event.start > now)
+                .map(|event| LookingForScrim {
+                    time: event.start,
+                    ..import.template.clone()
+                })
+                .collect::<Vec<_>>();
+            let guild_id = i.guild_id.map(|id| id.get() as i64);
+            // The preview stays if posting fails, e.g. because the postings overlap
+            let warning = check_overlaps(db, guild_id, &postings, false).await?;
+            let policy = db::get_overlap_policy(db, guild_id).await?;
+            let posted = db::post_import(db, import_id, creator_id, postings, policy)
+                .await?
+                .ok_or_else(expired)?;
+            let mut content = t!(locale, "import-posted", count = posted.len());
+            if let Some(warning) = warning {
+                content = format!("{}\n{content}", warning.render(locale));
+            }
+            View::new(content)
+        }
+        ImportAction::Cancel => {
+            db::delete_import(db, import_id, creator_id).await?;
+            View::new(t!(locale, "import-cancelled"))
+        }
+    };
+    i.create_response(
+        ctx,
+        CreateInteractionResponse::UpdateMessage(view.response()),
+    )
+    .await?;
+    Ok(())
+}
@@ -0,0 +1,173 @@
+use std::time::Duration;
+
+use poise::serenity_prelude::{
+    self as serenity, ComponentInteraction, CreateInteractionResponse,
+    CreateInteractionResponseMessage, CreateModal, FullEvent, Interaction,
+    ModalInteractionCollector,
+};
+
+use crate::{
+    auto_match, board, browse, cancel, check_in,
+    components::ComponentAction,
+    history,
+    i18n::{self, t, Locale},
+    import, invites, lfs, no_show, proposals, results, scrims, Data, Error,
+};
+
+/// How long to wait for a modal to be submitted.
+pub const MODAL_TIMEOUT: Duration = Duration::from_secs(30 * 60);
+
+/// Route component interactions, e.g. buttons in direct messages sent by the scheduler or in the
+/// replies to `/scrims`. Everything they need is in their custom id or the database, so they keep
+/// working after restarts.
+pub async fn handle_event(
+    ctx: &serenity::Context,
+    event: &FullEvent,
+    data: &Data,
+) -> Result<(), Error> {
+    let FullEvent::InteractionCreate {
+        interaction: Interaction::Component(i),
+    } = event
+    else {
+        return Ok(());
+    };
+
+    let Some(action) = ComponentAction::parse(&i.data.custom_id) else {
+        return expired(ctx, data, i).await;
+    };
+    match action {
+        ComponentAction::Lfs { action, draft_id } => {
+            lfs::handle_component(ctx, data, i, action, draft_id).await
+        }
+        ComponentAction::LfsForm {
+            action,
+            region,
+            platform,
+        } => lfs::handle_form(ctx, data, i, action, region, platform).await,
+        ComponentAction::Scrims(action) => scrims::handle_component(ctx, data, i, action).await,
+        ComponentAction::Cancel {
+            action,
+            page,
+            filter,
+        } => cancel::handle_component(ctx, data, i, action, page, filter).await,
+        ComponentAction::CheckIn { scrim_id } => {
+            check_in::handle_component(ctx, data, i, scrim_id, None).await
+        }
+        ComponentAction::Rematch {
+            scrim_id,
+            candidate_id,
+        } => check_in::handle_component(ctx, data, i, scrim_id, Some(candidate_id)).await,
+        ComponentAction::NoShow { scrim_id } => {
+            no_show::handle_component(ctx, data, i, scrim_id).await
+        }
+        ComponentAction::AutoMatch { scrim_id } => {
+            auto_match::handle_component(ctx, data, i, scrim_id).await
+        }
+        ComponentAction::Report { scrim_id } => {
+            results::handle_component(ctx, data, i, scrim_id).await
+        }
+        ComponentAction::Invite { action, invite_id } => {
+            invites::handle_component(ctx, data, i, action, invite_id).await
+        }
+        ComponentAction::Proposal {
+            answer,
+            proposal_id,
+        } => proposals::handle_component(ctx, data, i, answer, proposal_id).await,
+        ComponentAction::Challenge { scrim_id } => {
+            board::handle_challenge(ctx, data, i, scrim_id).await
+        }
+        ComponentAction::ChallengeAnswer {
+            answer,
+            scrim_id,
+            challenger,
+        } => board::handle_answer(ctx, data, i, answer, scrim_id, challenger).await,
+        ComponentAction::History { filter_id, action } => {
+            history::handle_component(ctx, data, i, filter_id, action).await
+        }
+        ComponentAction::Browse { filter_id, action } => {
+            browse::handle_component(ctx, data, i, filter_id, action).await
+        }
+        ComponentAction::Import { import_id, action } => {
+            import::handle_component(ctx, data, i, import_id, action).await
+        }
+    }
+}
+
+/// Tell the user that the component can't be used anymore, e.g. because it was sent by an older
+/// version of the bot or what it belongs to is gone.
+async fn expired(
+    ctx: &serenity::Context,
+    data: &Data,
+    i: &ComponentInteraction,
+) -> Result<(), Error> {
+    let locale = i18n::component_locale(data, i).await;
+    i.create_response(
+        ctx,
+        CreateInteractionResponse::Message(
+            CreateInteractionResponseMessage::new()
+                .content(t!(locale, "button-expired"))
+                .ephemeral(true),
+        ),
+    )
+    .await?;
+    Ok(())
+}
+
+/// Show the error of a component handler to the user, who would otherwise only see that the
+/// interaction failed.
+pub async fn report_error(
+    ctx: &serenity::Context,
+    data: &Data,
+    i: &ComponentInteraction,
+    error: Error,
+) -> Result<(), Error> {
+    tracing::error!("An error occured in a component: {error}");
+    let locale = i18n::component_locale(data, i).await;
+    i.create_response(
+        ctx,
+        CreateInteractionResponse::Message(
+            CreateInteractionResponseMessage::new()
+                .content(i18n::error_text(&error, locale))
+                .ephemeral(true),
+        ),
+    )
+    .await?;
+    Ok(())
+}
+
+/// Modal whose title and inputs are shown in the language of the user. The derived
+/// [`poise::Modal`] parses the submission, so the inputs must use the field names as custom ids.
+pub trait LocalizedModal: poise::Modal {
+    fn create(locale: Locale, custom_id: String) -> CreateModal;
+}
+
+/// Show a modal in response to a component interaction and wait for it to be submitted. Works like
+/// [`poise::execute_modal_on_component_interaction`], but without a poise context.
+pub async fn execute_modal<M: LocalizedModal>(
+    ctx: &serenity::Context,
+    i: &ComponentInteraction,
+    locale: Locale,
+    timeout: Duration,
+) -> Result<Option<M>, serenity::Error> {
+    let modal_id = i.id.to_string();
+    i.create_response(
+        ctx,
+        CreateInteractionResponse::Modal(<M as LocalizedModal>::create(locale, modal_id.clone())),
+    )
+    .await?;
+
+    let Some(response) = ModalInteractionCollector::new(&ctx.shard)
+        .filter(move |m| m.data.custom_id == modal_id)
+        .timeout(timeout)
+        .await
+    else {
+        return Ok(None);
+    };
+    response
+        .create_response(ctx, CreateInteractionResponse::Acknowledge)
+        .await?;
+
+    Ok(Some(
+        M::parse(response.data.clone()).map_err(serenity::Error::Other)?,
+    ))
+}
@@ -0,0 +1,217 @@
+use chrono::{DateTime, Utc};
+use poise::serenity_prelude::{
+    self as serenity, ButtonStyle, ComponentInteraction, CreateActionRow, CreateButton,
+    CreateInteractionResponse, CreateInteractionResponseMessage, CreateMessage,
+};
+
+use crate::{
+    components::{ComponentAction, InviteAction},
+    db,
+    i18n::{self, t, Text},
+    lfs::LookingForScrim,
+    overlaps::overlap_warning,
+    proposals::propose_time,
+    reminders::send_dm,
+    render::{scrim_card, write_scrim_meta, write_scrim_with_name, View},
+    Data, Error,
+};
+
+/// Status of invites and proposals.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, enum_utils::FromStr)]
+pub enum InviteStatus {
+    Pending,
+    Accepted,
+    Declined,
+}
+
+/// Invitation of a specific captain to play against a posting.
+#[derive(Debug, Clone)]
+pub struct Invite {
+    pub id: i32,
+    pub scrim_id: i32,
+    pub target_id: i64,
+    pub target_team_name: Option<String>,
+    pub status: InviteStatus,
+}
+
+/// Invite `target_id` to play against `scrim` via a direct message.
+pub async fn send_invite(
+    ctx: &serenity::Context,
+    db: &sqlx::PgPool,
+    scrim: &LookingForScrim,
+    target_id: i64,
+    target_team_name: Option<&str>,
+    title: Text,
+) -> Result<(), Error> {
+    let locale = i18n::locale_of(db, target_id, None).await;
+    let id = db::create_invite(db, scrim.id, target_id, target_team_name).await?;
+    let invite_id = |action| {
+        ComponentAction::Invite {
+            action,
+            invite_id: id,
+        }
+        .custom_id()
+    };
+
+    let content = t!(
+        locale,
+        "invite",
+        title = title.render(locale),
+        user = scrim.creator_id
+    );
+    let msg = View::new(content)
+        .embed(scrim_card(scrim, locale, Utc::now()))
+        .components(vec![CreateActionRow::Buttons(vec![
+            CreateButton::new(invite_id(InviteAction::Accept)?)
+                .style(ButtonStyle::Success)
+                .label(t!(locale, "button-accept")),
+            CreateButton::new(invite_id(InviteAction::Decline)?)
+                .style(ButtonStyle::Danger)
+                .label(t!(locale, "button-decline")),
+            CreateButton::new(invite_id(InviteAction::Propose)?)
+                .style(ButtonStyle::Secondary)
+                .label(t!(locale, "button-propose-other")),
+        ])])
+        .message();
+    send_dm(ctx, target_id, msg)
+        .await
+        .map_err(|_| Text::new("invite-failed").arg("user", target_id))?;
+    Ok(())
+}
+
+/// Handle the buttons of invites sent in direct messages.
+pub async fn handle_component(
+    ctx: &serenity::Context,
+    data: &Data,
+    i: &ComponentInteraction,
+    action: InviteAction,
+    invite_id: i32,
+) -> Result<(), Error> {
+    let invite = db::get_invite(&data.db, invite_id).await?;
+    let locale = i18n::component_locale(data, i).await;
+    if invite.target_id != i.user.id.get() as i64 {
+        i.create_response(
+            ctx,
+            CreateInteractionResponse::Message(
+                CreateInteractionResponseMessage::new()
+                    .content(t!(locale, "scrim-not-yours"))
+                    .ephemeral(true),
+            ),
+        )
+        .await?;
+        return Ok(());
+    }
+    let scrim = db::get_scrim_in(&data.db, invite.scrim_id).await?;
+
+    let content = match action {
+        _ if invite.status != InviteStatus::Pending => t!(locale, "invite-answered"),
+        InviteAction::Accept => match accept(data, i, &invite, &scrim).await {
+            Ok(times) => {
+                let mut opponent = String::new();
+                write_scrim_with_name(&scrim, None, true, locale, Utc::now(), &mut opponent);
+                notify_creator(ctx, data, &scrim, &invite, InviteStatus::Accepted).await;
+                let content = t!(locale, "invite-accepted", opponent = opponent);
+                if times.is_empty() {
+                    content
+                } else {
+                    format!("{}\n{content}", overlap_warning(&times).render(locale))
+                }
+            }
+            Err(e) => i18n::error_text(&e, locale),
+        },
+        InviteAction::Propose => {
+            // The modal takes care of the response
+            return propose_time(
+                ctx,
+                data,
+                i,
+                &scrim,
+                None,
+                Some(invite.id),
+                scrim.creator_id,
+            )
+            .await;
+        }
+        InviteAction::Decline => {
+            if db::answer_invite(&data.db, invite.id, InviteStatus::Declined).await? {
+                notify_creator(ctx, data, &scrim, &invite, InviteStatus::Declined).await;
+                t!(locale, "invite-declined")
+            } else {
+                t!(locale, "invite-answered")
+            }
+        }
+    };
+
+    i.create_response(
+        ctx,
+        CreateInteractionResponse::UpdateMessage(
+            CreateInteractionResponseMessage::new()
+                .content(content)
+                .components(vec![]),
+        ),
+    )
+    .await?;
+    Ok(())
+}
+
+/// Accept an invite by creating a matched posting for the invited side. Returns the times of other
+/// matched scrims of the invited team it overlaps with.
+async fn accept(
+    data: &Data,
+    i: &ComponentInteraction,
+    invite: &Invite,
+    scrim: &LookingForScrim,
+) -> Result<Vec<DateTime<Utc>>, Error> {
+    if !db::has_timezone(&data.db, invite.target_id).await? {
+        return Err(Text::new("timezone-missing").into());
+    }
+    let guild_id = i.guild_id.map(|id| id.get() as i64);
+    let policy = db::get_overlap_policy(&data.db, guild_id).await?;
+    db::accept_invite(&data.db, invite, invited_posting(invite, scrim), policy).await
+}
+
+/// Posting of the invited side that is matched with `scrim` once the invite is accepted.
+pub fn invited_posting(invite: &Invite, scrim: &LookingForScrim) -> LookingForScrim {
+    LookingForScrim {
+        id: 0,
+        creator_id: invite.target_id,
+        team_name: invite.target_team_name.clone(),
+        match_id: Some(scrim.id),
+        cancelled: false,
+        checked_in: false,
+        no_show: false,
+        reliability: None,
+        auto_match: false,
+        ..scrim.clone()
+    }
+}
+
+async fn notify_creator(
+    ctx: &serenity::Context,
+    data: &Data,
+    scrim: &LookingForScrim,
+    invite: &Invite,
+    answer: InviteStatus,
+) {
+    let locale = i18n::locale_of(&data.db, scrim.creator_id, None).await;
+    let mut meta = String::new();
+    write_scrim_meta(scrim, None, locale, Utc::now(), &mut meta);
+    let content = if answer == InviteStatus::Accepted {
+        t!(
+            locale,
+            "invite-accepted-notice",
+            user = invite.target_id,
+            scrim = meta
+        )
+    } else {
+        t!(
+            locale,
+            "invite-declined-notice",
+            user = invite.target_id,
+            scrim = meta
+        )
+    };
+    if let Err(e) = send_dm(ctx, scrim.creator_id, CreateMessage::new().content(content)).await {
+        tracing::warn!("Could not notify {} about invite: {e}", scrim.creator_id);
+    }
+}
@@ -1,33 +1,43 @@
-use std::{ops::Range, time::Duration};
+use std::ops::Range;
 
-use chrono::Utc;
+use chrono::{DateTime, Datelike, NaiveDate, Utc, Weekday};
 use poise::{
     serenity_prelude::{
-        self as serenity, ButtonStyle, CreateActionRow, CreateButton,
-        CreateInteractionResponseMessage,
+        self as serenity, ButtonStyle, ComponentInteraction, ComponentInteractionDataKind,
+        CreateActionRow, CreateButton, CreateInputText, CreateInteractionResponse,
+        CreateInteractionResponseMessage, CreateModal, CreateSelectMenu, CreateSelectMenuKind,
+        CreateSelectMenuOption, InputTextStyle,
     },
-    ChoiceParameter, CreateReply,
+    ChoiceParameter, Modal,
 };
 
-use crate::{db, Context, Error};
+use crate::{
+    auto_match::AUTO_MATCH_GRACE,
+    components::{ComponentAction, DraftAction, FormAction},
+    db,
+    i18n::{self, t, Locale, Text},
+    interactions::{execute_modal, LocalizedModal, MODAL_TIMEOUT},
+    overlaps::check_overlaps,
+    preferences::Preferences,
+    preset::{complete_preset, find_preset},
+    render::{scrim_card, View},
+    Context, Data, Error,
+};
 
-#[derive(ChoiceParameter, Debug, Clone, enum_utils::FromStr)]
+#[derive(ChoiceParameter, Debug, Clone, Copy, PartialEq, Eq, enum_utils::FromStr)]
 pub enum Region {
     EU,
     NA,
 }
 
-#[derive(ChoiceParameter, Debug, Clone, enum_utils::FromStr)]
+#[derive(ChoiceParameter, Debug, Clone, Copy, PartialEq, Eq, enum_utils::FromStr)]
 pub enum Platform {
     PC,
     Console,
 }
 
-fn parse_rank(s: &str) -> Result<u32, String> {
-    let err = format!(
-        "Invalid rank range. Must be formatted like `4.3k` or `4k-4.5k`. You entered: `{}`",
-        s
-    );
+fn parse_rank(s: &str) -> Result<u32, Text> {
+    let err = Text::new("rank-invalid").arg("input", s);
     let s = s.trim().trim_end_matches('k');
     let Ok(rank) = s.parse::<f64>() else {
         return Err(err);
@@ -35,7 +45,7 @@ fn parse_rank(s: &str) -> Result<u32, String> {
     Ok((rank * 1000.) as u32)
 }
 
-fn parse_rank_range(s: &str) -> Result<Range<u32>, String> {
+pub fn parse_rank_range(s: &str) -> Result<Range<u32>, Text> {
     let s = s.trim();
     if let Some((from, to)) = s.split_once('-') {
         let from = parse_rank(from)?;
@@ -47,6 +57,191 @@ fn parse_rank_range(s: &str) -> Result<Range<u32>, String> {
     }
 }
 
+pub fn check_team_name(team_name: Option<&str>) -> Result<(), Text> {
+    if let Some(team_name) = team_name {
+        if team_name.len() > 50 {
+            return Err(Text::new("team-name-too-long"));
+        } else if team_name.len() < 3 {
+            return Err(Text::new("team-name-too-short"));
+        }
+    }
+    Ok(())
+}
+
+/// First day of the week containing `date`, for weeks beginning on `week_start`.
+fn week_of(date: NaiveDate, week_start: Weekday) -> NaiveDate {
+    let offset =
+        (7 + date.weekday().num_days_from_monday() - week_start.num_days_from_monday()) % 7;
+    date - chrono::Duration::days(offset.into())
+}
+
+/// Date of a day like `next friday` or `last mon` relative to the week containing `today`, for
+/// weeks beginning on `week_start`. `this friday` is the friday of the current week, even if it
+/// already passed.
+fn relative_weekday(text: &str, today: NaiveDate, week_start: Weekday) -> Option<NaiveDate> {
+    let words: Vec<String> = text.split_whitespace().map(str::to_lowercase).collect();
+    words.windows(2).find_map(|pair| {
+        let weeks = match pair[0].as_str() {
+            "last" => -1,
+            "this" => 0,
+            "next" => 1,
+            _ => return None,
+        };
+        let day = pair[1].parse::<Weekday>().ok()?;
+        let offset = (7 + day.num_days_from_monday() - week_start.num_days_from_monday()) % 7;
+        Some(
+            week_of(today, week_start)
+                + chrono::Duration::weeks(weeks)
+                + chrono::Duration::days(offset.into()),
+        )
+    })
+}
+
+/// Parse the date of a day like `next friday` or `july 4th` relative to `today`.
+fn parse_day(text: &str, today: NaiveDate, week_start: Weekday) -> Option<NaiveDate> {
+    relative_weekday(text, today, week_start)
+        .or_else(|| date_time_parser::DateParser::parse_relative(text, today))
+}
+
+/// Parse a start time like `8:30pm` or `tomorrow 8pm` in the timezone of `prefs`.
+pub fn parse_time(time: &str, prefs: Preferences) -> Result<chrono::DateTime<Utc>, Text> {
+    let now = Utc::now().with_timezone(&prefs.timezone);
+
+    let date =
+        parse_day(time, now.date_naive(), prefs.week_start.weekday()).unwrap_or(now.date_naive());
+    let Some(time) = date_time_parser::TimeParser::parse_relative(time, now.time()) else {
+        return Err(Text::new("time-missing"));
+    };
+    let time = chrono::NaiveDateTime::new(date, time);
+    let Some(time) = time.and_local_timezone(prefs.timezone).single() else {
+        return Err(Text::new("time-invalid"));
+    };
+    Ok(time.with_timezone(&Utc))
+}
+
+/// Most postings one `/lfs` can create, as a message shows at most 10 embeds.
+const MAX_SLOTS: usize = 10;
+
+/// Whether `text` contains a time of day, e.g. `8pm` but not `friday`.
+fn has_time(text: &str) -> bool {
+    date_time_parser::TimeParser::parse_relative(text, chrono::NaiveTime::MIN).is_some()
+}
+
+/// Split a list of start times like `mon 20, tue 20` into its slots. A part without a time of
+/// day belongs to the next one, as in `friday, 8pm`. A range of days like `mon-fri 20` stands
+/// for the same time on each of those days.
+fn time_slots(times: &str) -> Vec<String> {
+    let mut parts = Vec::new();
+    let mut pending = String::new();
+    for part in times
+        .split(',')
+        .map(str::trim)
+        .filter(|part| !part.is_empty())
+    {
+        if !pending.is_empty() {
+            pending.push(' ');
+        }
+        pending.push_str(part);
+        if has_time(part) {
+            parts.push(std::mem::take(&mut pending));
+        }
+    }
+    if !pending.is_empty() {
+        parts.push(pending);
+    }
+
+    let mut slots = Vec::new();
+    for slot in parts {
+        let words: Vec<&str> = slot.split_whitespace().collect();
+        let days = words.iter().enumerate().find_map(|(index, word)| {
+            let (from, to) = word.split_once('-')?;
+            Some((
+                index,
+                from.parse::<Weekday>().ok()?,
+                to.parse::<Weekday>().ok()?,
+            ))
+        });
+        let Some((index, from, to)) = days else {
+            slots.push(slot);
+            continue;
+        };
+        let mut day = from;
+        loop {
+            let mut words: Vec<String> = words.iter().map(|word| word.to_string()).collect();
+            words[index] = day.to_string().to_lowercase();
+            slots.push(words.join(" "));
+            if day == to {
+                break;
+            }
+            day = day.succ();
+        }
+    }
+    slots
+}
+
+/// Parse a list of start times like `mon 20, tue 20` or `mon-fri 20` in the timezone of `prefs`,
+/// sorted and without duplicates.
+fn parse_times(times: &str, prefs: Preferences) -> Result<Vec<DateTime<Utc>>, Text> {
+    let slots = time_slots(times);
+    if slots.len() > MAX_SLOTS {
+        return Err(Text::new("time-too-many").arg("max", MAX_SLOTS));
+    }
+    let mut times = slots
+        .iter()
+        .map(|slot| parse_time(slot, prefs))
+        .collect::<Result<Vec<_>, _>>()?;
+    if times.is_empty() {
+        return Err(Text::new("time-missing"));
+    }
+    times.sort();
+    times.dedup();
+    Ok(times)
+}
+
+/// Start of the day `date` in `zone`.
+fn start_of(date: NaiveDate, zone: chrono_tz::Tz) -> Option<DateTime<Utc>> {
+    date.and_time(chrono::NaiveTime::MIN)
+        .and_local_timezone(zone)
+        .earliest()
+        .map(|date| date.with_timezone(&Utc))
+}
+
+/// Parse a date like `monday` or `july 4th` as the start of that day in the timezone of `prefs`.
+pub fn parse_date(s: &str, prefs: Preferences) -> Result<chrono::DateTime<Utc>, Text> {
+    let today = Utc::now().with_timezone(&prefs.timezone).date_naive();
+    parse_day(s, today, prefs.week_start.weekday())
+        .and_then(|date| start_of(date, prefs.timezone))
+        .ok_or_else(|| Text::new("date-invalid").arg("input", s))
+}
+
+/// Parse a day like `july 4th` or a week like `last week` as the time it spans in the timezone of
+/// `prefs`. Weeks begin on the first day of the week the user prefers.
+pub fn parse_days(s: &str, prefs: Preferences) -> Result<Range<DateTime<Utc>>, Text> {
+    let today = Utc::now().with_timezone(&prefs.timezone).date_naive();
+    let week_start = prefs.week_start.weekday();
+    let weeks = match s.trim().to_lowercase().as_str() {
+        "last week" => Some(-1),
+        "this week" => Some(0),
+        "next week" => Some(1),
+        _ => None,
+    };
+    let (first, days) = match weeks {
+        Some(weeks) => (
+            Some(week_of(today, week_start) + chrono::Duration::weeks(weeks)),
+            7,
+        ),
+        None => (parse_day(s, today, week_start), 1),
+    };
+    first
+        .and_then(|first| {
+            Some(
+                start_of(first, prefs.timezone)?
+                    ..start_of(first + chrono::Duration::days(days), prefs.timezone)?,
+            )
+        })
+        .ok_or_else(|| Text::new("date-invalid").arg("input", s))
+}
+
 #[derive(Debug, Clone)]
 pub struct RankRange(pub Range<u32>);
 
@@ -61,6 +256,13 @@ pub struct LookingForScrim {
     pub time: chrono::DateTime<Utc>,
     pub match_id: Option<i32>,
     pub cancelled: bool,
+    pub checked_in: bool,
+    /// Whether the partner reported that the creator didn't show up.
+    pub no_show: bool,
+    /// Reliability of the creator between 0 and 1, if known.
+    pub reliability: Option<f32>,
+    /// Whether the posting is paired automatically with other postings that opted in.
+    pub auto_match: bool,
 }
 
 impl std::fmt::Display for RankRange {
@@ -78,115 +280,447 @@ impl std::fmt::Display for RankRange {
     }
 }
 
-#[poise::command(
-    slash_command,
-    description_localized("en-US", "Look for a scrim"),
-    ephemeral
-)]
+#[poise::command(slash_command, ephemeral)]
 #[tracing::instrument(err, skip(ctx), fields(user = ctx.author().id.get()))]
+#[allow(clippy::too_many_arguments)]
 pub async fn lfs(
     ctx: Context<'_>,
-    #[description = "Region to look in"] region: Region,
-    #[description = "Platform to look on"] platform: Platform,
-    #[description = "Single rank or range of ranks to look for, e.g. `4.3k` or `4k-4.5k`"]
-    range: String,
-    #[description = "Start time, e.g. `20`, `8:30pm`, `tomorrow 8pm`, `20 monday` or `july 4th 20`"]
     time: String,
-    #[description = "Optional team name to show in the confirmation message and to other users"]
+    #[autocomplete = "complete_preset"] preset: Option<String>,
+    region: Option<Region>,
+    platform: Option<Platform>,
+    range: Option<String>,
     team_name: Option<String>,
+    auto_match: Option<bool>,
 ) -> Result<(), Error> {
-    if let Some(team_name) = &team_name {
-        if team_name.len() > 50 {
-            return Err("Team name is too long".into());
-        } else if team_name.len() < 3 {
-            return Err("Team name is too short".into());
-        }
-    }
-
-    let zone = db::get_timezone(ctx).await?;
-    let now = Utc::now().with_timezone(&zone);
+    let locale = i18n::locale(ctx).await;
+    let creator_id = ctx.author().id.get() as i64;
 
-    let date = date_time_parser::DateParser::parse_relative(&time, now.date_naive())
-        .unwrap_or(now.date_naive());
-    let Some(time) = date_time_parser::TimeParser::parse_relative(&time, now.time()) else {
-        return Err("No time specified. Please try again".into());
+    // Whatever isn't given is taken from the preset
+    let preset = match preset {
+        Some(name) => Some(find_preset(&ctx.data().db, creator_id, &name).await?),
+        None => None,
     };
-    let time = chrono::NaiveDateTime::new(date, time);
-    let Some(time) = time.and_local_timezone(zone).single() else {
-        return Err("Invalid time".into());
+    let missing = || Text::new("lfs-missing");
+    let region = region
+        .or(preset.as_ref().and_then(|preset| preset.region))
+        .ok_or_else(missing)?;
+    let platform = platform
+        .or(preset.as_ref().and_then(|preset| preset.platform))
+        .ok_or_else(missing)?;
+    let range = match range {
+        Some(range) => parse_rank_range(&range)?,
+        None => {
+            preset
+                .as_ref()
+                .and_then(|preset| preset.range.clone())
+                .ok_or_else(missing)?
+                .0
+        }
     };
-    let time = time.with_timezone(&Utc);
+    let team_name = team_name.or(preset.and_then(|preset| preset.team_name));
 
-    let lfs = LookingForScrim {
-        id: 0,
-        creator_id: ctx.author().id.get() as i64,
-        team_name,
+    let mut postings = new_postings(
+        &ctx.data().db,
+        creator_id,
         region,
         platform,
-        range: RankRange(parse_rank_range(&range)?),
-        time,
-        match_id: None,
-        cancelled: false,
+        range,
+        &time,
+        team_name,
+    )
+    .await?;
+    for lfs in &mut postings {
+        lfs.auto_match = auto_match.unwrap_or_default();
+    }
+
+    let guild_id = ctx.guild_id().map(|id| id.get() as i64);
+    let warning = check_overlaps(&ctx.data().db, guild_id, &postings, false).await?;
+
+    let draft_id = db::create_drafts(&ctx.data().db, &postings).await?;
+    ctx.send(confirm_msg(&postings, draft_id, warning, locale, Utc::now())?.reply())
+        .await?;
+
+    Ok(())
+}
+
+/// Validate the fields of new postings of `creator_id`, one for each of the given times.
+async fn new_postings(
+    db: &sqlx::PgPool,
+    creator_id: i64,
+    region: Region,
+    platform: Platform,
+    range: Range<u32>,
+    times: &str,
+    team_name: Option<String>,
+) -> Result<Vec<LookingForScrim>, Error> {
+    check_team_name(team_name.as_deref())?;
+
+    let prefs = db::get_preferences_of(db, creator_id).await?;
+    let times = parse_times(times, prefs)?;
+
+    Ok(times
+        .into_iter()
+        .map(|time| LookingForScrim {
+            id: 0,
+            creator_id,
+            team_name: team_name.clone(),
+            region,
+            platform,
+            range: RankRange(range.clone()),
+            time,
+            match_id: None,
+            cancelled: false,
+            checked_in: false,
+            no_show: false,
+            reliability: None,
+            auto_match: false,
+        })
+        .collect())
+}
+
+/// Ask to confirm the drafts of new postings at once, with a warning about overlapping scrims.
+fn confirm_msg(
+    postings: &[LookingForScrim],
+    draft_id: i32,
+    warning: Option<Text>,
+    locale: Locale,
+    now: DateTime<Utc>,
+) -> Result<View, Error> {
+    let components = vec![CreateActionRow::Buttons(vec![
+        CreateButton::new(
+            ComponentAction::Lfs {
+                action: DraftAction::Confirm,
+                draft_id,
+            }
+            .custom_id()?,
+        )
+        .style(ButtonStyle::Success)
+        .label(t!(locale, "button-confirm")),
+        CreateButton::new(
+            ComponentAction::Lfs {
+                action: DraftAction::Cancel,
+                draft_id,
+            }
+            .custom_id()?,
+        )
+        .style(ButtonStyle::Danger)
+        .label(t!(locale, "button-cancel")),
+    ])];
+    let mut content = match postings.len() {
+        1 => t!(locale, "lfs-confirm"),
+        count => t!(locale, "lfs-confirm-many", count = count),
     };
+    if let Some(warning) = warning {
+        content = format!("{}\n{content}", warning.render(locale));
+    }
+    if postings.iter().any(|lfs| lfs.auto_match) {
+        content.push_str(&format!(
+            "\n-# {}",
+            t!(locale, "lfs-auto-match", minutes = AUTO_MATCH_GRACE)
+        ));
+    }
+    let mut view = View::new(content).components(components);
+    for lfs in postings {
+        view = view.embed(scrim_card(lfs, locale, now));
+    }
+    Ok(view)
+}
 
-    let confirm_reply = CreateReply::default()
-        .content(format!(
-            "Looking for a scrim in {:?}/{:?} at {} on <t:{}:F>. Please confirm:",
-            lfs.region,
-            lfs.platform,
-            lfs.range,
-            lfs.time.timestamp()
-        ))
-        .components(vec![CreateActionRow::Buttons(vec![
-            CreateButton::new("confirm")
-                .style(ButtonStyle::Success)
-                .label("Confirm"),
-            CreateButton::new("cancel")
-                .style(ButtonStyle::Danger)
-                .label("Cancel"),
-        ])]);
-    let confirm_handle = ctx.send(confirm_reply).await?;
-    let confirm_msg = confirm_handle.message().await?;
-
-    if let Some(i) = confirm_msg
-        .await_component_interaction(ctx)
-        .timeout(Duration::from_secs(30 * 60))
-        .await
-    {
-        let confirmed = i.data.custom_id == "confirm";
-        if confirmed {
-            i.create_response(
-                ctx,
-                serenity::CreateInteractionResponse::UpdateMessage(
-                    CreateInteractionResponseMessage::new()
-                    .content(format!(
-                        "Looking for a scrim in {:?}/{:?} at {} on <t:{}:F>\nUse `/scrims` to see potential matches.",
-                        lfs.region,
-                        lfs.platform,
-                        lfs.range,
-                        lfs.time.timestamp()
-                    )).components(vec![])
-                ),
+#[derive(Debug, Modal)]
+struct PostingModal {
+    time: String,
+    range: String,
+    team_name: Option<String>,
+}
+
+impl LocalizedModal for PostingModal {
+    fn create(locale: Locale, custom_id: String) -> CreateModal {
+        CreateModal::new(custom_id, t!(locale, "lfs-modal")).components(vec![
+            CreateActionRow::InputText(
+                CreateInputText::new(InputTextStyle::Short, t!(locale, "lfs-modal-time"), "time")
+                    .placeholder(t!(locale, "lfs-modal-time-placeholder"))
+                    .max_length(100),
+            ),
+            CreateActionRow::InputText(
+                CreateInputText::new(
+                    InputTextStyle::Short,
+                    t!(locale, "lfs-modal-range"),
+                    "range",
+                )
+                .placeholder("4k-4.5k")
+                .max_length(20),
+            ),
+            CreateActionRow::InputText(
+                CreateInputText::new(
+                    InputTextStyle::Short,
+                    t!(locale, "lfs-modal-team-name"),
+                    "team_name",
+                )
+                .max_length(50)
+                .required(false),
+            ),
+        ])
+    }
+}
+
+/// Button that opens the form for new postings.
+pub fn new_scrim_button(locale: Locale) -> Result<CreateButton, Error> {
+    Ok(CreateButton::new(
+        ComponentAction::LfsForm {
+            action: FormAction::Open,
+            region: None,
+            platform: None,
+        }
+        .custom_id()?,
+    )
+    .style(ButtonStyle::Primary)
+    .label(t!(locale, "lfs-new")))
+}
+
+/// Selects for the region and platform of a new posting. The remaining fields are asked in a modal
+/// once both are chosen.
+fn form_msg(
+    region: Option<Region>,
+    platform: Option<Platform>,
+    locale: Locale,
+) -> Result<(String, Vec<CreateActionRow>), Error> {
+    let custom_id = |action| {
+        ComponentAction::LfsForm {
+            action,
+            region,
+            platform,
+        }
+        .custom_id()
+    };
+    let options = |choices: Vec<poise::CommandParameterChoice>, selected: Option<String>| {
+        choices
+            .into_iter()
+            .map(|choice| {
+                let selected = selected.as_ref() == Some(&choice.name);
+                CreateSelectMenuOption::new(&choice.name, &choice.name).default_selection(selected)
+            })
+            .collect()
+    };
+
+    let components = vec![
+        CreateActionRow::SelectMenu(
+            CreateSelectMenu::new(
+                custom_id(FormAction::Region)?,
+                CreateSelectMenuKind::String {
+                    options: options(Region::list(), region.map(|r| format!("{r:?}"))),
+                },
             )
-            .await?;
-        } else {
+            .placeholder(t!(locale, "cmd-lfs-region")),
+        ),
+        CreateActionRow::SelectMenu(
+            CreateSelectMenu::new(
+                custom_id(FormAction::Platform)?,
+                CreateSelectMenuKind::String {
+                    options: options(Platform::list(), platform.map(|p| format!("{p:?}"))),
+                },
+            )
+            .placeholder(t!(locale, "cmd-lfs-platform")),
+        ),
+        CreateActionRow::Buttons(vec![CreateButton::new(custom_id(FormAction::Continue)?)
+            .style(ButtonStyle::Primary)
+            .label(t!(locale, "lfs-continue"))
+            .disabled(region.is_none() || platform.is_none())]),
+    ];
+    Ok((t!(locale, "lfs-form"), components))
+}
+
+/// Handle the form for new postings. It ends in the same confirmation as `/lfs`.
+pub async fn handle_form(
+    ctx: &serenity::Context,
+    data: &Data,
+    i: &ComponentInteraction,
+    action: FormAction,
+    mut region: Option<Region>,
+    mut platform: Option<Platform>,
+) -> Result<(), Error> {
+    let locale = i18n::component_locale(data, i).await;
+    let selected = match &i.data.kind {
+        ComponentInteractionDataKind::StringSelect { values } => values.first(),
+        _ => None,
+    };
+    match action {
+        FormAction::Open => {
+            let (content, components) = form_msg(None, None, locale)?;
             i.create_response(
                 ctx,
-                serenity::CreateInteractionResponse::UpdateMessage(
+                CreateInteractionResponse::Message(
                     CreateInteractionResponseMessage::new()
-                        .content("Cancelled")
-                        .components(vec![]),
+                        .content(content)
+                        .components(components)
+                        .ephemeral(true),
                 ),
             )
             .await?;
-            return Ok(()); // Cancelled
+            return Ok(());
         }
-    } else {
-        confirm_handle.delete(ctx.into()).await?;
+        FormAction::Region => region = selected.and_then(|value| value.parse().ok()),
+        FormAction::Platform => platform = selected.and_then(|value| value.parse().ok()),
+        FormAction::Continue => {
+            let (Some(region), Some(platform)) = (region, platform) else {
+                return Ok(());
+            };
+            return submit_form(ctx, data, i, region, platform, locale).await;
+        }
+    }
+
+    let (content, components) = form_msg(region, platform, locale)?;
+    i.create_response(
+        ctx,
+        CreateInteractionResponse::UpdateMessage(
+            CreateInteractionResponseMessage::new()
+                .content(content)
+                .components(components),
+        ),
+    )
+    .await?;
+    Ok(())
+}
+
+/// Ask for the remaining fields of a new posting and reply with the confirmation. The form stays,
+/// so invalid input can be corrected.
+async fn submit_form(
+    ctx: &serenity::Context,
+    data: &Data,
+    i: &ComponentInteraction,
+    region: Region,
+    platform: Platform,
+    locale: Locale,
+) -> Result<(), Error> {
+    let Some(modal) = execute_modal::<PostingModal>(ctx, i, locale, MODAL_TIMEOUT).await? else {
         return Ok(()); // Timeout
+    };
+
+    let creator_id = i.user.id.get() as i64;
+    let drafted = async {
+        let postings = new_postings(
+            &data.db,
+            creator_id,
+            region,
+            platform,
+            parse_rank_range(&modal.range)?,
+            &modal.time,
+            modal.team_name,
+        )
+        .await?;
+        let guild_id = i.guild_id.map(|id| id.get() as i64);
+        let warning = check_overlaps(&data.db, guild_id, &postings, false).await?;
+        let draft_id = db::create_drafts(&data.db, &postings).await?;
+        confirm_msg(&postings, draft_id, warning, locale, Utc::now())
     }
+    .await;
 
-    db::create_scrim(ctx, lfs).await?;
+    let view = drafted.unwrap_or_else(|e| View::new(i18n::error_text(&e, locale)));
+    i.create_followup(ctx, view.followup().ephemeral(true))
+        .await?;
+    Ok(())
+}
+
+/// Handle the confirmation buttons of `/lfs`.
+pub async fn handle_component(
+    ctx: &serenity::Context,
+    data: &Data,
+    i: &ComponentInteraction,
+    action: DraftAction,
+    draft_id: i32,
+) -> Result<(), Error> {
+    let user_id = i.user.id.get() as i64;
+    let locale = i18n::component_locale(data, i).await;
 
+    let view = match action {
+        DraftAction::Confirm => {
+            // Other scrims might have been posted since the drafts were checked for overlaps
+            let guild_id = i.guild_id.map(|id| id.get() as i64);
+            let policy = db::get_overlap_policy(&data.db, guild_id).await?;
+            match db::confirm_drafts(&data.db, draft_id, user_id, policy).await {
+                Ok(postings) => {
+                    let content = match postings.len() {
+                        0 => t!(locale, "lfs-expired"),
+                        1 => t!(locale, "lfs-posted"),
+                        count => t!(locale, "lfs-posted-many", count = count),
+                    };
+                    let now = Utc::now();
+                    let mut view = View::new(content);
+                    for lfs in &postings {
+                        view = view.embed(scrim_card(lfs, locale, now));
+                    }
+                    view
+                }
+                Err(e) => View::new(i18n::error_text(&e, locale)),
+            }
+        }
+        DraftAction::Cancel => {
+            db::delete_drafts(&data.db, draft_id, user_id).await?;
+            View::new(t!(locale, "lfs-cancelled"))
+        }
+    };
+    i.create_response(
+        ctx,
+        serenity::CreateInteractionResponse::UpdateMessage(view.response()),
+    )
+    .await?;
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn time_slots_are_split() {
+        assert_eq!(time_slots("tomorrow 8pm"), ["tomorrow 8pm"]);
+        assert_eq!(
+            time_slots("mon 20, tue 20,, thu 21 "),
+            ["mon 20", "tue 20", "thu 21"]
+        );
+        assert_eq!(
+            time_slots("20 fri-mon, wed 8pm"),
+            ["20 fri", "20 sat", "20 sun", "20 mon", "wed 8pm"]
+        );
+        assert_eq!(time_slots("4k-4.5k 20"), ["4k-4.5k 20"]);
+    }
+
+    #[test]
+    fn time_slots_keep_dates_with_their_time() {
+        assert_eq!(time_slots("friday, 8pm"), ["friday 8pm"]);
+        assert_eq!(
+            time_slots("july 4th, 8pm, sat 21"),
+            ["july 4th 8pm", "sat 21"]
+        );
+        assert_eq!(time_slots("mon-tue, 20"), ["mon 20", "tue 20"]);
+    }
+
+    #[test]
+    fn relative_weekdays_follow_week_start() {
+        // A Sunday
+        let today = NaiveDate::from_ymd_opt(2024, 12, 29).unwrap();
+        let date = |day| NaiveDate::from_ymd_opt(2024, 12, day).unwrap();
+        assert_eq!(week_of(today, Weekday::Mon), date(23));
+        assert_eq!(week_of(today, Weekday::Sun), today);
+        assert_eq!(week_of(today, Weekday::Sat), date(28));
+
+        assert_eq!(
+            relative_weekday("next friday 8pm", today, Weekday::Mon),
+            Some(NaiveDate::from_ymd_opt(2025, 1, 3).unwrap())
+        );
+        assert_eq!(
+            relative_weekday("this fri", today, Weekday::Mon),
+            Some(date(27))
+        );
+        assert_eq!(
+            relative_weekday("this fri", today, Weekday::Sun),
+            Some(NaiveDate::from_ymd_opt(2025, 1, 3).unwrap())
+        );
+        assert_eq!(
+            relative_weekday("last Monday", today, Weekday::Sat),
+            Some(date(23))
+        );
+        assert_eq!(relative_weekday("friday 8pm", today, Weekday::Mon), None);
+        assert_eq!(relative_weekday("next week", today, Weekday::Mon), None);
+    }
+}
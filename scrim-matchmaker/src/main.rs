@@ -1,29 +1,93 @@
+mod auto_match;
+mod board;
+mod browse;
+mod calendar;
 mod cancel;
+mod challenge;
+mod check_in;
+mod components;
 mod db;
+mod history;
+mod i18n;
+mod import;
+mod interactions;
+mod invites;
 mod lfs;
+mod no_show;
+mod overlaps;
+mod preferences;
+mod preset;
+mod proposals;
+mod reminders;
+mod render;
+mod results;
+mod scheduler;
 mod scrims;
 mod timezone;
 
+use std::net::SocketAddr;
+
 use anyhow::Context as _;
 use poise::serenity_prelude::{ClientBuilder, GatewayIntents};
 use shuttle_runtime::SecretStore;
-use shuttle_serenity::ShuttleSerenity;
+use shuttle_serenity::SerenityService;
 use tracing::Level;
 use tracing_subscriber::{
     filter::Targets, fmt::format::FmtSpan, layer::SubscriberExt, util::SubscriberInitExt,
 };
 
+#[derive(Clone)]
 struct Data {
     db: sqlx::PgPool,
+    /// Public URL calendar feeds are served under, if they are enabled.
+    feed_url: Option<String>,
 }
 type Error = Box<dyn std::error::Error + Send + Sync>;
 type Context<'a> = poise::ApplicationContext<'a, Data, Error>;
 
+fn commands() -> Vec<poise::Command<Data, Error>> {
+    let mut commands = vec![
+        lfs::lfs(),
+        timezone::timezone(),
+        preferences::preferences(),
+        preset::preset(),
+        i18n::language(),
+        scrims::scrims(),
+        cancel::cancel(),
+        reminders::reminders(),
+        no_show::noshow(),
+        results::report(),
+        history::history(),
+        challenge::challenge(),
+        board::board(),
+        overlaps::overlaps(),
+        browse::browse(),
+        calendar::calendar(),
+        import::import(),
+    ];
+    i18n::localize_commands(&mut commands);
+    commands
+}
+
+/// The bot together with the HTTP server for calendar feeds.
+struct Service {
+    discord: SerenityService,
+    db: sqlx::PgPool,
+}
+
+#[shuttle_runtime::async_trait]
+impl shuttle_runtime::Service for Service {
+    async fn bind(self, addr: SocketAddr) -> Result<(), shuttle_runtime::Error> {
+        tokio::spawn(calendar::serve(addr, self.db));
+        self.discord.bind(addr).await
+    }
+}
+
 #[shuttle_runtime::main]
 async fn main(
     #[shuttle_runtime::Secrets] secret_store: SecretStore,
     #[shuttle_shared_db::Postgres] pool: sqlx::PgPool,
-) -> ShuttleSerenity {
+) -> Result<Service, shuttle_runtime::Error> {
     let layer = tracing_subscriber::fmt::layer()
         .pretty()
         .with_file(false)
@@ -39,19 +103,22 @@ async fn main(
         .with(layer)
         .init();
 
+    i18n::load().context("Failed to load the catalogues")?;
+
     // Get the discord token set in `Secrets.toml`
     let discord_token = secret_store
         .get("DISCORD_TOKEN")
         .context("'DISCORD_TOKEN' was not found")?;
+    // Calendar feeds are only offered if the URL the bot is reachable at is known
+    let feed_url = secret_store.get("PUBLIC_URL");
+    let feed_db = pool.clone();
 
     let framework = poise::Framework::builder()
         .options(poise::FrameworkOptions {
-            commands: vec![
-                lfs::lfs(),
-                timezone::timezone(),
-                scrims::scrims(),
-                cancel::cancel(),
-            ],
+            commands: commands(),
+            event_handler: |ctx, event, _framework, data| {
+                Box::pin(interactions::handle_event(ctx, event, data))
+            },
             on_error: |error| {
                 Box::pin(async move {
                     match error {
@@ -59,9 +126,12 @@ async fn main(
                             tracing::error!("Error in user data setup: {error}");
                         }
                         poise::FrameworkError::Command { ctx, error, .. } => {
-                            let error = error.to_string();
                             tracing::error!("An error occured in a command: {}", error);
-                            if let Err(e) = ctx.say(error).await {
+                            let locale = match ctx {
+                                poise::Context::Application(ctx) => i18n::locale(ctx).await,
+                                poise::Context::Prefix(_) => i18n::Locale::default(),
+                            };
+                            if let Err(e) = ctx.say(i18n::error_text(&error, locale)).await {
                                 tracing::error!("Error while handling error: {e}");
                             }
                         }
@@ -82,7 +152,8 @@ async fn main(
                 tracing::info!("Running migrations");
                 sqlx::migrate!().run(&pool).await?;
                 tracing::info!("Migrations done");
-                Ok(Data { db: pool })
+                scheduler::start(ctx.clone(), pool.clone());
+                Ok(Data { db: pool, feed_url })
             })
         })
         .build();
@@ -92,5 +163,8 @@ async fn main(
         .await
         .map_err(shuttle_runtime::CustomError::new)?;
 
-    Ok(client.into())
+    Ok(Service {
+        discord: client.into(),
+        db: feed_db,
+    })
 }
@@ -0,0 +1,56 @@
+use poise::serenity_prelude::{
+    self as serenity, ButtonStyle, ComponentInteraction, CreateButton, CreateInteractionResponse,
+    CreateInteractionResponseMessage,
+};
+
+use crate::{
+    components::ComponentAction,
+    db,
+    i18n::{self, t, Locale},
+    scrims::complete_recent_match,
+    Context, Data, Error,
+};
+
+pub fn no_show_button(scrim_id: i32, locale: Locale) -> Result<CreateButton, Error> {
+    Ok(
+        CreateButton::new(ComponentAction::NoShow { scrim_id }.custom_id()?)
+            .style(ButtonStyle::Danger)
+            .label(t!(locale, "no-show-button")),
+    )
+}
+
+#[poise::command(slash_command, ephemeral)]
+#[tracing::instrument(err, skip(ctx), fields(user = ctx.author().id.get()))]
+pub async fn noshow(
+    ctx: Context<'_>,
+    #[autocomplete = "complete_recent_match"] scrim: i32,
+) -> Result<(), Error> {
+    db::report_no_show(&ctx.data().db, ctx.author().id.get() as i64, scrim).await?;
+    ctx.reply(t!(i18n::locale(ctx).await, "no-show-reported"))
+        .await?;
+    Ok(())
+}
+
+/// Handle the no-show buttons sent in direct messages.
+pub async fn handle_component(
+    ctx: &serenity::Context,
+    data: &Data,
+    i: &ComponentInteraction,
+    scrim_id: i32,
+) -> Result<(), Error> {
+    let locale = i18n::component_locale(data, i).await;
+    let content = match db::report_no_show(&data.db, i.user.id.get() as i64, scrim_id).await {
+        Ok(()) => t!(locale, "no-show-reported"),
+        Err(e) => i18n::error_text(&e, locale),
+    };
+    i.create_response(
+        ctx,
+        CreateInteractionResponse::Message(
+            CreateInteractionResponseMessage::new()
+                .content(content)
+                .ephemeral(true),
+        ),
+    )
+    .await?;
+    Ok(())
+}
@@ -0,0 +1,140 @@
+use std::collections::BTreeSet;
+
+use chrono::{DateTime, Utc};
+use poise::ChoiceParameter;
+
+use crate::{
+    db,
+    i18n::{self, Text},
+    lfs::LookingForScrim,
+    results::SCRIM_DURATION,
+    Context, Error,
+};
+
+/// How a guild handles scrims of the same creator and team at overlapping times.
+#[derive(ChoiceParameter, Debug, Clone, Copy, PartialEq, Eq, Default, enum_utils::FromStr)]
+pub enum OverlapPolicy {
+    #[default]
+    #[name = "Warn about overlaps"]
+    #[name_localized("de", "Vor Überschneidungen warnen")]
+    #[name_localized("fr", "Avertir des chevauchements")]
+    Warn,
+    #[name = "Refuse overlaps"]
+    #[name_localized("de", "Überschneidungen ablehnen")]
+    #[name_localized("fr", "Refuser les chevauchements")]
+    Block,
+}
+
+/// Whether scrims starting at `a` and `b` overlap.
+fn overlap(a: DateTime<Utc>, b: DateTime<Utc>) -> bool {
+    (a - b).num_minutes().abs() < SCRIM_DURATION.into()
+}
+
+/// Start times of the scrims that overlap with `postings` of one creator and team: other scrims
+/// of the team, and the postings that overlap with each other. With `matched_only`, only other
+/// scrims that found an opponent count.
+pub async fn overlapping_times(
+    db: &mut sqlx::PgConnection,
+    postings: &[LookingForScrim],
+    matched_only: bool,
+) -> Result<Vec<DateTime<Utc>>, sqlx::Error> {
+    let mut times = BTreeSet::new();
+    for (index, lfs) in postings.iter().enumerate() {
+        for scrim in db::get_overlapping_scrims(&mut *db, lfs, SCRIM_DURATION, matched_only).await?
+        {
+            times.insert(scrim.time);
+        }
+        for other in &postings[index + 1..] {
+            if overlap(lfs.time, other.time) {
+                times.insert(lfs.time);
+                times.insert(other.time);
+            }
+        }
+    }
+    Ok(times.into_iter().collect())
+}
+
+fn format_times(times: &[DateTime<Utc>]) -> String {
+    times
+        .iter()
+        .map(|time| format!("<t:{}:F>", time.timestamp()))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// Warning listing the overlapping `times`.
+pub fn overlap_warning(times: &[DateTime<Utc>]) -> Text {
+    Text::new("overlap-warning").arg("times", format_times(times))
+}
+
+/// Error listing the overlapping `times` that blocked a scrim.
+pub fn overlap_blocked(times: &[DateTime<Utc>]) -> Text {
+    Text::new("overlap-blocked").arg("times", format_times(times))
+}
+
+/// Check `postings` of one creator and team for overlaps. Depending on the policy of the guild
+/// they fail with the conflicting scrims or return a warning listing them. With `matched_only`,
+/// only other scrims that found an opponent count.
+pub async fn check_overlaps(
+    db: &sqlx::PgPool,
+    guild_id: Option<i64>,
+    postings: &[LookingForScrim],
+    matched_only: bool,
+) -> Result<Option<Text>, Error> {
+    let times = overlapping_times(&mut *db.acquire().await?, postings, matched_only).await?;
+    if times.is_empty() {
+        return Ok(None);
+    }
+    match db::get_overlap_policy(db, guild_id).await? {
+        OverlapPolicy::Warn => Ok(Some(overlap_warning(&times))),
+        OverlapPolicy::Block => Err(overlap_blocked(&times).into()),
+    }
+}
+
+#[poise::command(
+    slash_command,
+    guild_only,
+    default_member_permissions = "MANAGE_GUILD",
+    ephemeral
+)]
+#[tracing::instrument(err, skip(ctx), fields(user = ctx.author().id.get()))]
+pub async fn overlaps(ctx: Context<'_>, policy: Option<OverlapPolicy>) -> Result<(), Error> {
+    let guild_id = ctx.guild_id().ok_or(Text::new("overlaps-guild-only"))?;
+    let guild_id = guild_id.get() as i64;
+    if let Some(policy) = policy {
+        db::set_overlap_policy(&ctx.data().db, guild_id, policy).await?;
+    }
+
+    let locale = i18n::locale(ctx).await;
+    let current = db::get_overlap_policy(&ctx.data().db, Some(guild_id)).await?;
+    let reply = if policy.is_some() {
+        Text::new("overlaps-set")
+    } else {
+        Text::new("overlaps-current")
+    };
+    let reply = reply
+        .arg(
+            "policy",
+            current
+                .localized_name(locale.code())
+                .unwrap_or(current.name()),
+        )
+        .render(locale);
+    ctx.reply(reply).await?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::TimeZone;
+
+    use super::*;
+
+    #[test]
+    fn overlaps_are_found() {
+        let time = |hour, minute| Utc.with_ymd_and_hms(2024, 7, 1, hour, minute, 0).unwrap();
+        assert!(overlap(time(20, 0), time(20, 30)));
+        assert!(overlap(time(21, 59), time(20, 0)));
+        assert!(!overlap(time(20, 0), time(22, 0)));
+    }
+}
@@ -0,0 +1,227 @@
+use chrono::{DateTime, Datelike, Utc, Weekday};
+use poise::ChoiceParameter;
+
+use crate::{
+    db,
+    i18n::{self, t, Locale, Text},
+    Context, Error,
+};
+
+#[derive(ChoiceParameter, Debug, Clone, Copy, PartialEq, Eq, Default, enum_utils::FromStr)]
+pub enum Clock {
+    #[default]
+    #[name = "24-hour (20:00)"]
+    #[name_localized("de", "24 Stunden (20:00)")]
+    #[name_localized("fr", "24 heures (20:00)")]
+    H24,
+    #[name = "12-hour (8:00 PM)"]
+    #[name_localized("de", "12 Stunden (8:00 PM)")]
+    #[name_localized("fr", "12 heures (8:00 PM)")]
+    H12,
+}
+
+#[derive(ChoiceParameter, Debug, Clone, Copy, PartialEq, Eq, Default, enum_utils::FromStr)]
+pub enum DateOrder {
+    #[default]
+    #[name = "Day first (31/12/2024)"]
+    #[name_localized("de", "Tag zuerst (31/12/2024)")]
+    #[name_localized("fr", "Jour d'abord (31/12/2024)")]
+    DayMonth,
+    #[name = "Month first (12/31/2024)"]
+    #[name_localized("de", "Monat zuerst (12/31/2024)")]
+    #[name_localized("fr", "Mois d'abord (12/31/2024)")]
+    MonthDay,
+    #[name = "Year first (2024-12-31)"]
+    #[name_localized("de", "Jahr zuerst (2024-12-31)")]
+    #[name_localized("fr", "Année d'abord (2024-12-31)")]
+    YearMonthDay,
+}
+
+#[derive(ChoiceParameter, Debug, Clone, Copy, PartialEq, Eq, Default, enum_utils::FromStr)]
+pub enum WeekStart {
+    #[default]
+    #[name_localized("de", "Montag")]
+    #[name_localized("fr", "Lundi")]
+    Monday,
+    #[name_localized("de", "Sonntag")]
+    #[name_localized("fr", "Dimanche")]
+    Sunday,
+    #[name_localized("de", "Samstag")]
+    #[name_localized("fr", "Samedi")]
+    Saturday,
+}
+
+impl WeekStart {
+    pub const fn weekday(self) -> Weekday {
+        match self {
+            Self::Monday => Weekday::Mon,
+            Self::Sunday => Weekday::Sun,
+            Self::Saturday => Weekday::Sat,
+        }
+    }
+}
+
+/// How a user wants times written out in text.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Preferences {
+    pub timezone: chrono_tz::Tz,
+    pub clock: Clock,
+    pub date_order: DateOrder,
+    pub week_start: WeekStart,
+}
+
+impl Default for Preferences {
+    fn default() -> Self {
+        Self {
+            timezone: chrono_tz::UTC,
+            clock: Clock::default(),
+            date_order: DateOrder::default(),
+            week_start: WeekStart::default(),
+        }
+    }
+}
+
+impl Preferences {
+    fn time_format(self) -> &'static str {
+        match self.clock {
+            Clock::H24 => "%H:%M",
+            Clock::H12 => "%-I:%M %p",
+        }
+    }
+
+    /// A time with its date in digits, like `31/12/2024 20:00`.
+    pub fn short(self, time: DateTime<Utc>) -> String {
+        let date = match self.date_order {
+            DateOrder::DayMonth => "%d/%m/%Y",
+            DateOrder::MonthDay => "%m/%d/%Y",
+            DateOrder::YearMonthDay => "%Y-%m-%d",
+        };
+        time.with_timezone(&self.timezone)
+            .format(&format!("{date} {}", self.time_format()))
+            .to_string()
+    }
+
+    /// A time with its weekday and timezone in the language of `locale`, like
+    /// `Tuesday, 31 December, 20:00 CET`.
+    pub fn long(self, time: DateTime<Utc>, locale: Locale) -> String {
+        let time = time.with_timezone(&self.timezone);
+        let weekdays = t!(locale, "weekdays");
+        let weekday = weekdays
+            .split_whitespace()
+            .nth(time.weekday().num_days_from_monday() as usize)
+            .unwrap_or_default();
+        let months = t!(locale, "months");
+        let month = months
+            .split_whitespace()
+            .nth(time.month0() as usize)
+            .unwrap_or_default();
+        let date = match self.date_order {
+            DateOrder::DayMonth => t!(
+                locale,
+                "date-day-month",
+                weekday = weekday,
+                day = time.day(),
+                month = month
+            ),
+            DateOrder::MonthDay => t!(
+                locale,
+                "date-month-day",
+                weekday = weekday,
+                day = time.day(),
+                month = month
+            ),
+            DateOrder::YearMonthDay => t!(
+                locale,
+                "date-year-month-day",
+                weekday = weekday,
+                date = time.format("%Y-%m-%d")
+            ),
+        };
+        format!(
+            "{date}, {}",
+            time.format(&format!("{} %Z", self.time_format()))
+        )
+    }
+}
+
+#[poise::command(slash_command, ephemeral)]
+#[tracing::instrument(err, skip(ctx), fields(user = ctx.author().id.get()))]
+pub async fn preferences(
+    ctx: Context<'_>,
+    clock: Option<Clock>,
+    date_order: Option<DateOrder>,
+    week_start: Option<WeekStart>,
+) -> Result<(), Error> {
+    let locale = i18n::locale(ctx).await;
+    let changed = clock.is_some() || date_order.is_some() || week_start.is_some();
+    if changed {
+        db::set_preferences(ctx, clock, date_order, week_start).await?;
+    }
+
+    let prefs = db::get_preferences(ctx).await?;
+    let now = Utc::now();
+    let reply = if changed {
+        Text::new("preferences-set")
+    } else {
+        Text::new("preferences-current")
+    };
+    let week_start = prefs.week_start;
+    let reply = reply
+        .arg("short", prefs.short(now))
+        .arg("long", prefs.long(now, locale))
+        .arg(
+            "week_start",
+            week_start
+                .localized_name(locale.code())
+                .unwrap_or(week_start.name()),
+        )
+        .render(locale);
+    ctx.reply(reply).await?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::TimeZone;
+
+    use super::*;
+
+    #[test]
+    fn formats_follow_preferences() {
+        let time = Utc.with_ymd_and_hms(2024, 12, 31, 19, 5, 0).unwrap();
+        let mut prefs = Preferences {
+            timezone: chrono_tz::Europe::Berlin,
+            ..Default::default()
+        };
+        assert_eq!(prefs.short(time), "31/12/2024 20:05");
+        assert_eq!(
+            prefs.long(time, Locale::En),
+            "Tuesday, 31 December, 20:05 CET"
+        );
+
+        prefs.clock = Clock::H12;
+        prefs.date_order = DateOrder::MonthDay;
+        assert_eq!(prefs.short(time), "12/31/2024 8:05 PM");
+        assert_eq!(
+            prefs.long(time, Locale::En),
+            "Tuesday, December 31, 8:05 PM CET"
+        );
+
+        prefs.date_order = DateOrder::YearMonthDay;
+        assert_eq!(prefs.short(time), "2024-12-31 8:05 PM");
+        assert_eq!(
+            prefs.long(time, Locale::En),
+            "Tuesday, 2024-12-31, 8:05 PM CET"
+        );
+
+        prefs.date_order = DateOrder::DayMonth;
+        assert_eq!(
+            prefs.long(time, Locale::De),
+            "Dienstag, 31. Dezember, 8:05 PM CET"
+        );
+        assert_eq!(
+            prefs.long(time, Locale::Fr),
+            "mardi 31 décembre, 8:05 PM CET"
+        );
+    }
+}
@@ -0,0 +1,139 @@
+use crate::{
+    db,
+    i18n::{self, t, Locale, Text},
+    lfs::{check_team_name, parse_rank_range, Platform, RankRange, Region},
+    Context, Error,
+};
+
+/// Most presets one user can save, as autocompletion shows at most 25 choices.
+const MAX_PRESETS: usize = 25;
+
+/// Defaults for `/lfs` saved under a name. Arguments given to `/lfs` take precedence.
+#[derive(Debug, Clone)]
+pub struct Preset {
+    pub name: String,
+    pub region: Option<Region>,
+    pub platform: Option<Platform>,
+    pub range: Option<RankRange>,
+    pub team_name: Option<String>,
+}
+
+impl Preset {
+    /// Line describing the preset in `/preset list`.
+    fn describe(&self, locale: Locale) -> String {
+        let mut fields = Vec::new();
+        if let Some(region) = self.region {
+            fields.push(format!("{region:?}"));
+        }
+        if let Some(platform) = self.platform {
+            fields.push(format!("{platform:?}"));
+        }
+        if let Some(range) = &self.range {
+            fields.push(range.to_string());
+        }
+        if let Some(team_name) = &self.team_name {
+            fields.push(t!(locale, "preset-team", team = team_name));
+        }
+        format!("**{}**: {}", self.name, fields.join(", "))
+    }
+}
+
+pub async fn complete_preset(ctx: Context<'_>, partial: &str) -> Vec<String> {
+    db::get_preset_names(ctx, partial.trim())
+        .await
+        .unwrap_or_default()
+}
+
+/// Look up a preset of `creator_id` by its name.
+pub async fn find_preset(db: &sqlx::PgPool, creator_id: i64, name: &str) -> Result<Preset, Error> {
+    db::get_preset(db, creator_id, name)
+        .await?
+        .ok_or_else(|| Text::new("preset-unknown").arg("name", name).into())
+}
+
+#[poise::command(
+    slash_command,
+    subcommands("save", "list", "delete"),
+    subcommand_required
+)]
+pub async fn preset(_ctx: Context<'_>) -> Result<(), Error> {
+    Ok(())
+}
+
+#[poise::command(slash_command, ephemeral)]
+#[tracing::instrument(err, skip(ctx), fields(user = ctx.author().id.get()))]
+pub async fn save(
+    ctx: Context<'_>,
+    name: String,
+    region: Option<Region>,
+    platform: Option<Platform>,
+    range: Option<String>,
+    team_name: Option<String>,
+) -> Result<(), Error> {
+    let locale = i18n::locale(ctx).await;
+    let name = name.trim().to_string();
+    if name.is_empty() || name.chars().count() > 50 {
+        return Err(Text::new("preset-name-invalid").into());
+    }
+    check_team_name(team_name.as_deref())?;
+    let preset = Preset {
+        name,
+        region,
+        platform,
+        range: range
+            .map(|range| parse_rank_range(&range).map(RankRange))
+            .transpose()?,
+        team_name,
+    };
+    if preset.region.is_none()
+        && preset.platform.is_none()
+        && preset.range.is_none()
+        && preset.team_name.is_none()
+    {
+        return Err(Text::new("preset-empty").into());
+    }
+
+    let creator_id = ctx.author().id.get() as i64;
+    let presets = db::get_presets(&ctx.data().db, creator_id).await?;
+    let replaces = presets
+        .iter()
+        .any(|existing| existing.name.to_lowercase() == preset.name.to_lowercase());
+    if !replaces && presets.len() >= MAX_PRESETS {
+        return Err(Text::new("preset-too-many").arg("max", MAX_PRESETS).into());
+    }
+    db::save_preset(&ctx.data().db, creator_id, &preset).await?;
+    ctx.reply(t!(locale, "preset-saved", preset = preset.describe(locale)))
+        .await?;
+    Ok(())
+}
+
+#[poise::command(slash_command, ephemeral)]
+#[tracing::instrument(err, skip(ctx), fields(user = ctx.author().id.get()))]
+pub async fn list(ctx: Context<'_>) -> Result<(), Error> {
+    let locale = i18n::locale(ctx).await;
+    let presets = db::get_presets(&ctx.data().db, ctx.author().id.get() as i64).await?;
+    if presets.is_empty() {
+        ctx.reply(t!(locale, "preset-none")).await?;
+        return Ok(());
+    }
+    let mut content = t!(locale, "preset-list");
+    for preset in &presets {
+        content.push_str(&format!("\n- {}", preset.describe(locale)));
+    }
+    ctx.reply(content).await?;
+    Ok(())
+}
+
+#[poise::command(slash_command, ephemeral)]
+#[tracing::instrument(err, skip(ctx), fields(user = ctx.author().id.get()))]
+pub async fn delete(
+    ctx: Context<'_>,
+    #[autocomplete = "complete_preset"] name: String,
+) -> Result<(), Error> {
+    let locale = i18n::locale(ctx).await;
+    if !db::delete_preset(&ctx.data().db, ctx.author().id.get() as i64, &name).await? {
+        return Err(Text::new("preset-unknown").arg("name", name).into());
+    }
+    ctx.reply(t!(locale, "preset-deleted", name = name)).await?;
+    Ok(())
+}
@@ -0,0 +1,305 @@
+use chrono::{DateTime, Utc};
+use poise::{
+    serenity_prelude::{
+        self as serenity, ButtonStyle, ComponentInteraction, CreateActionRow, CreateButton,
+        CreateInputText, CreateInteractionResponse, CreateInteractionResponseFollowup,
+        CreateInteractionResponseMessage, CreateMessage, CreateModal, InputTextStyle,
+    },
+    Modal,
+};
+
+use crate::{
+    components::{Answer, ComponentAction},
+    db,
+    i18n::{self, t, Locale, Text},
+    interactions::{execute_modal, LocalizedModal, MODAL_TIMEOUT},
+    invites::{self, InviteStatus},
+    lfs::{parse_time, LookingForScrim},
+    overlaps::overlap_warning,
+    reminders::send_dm,
+    render::write_scrim_meta,
+    Data, Error,
+};
+
+/// Proposal to move a posting to another time.
+#[derive(Debug, Clone)]
+pub struct Proposal {
+    pub id: i32,
+    pub scrim_id: i32,
+    /// Posting of the other side that is moved and matched together with the proposed one, if any.
+    pub other_id: Option<i32>,
+    /// Invite to the posting this proposal answers, if any.
+    pub invite_id: Option<i32>,
+    pub proposer_id: i64,
+    pub target_id: i64,
+    pub time: DateTime<Utc>,
+    pub status: InviteStatus,
+}
+
+#[derive(Debug, Modal)]
+struct TimeModal {
+    time: String,
+}
+
+impl LocalizedModal for TimeModal {
+    fn create(locale: Locale, custom_id: String) -> CreateModal {
+        CreateModal::new(custom_id, t!(locale, "proposal-modal")).components(vec![
+            CreateActionRow::InputText(
+                CreateInputText::new(InputTextStyle::Short, t!(locale, "lfs-modal-time"), "time")
+                    .placeholder(t!(locale, "lfs-modal-time-placeholder"))
+                    .max_length(100),
+            ),
+        ])
+    }
+}
+
+/// Ask the user for a new time for `scrim` and send it to `target_id` for approval. If `other` is
+/// given, it is the posting of the target which is moved together with `scrim`.
+pub async fn propose_time(
+    ctx: &serenity::Context,
+    data: &Data,
+    i: &ComponentInteraction,
+    scrim: &LookingForScrim,
+    other: Option<&LookingForScrim>,
+    invite_id: Option<i32>,
+    target_id: i64,
+) -> Result<(), Error> {
+    let locale = i18n::component_locale(data, i).await;
+    let Some(modal) = execute_modal::<TimeModal>(ctx, i, locale, MODAL_TIMEOUT).await? else {
+        return Ok(()); // Timeout
+    };
+
+    let proposer_id = i.user.id.get() as i64;
+    let proposed = async {
+        let prefs = db::get_preferences_of(&data.db, proposer_id).await?;
+        let time = parse_time(&modal.time, prefs)?;
+        if time <= Utc::now() {
+            return Err::<_, Error>(Text::new("proposal-past").into());
+        }
+        let mut proposal = Proposal {
+            id: 0,
+            scrim_id: scrim.id,
+            other_id: other.map(|other| other.id),
+            invite_id,
+            proposer_id,
+            target_id,
+            time,
+            status: InviteStatus::Pending,
+        };
+        proposal.id = db::create_proposal(&data.db, &proposal).await?;
+        send_proposal(ctx, data, other.unwrap_or(scrim), &proposal).await?;
+        Ok(proposal)
+    }
+    .await;
+
+    let content = match proposed {
+        Ok(proposal) => t!(
+            locale,
+            "proposal-sent",
+            time = proposal.time.timestamp(),
+            user = target_id
+        ),
+        Err(e) => i18n::error_text(&e, locale),
+    };
+    i.create_followup(
+        ctx,
+        CreateInteractionResponseFollowup::new()
+            .content(content)
+            .ephemeral(true),
+    )
+    .await?;
+    Ok(())
+}
+
+/// Send a proposal to its target. `scrim` is the posting of the target that is affected.
+async fn send_proposal(
+    ctx: &serenity::Context,
+    data: &Data,
+    scrim: &LookingForScrim,
+    proposal: &Proposal,
+) -> Result<(), Error> {
+    let locale = i18n::locale_of(&data.db, proposal.target_id, None).await;
+    let mut meta = String::new();
+    write_scrim_meta(scrim, None, locale, Utc::now(), &mut meta);
+    let content = t!(
+        locale,
+        "proposal",
+        user = proposal.proposer_id,
+        time = proposal.time.timestamp(),
+        old_time = scrim.time.timestamp(),
+        scrim = meta
+    );
+
+    let msg = CreateMessage::new()
+        .content(content)
+        .components(vec![CreateActionRow::Buttons(vec![
+            CreateButton::new(
+                ComponentAction::Proposal {
+                    answer: Answer::Accept,
+                    proposal_id: proposal.id,
+                }
+                .custom_id()?,
+            )
+            .style(ButtonStyle::Success)
+            .label(t!(locale, "button-accept")),
+            CreateButton::new(
+                ComponentAction::Proposal {
+                    answer: Answer::Decline,
+                    proposal_id: proposal.id,
+                }
+                .custom_id()?,
+            )
+            .style(ButtonStyle::Danger)
+            .label(t!(locale, "button-decline")),
+        ])]);
+    send_dm(ctx, proposal.target_id, msg)
+        .await
+        .map_err(|_| Text::new("proposal-failed").arg("user", proposal.target_id))?;
+    Ok(())
+}
+
+/// Handle the buttons of proposals sent in direct messages.
+pub async fn handle_component(
+    ctx: &serenity::Context,
+    data: &Data,
+    i: &ComponentInteraction,
+    answer: Answer,
+    proposal_id: i32,
+) -> Result<(), Error> {
+    let proposal = db::get_proposal(&data.db, proposal_id).await?;
+    let locale = i18n::component_locale(data, i).await;
+    if proposal.target_id != i.user.id.get() as i64 {
+        return respond_ephemeral(ctx, i, t!(locale, "scrim-not-yours")).await;
+    }
+
+    let (content, answered) = match answer {
+        _ if proposal.status != InviteStatus::Pending => (t!(locale, "proposal-answered"), None),
+        Answer::Accept => match accept(data, i, &proposal).await {
+            Ok(times) => {
+                let mut content = t!(
+                    locale,
+                    "proposal-accepted",
+                    time = proposal.time.timestamp()
+                );
+                if !times.is_empty() {
+                    let warning = overlap_warning(&times).render(locale);
+                    content = format!("{warning}\n{content}");
+                }
+                (content, Some(InviteStatus::Accepted))
+            }
+            Err(e) => (i18n::error_text(&e, locale), None),
+        },
+        Answer::Decline => {
+            if db::answer_proposal(&data.db, proposal.id, InviteStatus::Declined).await? {
+                (
+                    t!(locale, "proposal-declined"),
+                    Some(InviteStatus::Declined),
+                )
+            } else {
+                (t!(locale, "proposal-answered"), None)
+            }
+        }
+    };
+
+    // The buttons stay if nothing was answered, e.g. because accepting failed for now
+    let Some(answer) = answered else {
+        return respond_ephemeral(ctx, i, content).await;
+    };
+    notify_proposer(ctx, data, &proposal, answer).await;
+
+    i.create_response(
+        ctx,
+        CreateInteractionResponse::UpdateMessage(
+            CreateInteractionResponseMessage::new()
+                .content(content)
+                .components(vec![]),
+        ),
+    )
+    .await?;
+    Ok(())
+}
+
+/// Tell the proposer of `proposal` how it was answered.
+async fn notify_proposer(
+    ctx: &serenity::Context,
+    data: &Data,
+    proposal: &Proposal,
+    answer: InviteStatus,
+) {
+    let locale = i18n::locale_of(&data.db, proposal.proposer_id, None).await;
+    let content = if answer == InviteStatus::Accepted {
+        t!(
+            locale,
+            "proposal-accepted-notice",
+            user = proposal.target_id,
+            time = proposal.time.timestamp()
+        )
+    } else {
+        t!(
+            locale,
+            "proposal-declined-notice",
+            user = proposal.target_id,
+            time = proposal.time.timestamp()
+        )
+    };
+    if let Err(e) = send_dm(
+        ctx,
+        proposal.proposer_id,
+        CreateMessage::new().content(content),
+    )
+    .await
+    {
+        tracing::warn!(
+            "Could not notify {} about proposal: {e}",
+            proposal.proposer_id
+        );
+    }
+}
+
+async fn respond_ephemeral(
+    ctx: &serenity::Context,
+    i: &ComponentInteraction,
+    content: String,
+) -> Result<(), Error> {
+    i.create_response(
+        ctx,
+        CreateInteractionResponse::Message(
+            CreateInteractionResponseMessage::new()
+                .content(content)
+                .ephemeral(true),
+        ),
+    )
+    .await?;
+    Ok(())
+}
+
+/// Accept `proposal`. Returns the times of other matched scrims of the team of the user it
+/// overlaps with.
+async fn accept(
+    data: &Data,
+    i: &ComponentInteraction,
+    proposal: &Proposal,
+) -> Result<Vec<DateTime<Utc>>, Error> {
+    // Proposals answering an invite accept the invite at the new time
+    let invited = match proposal.invite_id {
+        Some(invite_id) => {
+            let invite = db::get_invite(&data.db, invite_id).await?;
+            if invite.status != InviteStatus::Pending {
+                return Err(Text::new("proposal-invite-answered").into());
+            }
+            if !db::has_timezone(&data.db, invite.target_id).await? {
+                return Err(Text::new("timezone-missing").into());
+            }
+            let scrim = db::get_scrim_in(&data.db, proposal.scrim_id).await?;
+            let posting = LookingForScrim {
+                time: proposal.time,
+                ..invites::invited_posting(&invite, &scrim)
+            };
+            Some((invite, posting))
+        }
+        None => None,
+    };
+    let guild_id = i.guild_id.map(|id| id.get() as i64);
+    let policy = db::get_overlap_policy(&data.db, guild_id).await?;
+    db::accept_proposal(&data.db, proposal, invited, policy).await
+}
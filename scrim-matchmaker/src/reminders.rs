@@ -0,0 +1,145 @@
+use chrono::Utc;
+use poise::serenity_prelude::{self as serenity, CreateMessage, UserId};
+
+use crate::{
+    db,
+    i18n::{self, t, Text},
+    render::write_scrim_with_name,
+    Context, Error,
+};
+
+fn parse_offset(s: &str) -> Result<i32, Text> {
+    let err = Text::new("reminders-invalid").arg("input", s);
+    let s = s.trim();
+    let (number, unit) = s.split_at(s.find(|c: char| !c.is_ascii_digit()).unwrap_or(s.len()));
+    let Ok(number) = number.parse::<i32>() else {
+        return Err(err);
+    };
+    let minutes = match unit.trim() {
+        "" | "m" | "min" => Some(number),
+        "h" => number.checked_mul(60),
+        "d" => number.checked_mul(24 * 60),
+        _ => return Err(err),
+    };
+    match minutes {
+        Some(minutes) if minutes > 0 && minutes <= 7 * 24 * 60 => Ok(minutes),
+        _ => Err(Text::new("reminders-out-of-range").arg("input", s)),
+    }
+}
+
+fn format_offset(minutes: i32) -> String {
+    if minutes % (24 * 60) == 0 {
+        format!("{}d", minutes / (24 * 60))
+    } else if minutes % 60 == 0 {
+        format!("{}h", minutes / 60)
+    } else {
+        format!("{minutes}m")
+    }
+}
+
+fn format_offsets(offsets: &[i32], locale: i18n::Locale) -> String {
+    if offsets.is_empty() {
+        t!(locale, "reminders-off")
+    } else {
+        offsets
+            .iter()
+            .map(|&o| format!("`{}`", format_offset(o)))
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
+}
+
+#[poise::command(slash_command, ephemeral)]
+#[tracing::instrument(err, skip(ctx), fields(user = ctx.author().id.get()))]
+pub async fn reminders(ctx: Context<'_>, offsets: Option<String>) -> Result<(), Error> {
+    let locale = i18n::locale(ctx).await;
+    if let Some(offsets) = offsets {
+        let mut offsets = if offsets.trim().eq_ignore_ascii_case("off") {
+            Vec::new()
+        } else {
+            offsets
+                .split(',')
+                .map(parse_offset)
+                .collect::<Result<Vec<_>, _>>()?
+        };
+        offsets.sort_unstable();
+        offsets.dedup();
+        db::set_reminder_offsets(ctx, &offsets).await?;
+
+        ctx.reply(t!(
+            locale,
+            "reminders-set",
+            offsets = format_offsets(&offsets, locale)
+        ))
+        .await?;
+    } else {
+        let offsets = db::get_reminder_offsets(ctx).await?;
+        ctx.reply(t!(
+            locale,
+            "reminders-current",
+            offsets = format_offsets(&offsets, locale)
+        ))
+        .await?;
+    }
+
+    Ok(())
+}
+
+/// Send a direct message to a user.
+pub async fn send_dm(
+    ctx: &serenity::Context,
+    user_id: i64,
+    msg: CreateMessage,
+) -> Result<(), serenity::Error> {
+    UserId::new(user_id as u64)
+        .create_dm_channel(ctx)
+        .await?
+        .send_message(ctx, msg)
+        .await?;
+    Ok(())
+}
+
+/// Send all reminders that are due. Only the closest offset is sent if several are due at once,
+/// e.g. when a scrim is matched shortly before it starts.
+#[tracing::instrument(err, skip(ctx, db))]
+pub async fn send_due(ctx: &serenity::Context, db: &sqlx::PgPool) -> Result<(), Error> {
+    let due = db::get_due_reminders(db).await?;
+
+    let mut last_scrim = None;
+    for reminder in due {
+        let first_for_scrim = last_scrim != Some(reminder.scrim_id);
+        last_scrim = Some(reminder.scrim_id);
+        if !db::mark_reminder_sent(db, reminder.scrim_id, reminder.offset_minutes).await?
+            || !first_for_scrim
+        {
+            continue;
+        }
+
+        let scrim = db::get_scrim_in(db, reminder.scrim_id).await?;
+        let other = db::get_scrim_in(db, reminder.other_id).await?;
+
+        let locale = i18n::locale_of(db, scrim.creator_id, None).await;
+        let mut opponent = String::new();
+        write_scrim_with_name(
+            &other,
+            Some(&scrim),
+            true,
+            locale,
+            Utc::now(),
+            &mut opponent,
+        );
+        let content = t!(
+            locale,
+            "reminder",
+            time = scrim.time.timestamp(),
+            opponent = opponent
+        );
+
+        if let Err(e) = send_dm(ctx, scrim.creator_id, CreateMessage::new().content(content)).await
+        {
+            tracing::warn!("Could not send reminder to {}: {e}", scrim.creator_id);
+        }
+    }
+
+    Ok(())
+}
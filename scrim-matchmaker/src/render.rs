@@ -0,0 +1,812 @@
+use chrono::{DateTime, Utc};
+use poise::{
+    serenity_prelude::{
+        ButtonStyle, Colour, CreateActionRow, CreateButton, CreateEmbed,
+        CreateInteractionResponseFollowup, CreateInteractionResponseMessage, CreateMessage,
+        CreateSelectMenu, CreateSelectMenuKind, CreateSelectMenuOption,
+    },
+    CreateReply,
+};
+
+use crate::{
+    check_in,
+    components::{ComponentAction, ScrimsAction},
+    db::HeadToHead,
+    i18n::{t, Locale},
+    lfs::{new_scrim_button, LookingForScrim},
+    preferences::Preferences,
+    Error,
+};
+
+/// Rendered message which can be sent as a reply, response, followup or direct message. Sending it
+/// replaces all content, embeds and components of an existing message.
+#[derive(Debug, Clone, Default)]
+pub struct View {
+    pub content: String,
+    pub embeds: Vec<CreateEmbed>,
+    pub components: Vec<CreateActionRow>,
+}
+
+impl View {
+    pub fn new(content: impl Into<String>) -> Self {
+        Self {
+            content: content.into(),
+            ..Default::default()
+        }
+    }
+
+    pub fn embed(mut self, embed: CreateEmbed) -> Self {
+        self.embeds.push(embed);
+        self
+    }
+
+    pub fn components(mut self, components: Vec<CreateActionRow>) -> Self {
+        self.components = components;
+        self
+    }
+
+    pub fn reply(self) -> CreateReply {
+        CreateReply {
+            embeds: self.embeds,
+            ..Default::default()
+        }
+        .content(self.content)
+        .components(self.components)
+    }
+
+    pub fn response(self) -> CreateInteractionResponseMessage {
+        CreateInteractionResponseMessage::new()
+            .content(self.content)
+            .embeds(self.embeds)
+            .components(self.components)
+    }
+
+    pub fn followup(self) -> CreateInteractionResponseFollowup {
+        CreateInteractionResponseFollowup::new()
+            .content(self.content)
+            .embeds(self.embeds)
+            .components(self.components)
+    }
+
+    pub fn message(self) -> CreateMessage {
+        CreateMessage::new()
+            .content(self.content)
+            .embeds(self.embeds)
+            .components(self.components)
+    }
+}
+
+/// Status of a posting shown on its card.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CardStatus {
+    Looking,
+    /// Looking, but picked a posting whose creator hasn't answered yet.
+    Awaiting,
+    Matched,
+    Cancelled,
+}
+
+impl CardStatus {
+    pub fn of(scrim: &LookingForScrim) -> Self {
+        if scrim.cancelled {
+            Self::Cancelled
+        } else if scrim.match_id.is_some() {
+            Self::Matched
+        } else {
+            Self::Looking
+        }
+    }
+
+    fn label(self, locale: Locale) -> String {
+        match self {
+            Self::Looking => t!(locale, "status-looking"),
+            Self::Awaiting => t!(locale, "status-awaiting"),
+            Self::Matched => t!(locale, "status-matched"),
+            Self::Cancelled => t!(locale, "status-cancelled"),
+        }
+    }
+
+    fn colour(self) -> Colour {
+        match self {
+            Self::Looking => Colour::BLUE,
+            Self::Awaiting => Colour::GOLD,
+            Self::Matched => Colour::DARK_GREEN,
+            Self::Cancelled => Colour::RED,
+        }
+    }
+}
+
+/// Card of a posting. Callers may change the title or colour, or add a thumbnail or fields.
+pub fn scrim_card(scrim: &LookingForScrim, locale: Locale, now: DateTime<Utc>) -> CreateEmbed {
+    scrim_card_with_status(scrim, CardStatus::of(scrim), locale, now)
+}
+
+/// Card of a posting whose status changed after it was loaded.
+pub fn scrim_card_with_status(
+    scrim: &LookingForScrim,
+    status: CardStatus,
+    locale: Locale,
+    now: DateTime<Utc>,
+) -> CreateEmbed {
+    let mut team = format!("<@{}>", scrim.creator_id);
+    if let Some(team_name) = &scrim.team_name {
+        team.push_str(&format!(" (**{team_name}**)"));
+    }
+    let mut time = format!("<t:{}:F>", scrim.time.timestamp());
+    if shows_relative_time(scrim.time, now) {
+        time.push_str(&format!("\n<t:{}:R>", scrim.time.timestamp()));
+    }
+
+    let mut card = CreateEmbed::new()
+        .title(scrim_title(scrim, locale))
+        .colour(status.colour())
+        .field(
+            t!(locale, "card-region"),
+            format!("{:?}", scrim.region),
+            true,
+        )
+        .field(
+            t!(locale, "card-platform"),
+            format!("{:?}", scrim.platform),
+            true,
+        )
+        .field(t!(locale, "card-rank"), scrim.range.to_string(), true)
+        .field(t!(locale, "card-time"), time, true)
+        .field(t!(locale, "card-team"), team, true)
+        .field(t!(locale, "card-status"), status.label(locale), true);
+    if let Some(reliability) = scrim.reliability {
+        card = card.field(
+            t!(locale, "card-reliability"),
+            format!("{:.0}%", reliability * 100.),
+            true,
+        );
+    }
+    card
+}
+
+const fn check_in_status(checked_in: bool) -> &'static str {
+    if checked_in {
+        ":white_check_mark:"
+    } else {
+        ":hourglass:"
+    }
+}
+
+/// How many candidates are shown at once.
+pub const CANDIDATE_PAGE: i64 = 5;
+/// How many scrims the selector lists at once. Discord allows at most 25 options per menu.
+const SELECT_PAGE: usize = 25;
+
+/// Scrim shown by `/scrims` together with everything needed to render it.
+pub struct ScrimMsg {
+    pub scrim: LookingForScrim,
+    pub state: ScrimState,
+    /// Rank of the first shown candidate.
+    pub offset: i64,
+}
+
+pub struct Candidate {
+    pub diff: f32,
+    pub scrim: LookingForScrim,
+    pub head_to_head: HeadToHead,
+}
+
+pub enum ScrimState {
+    Looking {
+        previous_revoked: bool,
+        matches: Vec<Candidate>,
+    },
+    Matched(LookingForScrim),
+    /// Picked the posting, whose creator hasn't answered yet.
+    Awaiting(LookingForScrim),
+    Cancelled,
+}
+
+impl ScrimMsg {
+    /// Render the scrim at `now`. `scrims` are all upcoming scrims of its creator, sorted by time,
+    /// which can be navigated to.
+    pub fn view(
+        &self,
+        scrims: &[LookingForScrim],
+        prefs: Preferences,
+        locale: Locale,
+        now: DateTime<Utc>,
+    ) -> Result<View, Error> {
+        use std::fmt::Write;
+
+        let id = self.scrim.id;
+        let custom_id = |action| ComponentAction::Scrims(action).custom_id();
+        let mut content = String::new();
+        let mut components = Vec::new();
+        let mut buttons = Vec::new();
+
+        // Navigation between the scrims. The scrim itself is missing from `scrims` if it was
+        // cancelled, so neighbours are found by time.
+        let key = |scrim: &LookingForScrim| (scrim.time, scrim.id);
+        let pos = scrims.partition_point(|scrim| key(scrim) < key(&self.scrim));
+        let listed = scrims.get(pos).is_some_and(|scrim| scrim.id == id);
+        let others = scrims.len() - usize::from(listed);
+        if others > 0 {
+            if listed {
+                writeln!(
+                    content,
+                    "-# {}",
+                    t!(
+                        locale,
+                        "scrims-position",
+                        index = pos + 1,
+                        count = scrims.len()
+                    )
+                )
+                .unwrap();
+            }
+
+            let prev = scrims[(pos + scrims.len() - 1) % scrims.len()].id;
+            let next = scrims[(pos + usize::from(listed)) % scrims.len()].id;
+            let page = pos / SELECT_PAGE * SELECT_PAGE;
+            let options = scrims
+                .iter()
+                .skip(page)
+                .take(SELECT_PAGE)
+                .map(|scrim| {
+                    CreateSelectMenuOption::new(
+                        scrim_label(scrim, prefs, locale),
+                        scrim.id.to_string(),
+                    )
+                    .default_selection(scrim.id == id)
+                })
+                .collect();
+            components.push(CreateActionRow::SelectMenu(
+                CreateSelectMenu::new(
+                    custom_id(ScrimsAction::Select)?,
+                    CreateSelectMenuKind::String { options },
+                )
+                .placeholder(t!(locale, "scrims-select")),
+            ));
+            buttons.push(
+                CreateButton::new(custom_id(ScrimsAction::Prev(prev))?)
+                    .style(ButtonStyle::Secondary)
+                    .label(t!(locale, "button-previous")),
+            );
+            buttons.push(
+                CreateButton::new(custom_id(ScrimsAction::Next(next))?)
+                    .style(ButtonStyle::Secondary)
+                    .label(t!(locale, "button-next")),
+            );
+        }
+
+        // The card reflects the state, as a revoked match isn't stored until the scrim is loaded
+        let status = match &self.state {
+            ScrimState::Looking { .. } => CardStatus::Looking,
+            ScrimState::Awaiting(_) => CardStatus::Awaiting,
+            ScrimState::Matched(_) => CardStatus::Matched,
+            ScrimState::Cancelled => CardStatus::Cancelled,
+        };
+        let mut embeds = vec![scrim_card_with_status(&self.scrim, status, locale, now)];
+
+        // Body
+        match &self.state {
+            ScrimState::Looking {
+                previous_revoked,
+                matches,
+            } => {
+                if *previous_revoked {
+                    writeln!(content, "{}", t!(locale, "scrims-revoked")).unwrap();
+                }
+                if matches.is_empty() && self.offset > 0 {
+                    writeln!(content, "{}", t!(locale, "scrims-no-more-matches")).unwrap();
+                } else if matches.is_empty() {
+                    writeln!(content, "{}", t!(locale, "scrims-no-matches")).unwrap();
+                } else {
+                    let mut accept_buttons = Vec::new();
+                    let mut propose_buttons = Vec::new();
+                    writeln!(content, "### {}", t!(locale, "scrims-matches")).unwrap();
+                    for (match_id, candidate) in matches.iter().enumerate() {
+                        let rank = self.offset as usize + match_id + 1;
+                        write!(content, "{rank}. ").unwrap();
+                        write_scrim_with_name(
+                            &candidate.scrim,
+                            Some(&self.scrim),
+                            true,
+                            locale,
+                            now,
+                            &mut content,
+                        );
+                        if candidate.scrim.match_id.is_some() {
+                            write!(content, " ({})", t!(locale, "scrims-picked-you")).unwrap();
+                        }
+                        if let Some(last) = candidate.head_to_head.last {
+                            let played = if candidate.head_to_head.played == 1 {
+                                t!(locale, "scrims-played-once", last = last.timestamp())
+                            } else {
+                                t!(
+                                    locale,
+                                    "scrims-played",
+                                    count = candidate.head_to_head.played,
+                                    last = last.timestamp()
+                                )
+                            };
+                            write!(content, " ({played})").unwrap();
+                        }
+                        // Left out of tests, so snapshots match in release builds
+                        if cfg!(debug_assertions) && !cfg!(test) {
+                            write!(content, " (diff: {})", candidate.diff).unwrap();
+                        }
+                        content.push('\n');
+
+                        let other = candidate.scrim.id;
+                        accept_buttons.push(
+                            CreateButton::new(custom_id(ScrimsAction::Accept {
+                                scrim_id: id,
+                                other_id: other,
+                            })?)
+                            .style(ButtonStyle::Success)
+                            .label(t!(
+                                locale,
+                                "scrims-accept",
+                                rank = rank
+                            )),
+                        );
+                        propose_buttons.push(
+                            CreateButton::new(custom_id(ScrimsAction::Propose {
+                                scrim_id: id,
+                                other_id: Some(other),
+                            })?)
+                            .style(ButtonStyle::Secondary)
+                            .label(t!(
+                                locale,
+                                "scrims-propose-for",
+                                rank = rank
+                            )),
+                        );
+                    }
+                    components.push(CreateActionRow::Buttons(accept_buttons));
+                    components.push(CreateActionRow::Buttons(propose_buttons));
+                }
+
+                buttons.push(
+                    CreateButton::new(custom_id(ScrimsAction::Refresh(id))?)
+                        .style(ButtonStyle::Primary)
+                        .label(t!(locale, "button-refresh")),
+                );
+                buttons.push(
+                    CreateButton::new(custom_id(ScrimsAction::Cancel(id))?)
+                        .style(ButtonStyle::Danger)
+                        .label(t!(locale, "button-cancel")),
+                );
+                if matches.len() as i64 == CANDIDATE_PAGE {
+                    let offset = self.offset + CANDIDATE_PAGE;
+                    buttons.push(
+                        CreateButton::new(custom_id(ScrimsAction::More {
+                            scrim_id: id,
+                            offset,
+                        })?)
+                        .style(ButtonStyle::Secondary)
+                        .label(t!(locale, "scrims-more")),
+                    );
+                } else if self.offset > 0 {
+                    buttons.push(
+                        CreateButton::new(custom_id(ScrimsAction::More {
+                            scrim_id: id,
+                            offset: 0,
+                        })?)
+                        .style(ButtonStyle::Secondary)
+                        .label(t!(locale, "scrims-top")),
+                    );
+                }
+            }
+            ScrimState::Awaiting(with) => {
+                writeln!(
+                    content,
+                    "{}",
+                    t!(locale, "scrims-awaiting", user = with.creator_id)
+                )
+                .unwrap();
+                embeds.push(scrim_card(with, locale, now));
+                buttons.push(
+                    CreateButton::new(custom_id(ScrimsAction::Refresh(id))?)
+                        .style(ButtonStyle::Primary)
+                        .label(t!(locale, "button-refresh")),
+                );
+                buttons.push(
+                    CreateButton::new(custom_id(ScrimsAction::Cancel(id))?)
+                        .style(ButtonStyle::Danger)
+                        .label(t!(locale, "button-cancel")),
+                );
+            }
+            ScrimState::Matched(with) => {
+                writeln!(
+                    content,
+                    "{}",
+                    t!(locale, "scrims-matched", user = with.creator_id)
+                )
+                .unwrap();
+                embeds.push(scrim_card(with, locale, now));
+                if self.scrim.checked_in || with.checked_in || check_in::is_open(&self.scrim, now) {
+                    content.push_str(&t!(
+                        locale,
+                        "scrims-check-in",
+                        you = check_in_status(self.scrim.checked_in),
+                        them = check_in_status(with.checked_in)
+                    ));
+                }
+                if !self.scrim.checked_in && check_in::is_open(&self.scrim, now) {
+                    buttons.push(
+                        CreateButton::new(custom_id(ScrimsAction::CheckIn(id))?)
+                            .style(ButtonStyle::Success)
+                            .label(t!(locale, "button-check-in")),
+                    );
+                }
+                buttons.push(
+                    CreateButton::new(custom_id(ScrimsAction::Propose {
+                        scrim_id: id,
+                        other_id: None,
+                    })?)
+                    .style(ButtonStyle::Secondary)
+                    .label(t!(locale, "scrims-propose")),
+                );
+                buttons.push(
+                    CreateButton::new(custom_id(ScrimsAction::Revoke(id))?)
+                        .style(ButtonStyle::Danger)
+                        .label(t!(locale, "scrims-revoke")),
+                );
+            }
+            ScrimState::Cancelled => {
+                content.push_str(&t!(locale, "scrims-cancelled"));
+                buttons.push(
+                    CreateButton::new(custom_id(ScrimsAction::Restore(id))?)
+                        .style(ButtonStyle::Primary)
+                        .label(t!(locale, "scrims-restore")),
+                );
+            }
+        }
+
+        components.push(CreateActionRow::Buttons(buttons));
+        components.push(CreateActionRow::Buttons(vec![new_scrim_button(locale)?]));
+        Ok(View {
+            content,
+            embeds,
+            components,
+        })
+    }
+}
+
+/// Whether a time is soon enough to also be shown relative to now.
+fn shows_relative_time(time: DateTime<Utc>, now: DateTime<Utc>) -> bool {
+    time - now < chrono::Duration::days(1)
+}
+
+/// Title of a posting, which is its team name if it has one.
+pub fn scrim_title(scrim: &LookingForScrim, locale: Locale) -> String {
+    match &scrim.team_name {
+        Some(team_name) => team_name.clone(),
+        None => t!(locale, "card-title"),
+    }
+}
+
+/// Label of a scrim in select menus, cut to the 100 characters Discord allows.
+pub fn scrim_label(scrim: &LookingForScrim, prefs: Preferences, locale: Locale) -> String {
+    let mut label = String::new();
+    if let Some(team_name) = &scrim.team_name {
+        label.push_str(&format!("{team_name}: "));
+    }
+    label.push_str(&t!(
+        locale,
+        "scrim-label",
+        region = format!("{:?}", scrim.region),
+        platform = format!("{:?}", scrim.platform),
+        range = scrim.range,
+        time = prefs.long(scrim.time, locale)
+    ));
+    if label.chars().count() > 100 {
+        label = label.chars().take(99).collect();
+        label.push('…');
+    }
+    label
+}
+
+pub fn write_scrim_with_name(
+    lfs: &LookingForScrim,
+    other: Option<&LookingForScrim>,
+    show_creator: bool,
+    locale: Locale,
+    now: DateTime<Utc>,
+    content: &mut String,
+) {
+    use std::fmt::Write;
+
+    if show_creator {
+        write!(content, "<@{}> ", lfs.creator_id).unwrap();
+        if let Some(team_name) = &lfs.team_name {
+            write!(content, "(**{team_name}**) ").unwrap();
+        }
+    } else if let Some(team_name) = &lfs.team_name {
+        write!(content, "**{team_name}** ").unwrap();
+    }
+    if let Some(reliability) = lfs.reliability {
+        let reliability = format!("{:.0}", reliability * 100.);
+        write!(
+            content,
+            "[{}] ",
+            t!(locale, "scrim-reliable", percent = reliability)
+        )
+        .unwrap();
+    }
+
+    write_scrim_meta(lfs, other, locale, now, content);
+}
+
+pub fn write_scrim_meta(
+    lfs: &LookingForScrim,
+    other: Option<&LookingForScrim>,
+    locale: Locale,
+    now: DateTime<Utc>,
+    content: &mut String,
+) {
+    use std::fmt::Write;
+
+    write!(content, "{:?}/{:?} {}", lfs.region, lfs.platform, lfs.range).unwrap();
+
+    let show_time = if let Some(other) = other {
+        lfs.time != other.time
+    } else {
+        true
+    };
+
+    if show_time {
+        write!(
+            content,
+            " {}",
+            t!(locale, "scrim-time", time = lfs.time.timestamp())
+        )
+        .unwrap();
+        if shows_relative_time(lfs.time, now) {
+            write!(content, " (<t:{}:R>)", lfs.time.timestamp()).unwrap();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::TimeZone;
+    use poise::serenity_prelude::json::to_string_pretty;
+
+    use super::*;
+    use crate::lfs::{Platform, RankRange, Region};
+
+    /// Compare the rendered view with `src/snapshots/<name>.snap`. Missing snapshots are written,
+    /// and `UPDATE_SNAPSHOTS=1` overwrites changed ones.
+    fn assert_snapshot(name: &str, view: &View) {
+        let rendered = format!(
+            "{}\n--- embeds ---\n{}\n--- components ---\n{}\n",
+            view.content,
+            to_string_pretty(&view.embeds).unwrap(),
+            to_string_pretty(&view.components).unwrap(),
+        );
+        let path = std::path::Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("src/snapshots")
+            .join(format!("{name}.snap"));
+        if std::env::var_os("UPDATE_SNAPSHOTS").is_some() {
+            std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+            std::fs::write(&path, rendered).unwrap();
+            return;
+        }
+        let expected = std::fs::read_to_string(&path).unwrap_or_else(|_| {
+            panic!("snapshot {name} is missing, run with UPDATE_SNAPSHOTS=1 to create it")
+        });
+        assert_eq!(rendered, expected, "snapshot {name} changed");
+    }
+
+    fn now() -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2024, 7, 4, 18, 0, 0).unwrap()
+    }
+
+    fn scrim(id: i32, creator_id: i64, hours: i64) -> LookingForScrim {
+        LookingForScrim {
+            id,
+            creator_id,
+            team_name: None,
+            region: Region::EU,
+            platform: Platform::PC,
+            range: RankRange(4000..4500),
+            time: now() + chrono::Duration::hours(hours),
+            match_id: None,
+            cancelled: false,
+            checked_in: false,
+            no_show: false,
+            reliability: None,
+            auto_match: false,
+        }
+    }
+
+    fn candidate(scrim: LookingForScrim, played: i64) -> Candidate {
+        Candidate {
+            diff: 0.5,
+            scrim,
+            head_to_head: HeadToHead {
+                played,
+                last: (played > 0).then(|| now() - chrono::Duration::days(14)),
+            },
+        }
+    }
+
+    fn render(msg: &ScrimMsg, scrims: &[LookingForScrim]) -> View {
+        msg.view(scrims, Preferences::default(), Locale::En, now())
+            .unwrap()
+    }
+
+    #[test]
+    fn looking_with_candidates() {
+        let own = LookingForScrim {
+            team_name: Some("Alpha".to_owned()),
+            ..scrim(1, 10, 6)
+        };
+        let picked_us = LookingForScrim {
+            match_id: Some(1),
+            reliability: Some(0.9),
+            ..scrim(2, 20, 6)
+        };
+        let other_time = LookingForScrim {
+            team_name: Some("Bravo".to_owned()),
+            ..scrim(3, 30, 8)
+        };
+        let msg = ScrimMsg {
+            scrim: own.clone(),
+            state: ScrimState::Looking {
+                previous_revoked: false,
+                matches: vec![candidate(picked_us, 0), candidate(other_time, 2)],
+            },
+            offset: 0,
+        };
+        assert_snapshot("looking_with_candidates", &render(&msg, &[own]));
+    }
+
+    #[test]
+    fn looking_without_candidates() {
+        let own = scrim(1, 10, 48);
+        let msg = ScrimMsg {
+            scrim: own.clone(),
+            state: ScrimState::Looking {
+                previous_revoked: false,
+                matches: Vec::new(),
+            },
+            offset: 0,
+        };
+        assert_snapshot("looking_without_candidates", &render(&msg, &[own]));
+    }
+
+    #[test]
+    fn looking_after_revoke() {
+        let own = scrim(1, 10, 6);
+        let msg = ScrimMsg {
+            scrim: own.clone(),
+            state: ScrimState::Looking {
+                previous_revoked: true,
+                matches: vec![candidate(scrim(2, 20, 6), 1)],
+            },
+            offset: 0,
+        };
+        assert_snapshot("looking_after_revoke", &render(&msg, &[own]));
+    }
+
+    #[test]
+    fn matched_before_check_in() {
+        let own = LookingForScrim {
+            match_id: Some(2),
+            ..scrim(1, 10, 48)
+        };
+        let msg = ScrimMsg {
+            scrim: own.clone(),
+            state: ScrimState::Matched(LookingForScrim {
+                match_id: Some(1),
+                ..scrim(2, 20, 48)
+            }),
+            offset: 0,
+        };
+        assert_snapshot("matched_before_check_in", &render(&msg, &[own]));
+    }
+
+    #[test]
+    fn awaiting_answer() {
+        let own = LookingForScrim {
+            match_id: Some(2),
+            ..scrim(1, 10, 6)
+        };
+        let msg = ScrimMsg {
+            scrim: own.clone(),
+            state: ScrimState::Awaiting(scrim(2, 20, 6)),
+            offset: 0,
+        };
+        assert_snapshot("awaiting_answer", &render(&msg, &[own]));
+    }
+
+    #[test]
+    fn matched_during_check_in() {
+        let own = LookingForScrim {
+            match_id: Some(2),
+            ..scrim(1, 10, 0)
+        };
+        let msg = ScrimMsg {
+            scrim: LookingForScrim {
+                time: now() + chrono::Duration::minutes(20),
+                ..own.clone()
+            },
+            state: ScrimState::Matched(LookingForScrim {
+                match_id: Some(1),
+                checked_in: true,
+                time: now() + chrono::Duration::minutes(20),
+                ..scrim(2, 20, 0)
+            }),
+            offset: 0,
+        };
+        assert_snapshot("matched_during_check_in", &render(&msg, &[own]));
+    }
+
+    #[test]
+    fn cancelled() {
+        let own = LookingForScrim {
+            cancelled: true,
+            ..scrim(1, 10, 6)
+        };
+        let msg = ScrimMsg {
+            scrim: own,
+            state: ScrimState::Cancelled,
+            offset: 0,
+        };
+        // Cancelled scrims are no longer listed, but the others can still be navigated to.
+        assert_snapshot("cancelled", &render(&msg, &[scrim(2, 10, 30)]));
+    }
+
+    #[test]
+    fn navigation_between_scrims() {
+        let scrims = [scrim(1, 10, 6), scrim(2, 10, 30), scrim(3, 10, 54)];
+        let msg = ScrimMsg {
+            scrim: scrims[1].clone(),
+            state: ScrimState::Looking {
+                previous_revoked: false,
+                matches: Vec::new(),
+            },
+            offset: 0,
+        };
+        assert_snapshot("navigation_between_scrims", &render(&msg, &scrims));
+    }
+
+    #[test]
+    fn relative_time_within_one_day() {
+        let soon = scrim(1, 10, 23);
+        let later = scrim(2, 20, 25);
+        assert!(shows_relative_time(soon.time, now()));
+        assert!(!shows_relative_time(later.time, now()));
+
+        let mut content = String::new();
+        write_scrim_meta(&soon, None, Locale::En, now(), &mut content);
+        content.push('\n');
+        write_scrim_meta(&later, None, Locale::En, now(), &mut content);
+        let view = View::new(content)
+            .embed(scrim_card(&soon, Locale::En, now()))
+            .embed(scrim_card(&later, Locale::En, now()));
+        assert_snapshot("relative_time_within_one_day", &view);
+    }
+
+    #[test]
+    fn matched_in_german() {
+        let own = LookingForScrim {
+            match_id: Some(2),
+            team_name: Some("Alpha".to_owned()),
+            ..scrim(1, 10, 6)
+        };
+        let msg = ScrimMsg {
+            scrim: own.clone(),
+            state: ScrimState::Matched(LookingForScrim {
+                match_id: Some(1),
+                reliability: Some(0.75),
+                ..scrim(2, 20, 6)
+            }),
+            offset: 0,
+        };
+        let view = msg
+            .view(&[own], Preferences::default(), Locale::De, now())
+            .unwrap();
+        assert_snapshot("matched_in_german", &view);
+    }
+}
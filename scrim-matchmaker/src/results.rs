@@ -0,0 +1,249 @@
+use chrono::Utc;
+use poise::{
+    serenity_prelude::{
+        self as serenity, ButtonStyle, ComponentInteraction, CreateActionRow, CreateButton,
+        CreateInputText, CreateMessage, CreateModal, EditMessage, InputTextStyle,
+    },
+    Modal,
+};
+
+use crate::{
+    components::ComponentAction,
+    db,
+    i18n::{self, t, Locale, Text},
+    interactions::{execute_modal, LocalizedModal, MODAL_TIMEOUT},
+    no_show::no_show_button,
+    reminders::send_dm,
+    render::write_scrim_with_name,
+    scrims::complete_recent_match,
+    Context, Data, Error,
+};
+
+/// How long a scrim is assumed to take.
+pub const SCRIM_DURATION: i32 = 2 * 60;
+/// How many minutes after the end of a scrim its creator is still asked for the result.
+const PROMPT_GRACE: i32 = 24 * 60;
+
+#[derive(Debug, Clone)]
+pub struct ScrimResult {
+    pub maps_won: i32,
+    pub maps_lost: i32,
+    pub details: Option<String>,
+    pub rating: i32,
+    pub comment: Option<String>,
+}
+
+fn parse_score(s: &str) -> Result<(i32, i32), Text> {
+    let err = Text::new("report-score-invalid").arg("input", s);
+    let Some((won, lost)) = s.trim().split_once('-') else {
+        return Err(err);
+    };
+    match (won.trim().parse::<i32>(), lost.trim().parse::<i32>()) {
+        (Ok(won), Ok(lost)) if won >= 0 && lost >= 0 => Ok((won, lost)),
+        _ => Err(err),
+    }
+}
+
+fn check_rating(rating: i32) -> Result<i32, Text> {
+    if (1..=5).contains(&rating) {
+        Ok(rating)
+    } else {
+        Err(Text::new("report-rating-invalid").arg("input", rating))
+    }
+}
+
+fn parse_rating(s: &str) -> Result<i32, Text> {
+    s.trim()
+        .parse::<i32>()
+        .map_err(|_| Text::new("report-rating-invalid").arg("input", s))
+}
+
+/// Check the length of a text field, failing with `too_long`.
+fn check_length(text: Option<String>, too_long: Text) -> Result<Option<String>, Text> {
+    match text {
+        Some(text) if text.len() > 1000 => Err(too_long),
+        text => Ok(text),
+    }
+}
+
+impl ScrimResult {
+    fn new(
+        score: &str,
+        rating: i32,
+        details: Option<String>,
+        comment: Option<String>,
+    ) -> Result<Self, Text> {
+        let (maps_won, maps_lost) = parse_score(score)?;
+        Ok(Self {
+            maps_won,
+            maps_lost,
+            details: check_length(details, Text::new("report-details-too-long"))?,
+            rating: check_rating(rating)?,
+            comment: check_length(comment, Text::new("report-comment-too-long"))?,
+        })
+    }
+
+    /// Parse the fields of the report modal, which are all text.
+    fn parse(
+        score: &str,
+        rating: &str,
+        details: Option<String>,
+        comment: Option<String>,
+    ) -> Result<Self, Text> {
+        Self::new(score, parse_rating(rating)?, details, comment)
+    }
+
+    fn summary(&self, locale: Locale) -> String {
+        use std::fmt::Write;
+
+        let mut summary = t!(
+            locale,
+            "report-saved",
+            won = self.maps_won,
+            lost = self.maps_lost,
+            rating = self.rating
+        );
+        if let Some(details) = &self.details {
+            write!(summary, "\n{details}").unwrap();
+        }
+        summary
+    }
+}
+
+#[derive(Debug, Modal)]
+struct ResultModal {
+    score: String,
+    rating: String,
+    details: Option<String>,
+    comment: Option<String>,
+}
+
+impl LocalizedModal for ResultModal {
+    fn create(locale: Locale, custom_id: String) -> CreateModal {
+        CreateModal::new(custom_id, t!(locale, "report-modal")).components(vec![
+            CreateActionRow::InputText(
+                CreateInputText::new(
+                    InputTextStyle::Short,
+                    t!(locale, "report-modal-score"),
+                    "score",
+                )
+                .placeholder("3-2")
+                .max_length(7),
+            ),
+            CreateActionRow::InputText(
+                CreateInputText::new(
+                    InputTextStyle::Short,
+                    t!(locale, "report-modal-rating"),
+                    "rating",
+                )
+                .placeholder("5")
+                .max_length(1),
+            ),
+            CreateActionRow::InputText(
+                CreateInputText::new(
+                    InputTextStyle::Paragraph,
+                    t!(locale, "report-modal-details"),
+                    "details",
+                )
+                .placeholder("Ilios 2-1, Rialto 3-2")
+                .max_length(1000)
+                .required(false),
+            ),
+            CreateActionRow::InputText(
+                CreateInputText::new(
+                    InputTextStyle::Paragraph,
+                    t!(locale, "report-modal-comment"),
+                    "comment",
+                )
+                .max_length(1000)
+                .required(false),
+            ),
+        ])
+    }
+}
+
+#[poise::command(slash_command, ephemeral)]
+#[tracing::instrument(err, skip(ctx), fields(user = ctx.author().id.get()))]
+pub async fn report(
+    ctx: Context<'_>,
+    #[autocomplete = "complete_recent_match"] scrim: i32,
+    score: String,
+    #[min = 1]
+    #[max = 5]
+    rating: u8,
+    details: Option<String>,
+    comment: Option<String>,
+) -> Result<(), Error> {
+    let result = ScrimResult::new(&score, rating.into(), details, comment)?;
+    db::save_result(&ctx.data().db, ctx.author().id.get() as i64, scrim, &result).await?;
+    ctx.reply(result.summary(i18n::locale(ctx).await)).await?;
+    Ok(())
+}
+
+/// Ask both sides of finished scrims to report the result.
+#[tracing::instrument(err, skip(ctx, db))]
+pub async fn send_prompts(ctx: &serenity::Context, db: &sqlx::PgPool) -> Result<(), Error> {
+    for (scrim_id, other_id) in db::get_results_to_prompt(db, SCRIM_DURATION, PROMPT_GRACE).await? {
+        if !db::mark_result_prompted(db, scrim_id).await? {
+            continue;
+        }
+
+        let scrim = db::get_scrim_in(db, scrim_id).await?;
+        let other = db::get_scrim_in(db, other_id).await?;
+
+        let locale = i18n::locale_of(db, scrim.creator_id, None).await;
+        let mut opponent = String::new();
+        write_scrim_with_name(
+            &other,
+            Some(&scrim),
+            true,
+            locale,
+            Utc::now(),
+            &mut opponent,
+        );
+        let content = t!(locale, "report-prompt", opponent = opponent);
+
+        let msg = CreateMessage::new()
+            .content(content)
+            .components(vec![CreateActionRow::Buttons(vec![
+                CreateButton::new(ComponentAction::Report { scrim_id }.custom_id()?)
+                    .style(ButtonStyle::Primary)
+                    .label(t!(locale, "report-button")),
+                no_show_button(scrim_id, locale)?,
+            ])]);
+        if let Err(e) = send_dm(ctx, scrim.creator_id, msg).await {
+            tracing::warn!("Could not send result prompt to {}: {e}", scrim.creator_id);
+        }
+    }
+
+    Ok(())
+}
+
+/// Handle the report buttons sent in direct messages.
+pub async fn handle_component(
+    ctx: &serenity::Context,
+    data: &Data,
+    i: &ComponentInteraction,
+    scrim_id: i32,
+) -> Result<(), Error> {
+    let locale = i18n::component_locale(data, i).await;
+    let Some(modal) = execute_modal::<ResultModal>(ctx, i, locale, MODAL_TIMEOUT).await? else {
+        return Ok(()); // Timeout
+    };
+
+    let saved = match ScrimResult::parse(&modal.score, &modal.rating, modal.details, modal.comment)
+    {
+        Ok(result) => db::save_result(&data.db, i.user.id.get() as i64, scrim_id, &result)
+            .await
+            .map(|()| result.summary(locale))
+            .map_err(|e| i18n::error_text(&e, locale)),
+        Err(e) => Err(e.render(locale)),
+    };
+    let edit = match saved {
+        Ok(summary) => EditMessage::new().content(summary).components(vec![]),
+        // Keep the buttons so the report can be retried
+        Err(e) => EditMessage::new().content(t!(locale, "report-retry", error = e)),
+    };
+    i.message.clone().edit(ctx, edit).await?;
+    Ok(())
+}
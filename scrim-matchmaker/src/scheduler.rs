@@ -0,0 +1,30 @@
+use std::time::Duration;
+
+use poise::serenity_prelude as serenity;
+
+use crate::{auto_match, board, check_in, db, reminders, results};
+
+/// How often the scheduler checks the database for due jobs.
+const TICK: Duration = Duration::from_secs(60);
+
+/// Start the background scheduler. All jobs are driven by the database, so nothing is lost when the
+/// bot restarts.
+pub fn start(ctx: serenity::Context, db: sqlx::PgPool) {
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(TICK);
+        interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+        loop {
+            interval.tick().await;
+            // Errors are already logged by the jobs themselves
+            _ = reminders::send_due(&ctx, &db).await;
+            _ = check_in::send_prompts(&ctx, &db).await;
+            _ = check_in::resolve(&ctx, &db).await;
+            _ = results::send_prompts(&ctx, &db).await;
+            _ = auto_match::run(&ctx, &db).await;
+            _ = board::sync(&ctx, &db).await;
+            _ = db::delete_stale_drafts(&db).await;
+            _ = db::delete_stale_filters(&db).await;
+            _ = db::delete_stale_imports(&db).await;
+        }
+    });
+}
@@ -1,339 +1,264 @@
 use chrono::Utc;
-use poise::{
-    serenity_prelude::{
-        self as serenity,
-        futures::{stream, StreamExt, TryStreamExt},
-        ButtonStyle, ComponentInteraction, ComponentInteractionCollector, CreateActionRow,
-        CreateButton, CreateInteractionResponse, CreateInteractionResponseMessage,
-    },
-    CreateReply,
+use poise::serenity_prelude::{
+    self as serenity, AutocompleteChoice, ComponentInteraction, ComponentInteractionDataKind,
+    CreateActionRow, CreateInteractionResponse, CreateInteractionResponseFollowup,
 };
 
-use crate::{db, lfs::LookingForScrim, Context, Error};
+use crate::{
+    check_in,
+    components::ScrimsAction,
+    db,
+    i18n::{self, t, Locale, Text},
+    interactions::report_error,
+    lfs::{new_scrim_button, LookingForScrim},
+    overlaps::overlap_warning,
+    proposals,
+    render::{Candidate, ScrimMsg, ScrimState, View, CANDIDATE_PAGE},
+    Context, Data, Error,
+};
+
+/// How many days after a scrim it can still be picked for reports.
+const REPORT_DAYS: i32 = 7;
 
-struct ScrimMsg {
-    index: usize,
-    scrim: LookingForScrim,
-    state: ScrimState,
+/// Autocomplete the matched scrims of the author that started recently.
+pub async fn complete_recent_match(ctx: Context<'_>, partial: &str) -> Vec<AutocompleteChoice> {
+    let (Ok(matches), Ok(prefs)) = (
+        db::get_recent_matches(ctx, REPORT_DAYS).await,
+        db::get_preferences(ctx).await,
+    ) else {
+        return Vec::new();
+    };
+    let locale = i18n::locale(ctx).await;
+    let partial = partial.trim().to_lowercase();
+    matches
+        .into_iter()
+        .filter_map(|(scrim, other)| {
+            let name = t!(
+                locale,
+                "recent-match",
+                team = other
+                    .team_name
+                    .clone()
+                    .unwrap_or_else(|| t!(locale, "unnamed-team")),
+                region = format!("{:?}", other.region),
+                platform = format!("{:?}", other.platform),
+                time = prefs.long(scrim.time, locale)
+            );
+            name.to_lowercase()
+                .contains(&partial)
+                .then(|| AutocompleteChoice::new(name, scrim.id))
+        })
+        .take(25)
+        .collect()
 }
 
-enum ScrimState {
-    Looking {
-        previous_revoked: bool,
-        matches: Vec<(f32, LookingForScrim)>,
-    },
-    Matched(LookingForScrim),
-    Cancelled,
+/// Find potential matches for a scrim together with the record of its creator against them.
+async fn find_candidates(
+    db: &sqlx::PgPool,
+    scrim: &LookingForScrim,
+    offset: i64,
+) -> Result<Vec<Candidate>, Error> {
+    let mut candidates = Vec::new();
+    for (diff, other) in db::find_matches_in(db, scrim, offset, CANDIDATE_PAGE).await? {
+        candidates.push(Candidate {
+            diff,
+            head_to_head: db::get_head_to_head_in(db, scrim.creator_id, other.creator_id).await?,
+            scrim: other,
+        });
+    }
+    Ok(candidates)
 }
 
 impl ScrimMsg {
-    async fn new(ctx: Context<'_>, scrim: LookingForScrim, index: usize) -> Result<Self, Error> {
-        if let Some(match_id) = scrim.match_id {
-            let other = db::get_scrim(ctx, match_id).await?;
-            if let Some(other_match_id) = other.match_id {
-                if other_match_id != scrim.id {
-                    db::revoke_scrim(ctx, scrim.id).await?;
-                    return Ok(Self {
-                        state: ScrimState::Looking {
-                            previous_revoked: true,
-                            matches: db::find_matches(ctx, &scrim).await?,
-                        },
-                        scrim,
-                        index,
-                    });
+    async fn new(db: &sqlx::PgPool, scrim: LookingForScrim, offset: i64) -> Result<Self, Error> {
+        let state = if scrim.cancelled {
+            ScrimState::Cancelled
+        } else if let Some(match_id) = scrim.match_id {
+            let other = db::get_scrim_in(db, match_id).await?;
+            if other.match_id.is_some_and(|id| id != scrim.id) {
+                db::revoke_scrim_in(db, scrim.id).await?;
+                ScrimState::Looking {
+                    previous_revoked: true,
+                    matches: find_candidates(db, &scrim, offset).await?,
                 }
+            } else if other.match_id == Some(scrim.id) {
+                ScrimState::Matched(other)
+            } else {
+                // Picked postings are only matched once the other side accepts
+                ScrimState::Awaiting(other)
             }
-            Ok(Self {
-                state: ScrimState::Matched(other),
-                scrim,
-                index,
-            })
         } else {
-            Ok(Self {
-                state: ScrimState::Looking {
-                    previous_revoked: false,
-                    matches: db::find_matches(ctx, &scrim).await?,
-                },
-                scrim,
-                index,
-            })
-        }
-    }
-
-    fn msg(&self, ctx: Context<'_>) -> (String, Vec<CreateActionRow>) {
-        use std::fmt::Write;
-
-        let mut content = String::new();
-        let mut buttons = Vec::new();
-
-        // Title
-        content.push_str("## ");
-        if let Some(team_name) = &self.scrim.team_name {
-            write!(content, "{team_name}: ").unwrap();
-        }
-        write_scrim_meta(&self.scrim, None, &mut content);
-        content.push('\n');
-
-        // Body
-        match &self.state {
             ScrimState::Looking {
-                previous_revoked,
-                matches,
-            } => {
-                if *previous_revoked {
-                    content.push_str("Your previous partner picked someone else.\n");
-                }
-                if matches.is_empty() {
-                    content.push_str("No matches found. Try again later\n");
-                } else {
-                    content.push_str("### Potential matches:\n");
-                    for (match_id, (diff, other)) in matches.iter().enumerate() {
-                        write!(content, "{}. ", match_id + 1).unwrap();
-                        write_scrim_with_name(other, Some(&self.scrim), true, &mut content);
-                        if other.match_id.is_some() {
-                            content.push_str(" (picked you)");
-                        }
-                        #[cfg(debug_assertions)]
-                        write!(content, " (diff: {diff})").unwrap();
-                        content.push('\n');
-
-                        buttons.push(
-                            CreateButton::new(format!(
-                                "{},accept,{},{}",
-                                ctx.id(),
-                                self.index,
-                                match_id
-                            ))
-                            .style(ButtonStyle::Success)
-                            .label(format!("Accept match {}", match_id + 1)),
-                        );
-                    }
-                }
-
-                buttons.push(
-                    CreateButton::new(format!("{},refresh,{}", ctx.id(), self.index))
-                        .style(ButtonStyle::Primary)
-                        .label("Refresh"),
-                );
-                buttons.push(
-                    CreateButton::new(format!("{},cancel,{}", ctx.id(), self.index))
-                        .style(ButtonStyle::Danger)
-                        .label("Cancel"),
-                );
+                previous_revoked: false,
+                matches: find_candidates(db, &scrim, offset).await?,
             }
-            ScrimState::Matched(with) => {
-                content.push_str("Matched with ");
-                write_scrim_with_name(with, Some(&self.scrim), true, &mut content);
-                content.push('\n');
-                content.push_str("Remember to message them about the details :)");
-                buttons.push(
-                    CreateButton::new(format!("{},revoke,{}", ctx.id(), self.index))
-                        .style(ButtonStyle::Danger)
-                        .label("Revoke"),
-                );
-            }
-            ScrimState::Cancelled => {
-                content.push_str("Scrim cancelled and removed from matchmaker");
-                buttons.push(
-                    CreateButton::new(format!("{},restore,{}", ctx.id(), self.index))
-                        .style(ButtonStyle::Primary)
-                        .label("Restore"),
-                );
-            }
-        }
-
-        if buttons.is_empty() {
-            (content, Vec::new())
-        } else {
-            (
-                content,
-                buttons
-                    .chunks(5)
-                    .map(|chunk| CreateActionRow::Buttons(chunk.to_vec()))
-                    .collect(),
-            )
-        }
+        };
+        Ok(Self {
+            scrim,
+            state,
+            offset,
+        })
     }
 }
 
-#[poise::command(
-    slash_command,
-    description_localized("en-US", "List your upcoming scrims"),
-    ephemeral
-)]
-#[tracing::instrument(err, skip(ctx), fields(user = ctx.author().id.get()))]
-pub async fn scrims(ctx: Context<'_>) -> Result<(), Error> {
-    let mut scrims = db::get_future_scrims(ctx).await?;
-    scrims.sort_by_key(|scrim| scrim.time);
-    if scrims.is_empty() {
-        ctx.reply("You have no upcoming scrims. Use `/lfs` to look for one")
-            .await?;
-        return Ok(());
-    }
-
-    let mut msgs: Vec<_> = stream::iter(scrims.into_iter())
-        .enumerate()
-        .then(|(index, scrim)| ScrimMsg::new(ctx, scrim, index))
-        .try_collect()
-        .await?;
-
-    let handles: Vec<_> = stream::iter(msgs.iter())
-        .then(|msg| async {
-            let (content, components) = msg.msg(ctx);
-            ctx.send(
-                CreateReply::default()
-                    .content(content)
-                    .components(components),
-            )
-            .await
-        })
-        .try_collect()
-        .await?;
-
-    let delete_handle = if msgs.len() > 2 {
-        Some(
-            ctx.send(
-                CreateReply::default().components(vec![CreateActionRow::Buttons(vec![
-                    CreateButton::new(format!("{},remove_msgs,0", ctx.id()))
-                        .style(ButtonStyle::Secondary)
-                        .label("Remove messages"),
-                ])]),
-            )
-            .await?,
-        )
-    } else {
-        None
+/// Render the scrim `id` of `creator_id`, or their next scrim if no id is given.
+async fn scrims_msg(
+    db: &sqlx::PgPool,
+    creator_id: i64,
+    id: Option<i32>,
+    offset: i64,
+    locale: Locale,
+) -> Result<Option<View>, Error> {
+    let mut scrims = db::get_future_scrims_in(db, creator_id).await?;
+    scrims.sort_by_key(|scrim| (scrim.time, scrim.id));
+    let scrim = match id {
+        Some(id) => db::get_scrim_in(db, id).await?,
+        None => match scrims.first() {
+            Some(scrim) => scrim.clone(),
+            None => return Ok(None),
+        },
     };
-
-    let ctx_id_str = ctx.id().to_string();
-    let mut listener = ComponentInteractionCollector::new(ctx)
-        .filter(move |i| i.data.custom_id.starts_with(&ctx_id_str))
-        .timeout(std::time::Duration::from_secs(30 * 60))
-        .stream();
-
-    while let Some(i) = listener.next().await {
-        let mut split = i.data.custom_id.split(',');
-        _ = split.next(); // skip the ctx_id
-        let action = split.next().unwrap();
-        let scrim_id = split.next().unwrap().parse::<usize>().unwrap();
-        let scrim = &mut msgs[scrim_id];
-
-        match action {
-            "refresh" => {
-                scrim.state = ScrimState::Looking {
-                    previous_revoked: false,
-                    matches: db::find_matches(ctx, &scrim.scrim).await?,
-                };
-                respond(ctx, i, scrim.msg(ctx)).await?;
-            }
-            "cancel" => {
-                db::cancel_scrim(ctx, scrim.scrim.id).await?;
-                scrim.state = ScrimState::Cancelled;
-                respond(ctx, i, scrim.msg(ctx)).await?;
-            }
-            "restore" => {
-                db::restore_scrim(ctx, scrim.scrim.id).await?;
-                scrim.state = ScrimState::Looking {
-                    previous_revoked: false,
-                    matches: db::find_matches(ctx, &scrim.scrim).await?,
-                };
-                respond(ctx, i, scrim.msg(ctx)).await?;
-            }
-            "revoke" => {
-                db::revoke_scrim(ctx, scrim.scrim.id).await?;
-                scrim.state = ScrimState::Looking {
-                    previous_revoked: false,
-                    matches: db::find_matches(ctx, &scrim.scrim).await?,
-                };
-                respond(ctx, i, scrim.msg(ctx)).await?;
-            }
-            "accept" => {
-                let match_id = split.next().unwrap().parse::<usize>().unwrap();
-                let ScrimState::Looking { matches, .. } = &scrim.state else {
-                    continue;
-                };
-                let other = &matches[match_id].1;
-
-                db::match_scrims(ctx, scrim.scrim.id, other.id).await?;
-                scrim.state = ScrimState::Matched(other.clone());
-                respond(ctx, i, scrim.msg(ctx)).await?;
-            }
-            "remove_msgs" => {
-                for h in handles {
-                    h.delete(ctx.into()).await?;
-                }
-                if let Some(h) = delete_handle {
-                    h.delete(ctx.into()).await?;
-                }
-                return Ok(());
-            }
-            _ => continue,
-        }
+    if scrim.creator_id != creator_id {
+        return Err(Text::new("scrim-not-yours").into());
     }
+    let prefs = db::get_preferences_of(db, creator_id)
+        .await
+        .unwrap_or_default();
 
-    // Timeout
-    for h in handles {
-        h.delete(ctx.into()).await?;
-    }
+    let msg = ScrimMsg::new(db, scrim, offset).await?;
+    msg.view(&scrims, prefs, locale, Utc::now()).map(Some)
+}
 
+#[poise::command(slash_command, ephemeral)]
+#[tracing::instrument(err, skip(ctx), fields(user = ctx.author().id.get()))]
+pub async fn scrims(ctx: Context<'_>) -> Result<(), Error> {
+    let locale = i18n::locale(ctx).await;
+    let user_id = ctx.author().id.get() as i64;
+    let view = match scrims_msg(&ctx.data().db, user_id, None, 0, locale).await? {
+        Some(view) => view,
+        None => {
+            View::new(t!(locale, "scrims-none")).components(vec![CreateActionRow::Buttons(vec![
+                new_scrim_button(locale)?,
+            ])])
+        }
+    };
+    ctx.send(view.reply()).await?;
     Ok(())
 }
 
-async fn respond(
-    ctx: Context<'_>,
-    i: ComponentInteraction,
-    (content, components): (String, Vec<CreateActionRow>),
-) -> Result<(), serenity::Error> {
-    i.create_response(
-        ctx,
-        CreateInteractionResponse::UpdateMessage(
-            CreateInteractionResponseMessage::new()
-                .content(content)
-                .components(components),
-        ),
-    )
-    .await
+/// Get the scrim `id` if it was posted by `user_id`.
+async fn own_scrim(db: &sqlx::PgPool, id: i32, user_id: i64) -> Result<LookingForScrim, Error> {
+    let scrim = db::get_scrim_in(db, id).await?;
+    if scrim.creator_id != user_id {
+        return Err(Text::new("scrim-not-yours").into());
+    }
+    Ok(scrim)
 }
 
-pub fn write_scrim_with_name(
-    lfs: &LookingForScrim,
-    other: Option<&LookingForScrim>,
-    show_creator: bool,
-    content: &mut String,
-) {
-    use std::fmt::Write;
-
-    if show_creator {
-        write!(content, "<@{}> ", lfs.creator_id).unwrap();
-        if let Some(team_name) = &lfs.team_name {
-            write!(content, "(**{team_name}**) ").unwrap();
-        }
-    } else if let Some(team_name) = &lfs.team_name {
-        write!(content, "**{team_name}** ").unwrap();
+/// Handle the components of `/scrims`. Everything is loaded from the database, so they keep
+/// working after restarts.
+pub async fn handle_component(
+    ctx: &serenity::Context,
+    data: &Data,
+    i: &ComponentInteraction,
+    action: ScrimsAction,
+) -> Result<(), Error> {
+    match handle_action(ctx, data, i, action).await {
+        Ok(()) => Ok(()),
+        Err(e) => report_error(ctx, data, i, e).await,
     }
-
-    write_scrim_meta(lfs, other, content);
 }
 
-pub fn write_scrim_meta(
-    lfs: &LookingForScrim,
-    other: Option<&LookingForScrim>,
-    content: &mut String,
-) {
-    use std::fmt::Write;
-
-    write!(content, "{:?}/{:?} {}", lfs.region, lfs.platform, lfs.range).unwrap();
+async fn handle_action(
+    ctx: &serenity::Context,
+    data: &Data,
+    i: &ComponentInteraction,
+    action: ScrimsAction,
+) -> Result<(), Error> {
+    let db = &data.db;
+    let user_id = i.user.id.get() as i64;
+    let locale = i18n::component_locale(data, i).await;
 
-    let show_time = if let Some(other) = other {
-        lfs.time != other.time
-    } else {
-        true
+    let mut warning = None;
+    let (id, offset) = match action {
+        ScrimsAction::Select => {
+            let ComponentInteractionDataKind::StringSelect { values } = &i.data.kind else {
+                return Ok(());
+            };
+            let Some(Ok(id)) = values.first().map(|value| value.parse::<i32>()) else {
+                return Ok(());
+            };
+            (id, 0)
+        }
+        ScrimsAction::Prev(id) | ScrimsAction::Next(id) | ScrimsAction::Refresh(id) => (id, 0),
+        ScrimsAction::More { scrim_id, offset } => (scrim_id, offset),
+        ScrimsAction::Cancel(id) => {
+            own_scrim(db, id, user_id).await?;
+            db::cancel_scrim_in(db, id).await?;
+            (id, 0)
+        }
+        ScrimsAction::Restore(id) => {
+            own_scrim(db, id, user_id).await?;
+            db::restore_scrim_in(db, id).await?;
+            (id, 0)
+        }
+        ScrimsAction::CheckIn(id) => {
+            let scrim = own_scrim(db, id, user_id).await?;
+            if !check_in::is_open(&scrim, Utc::now()) || !db::check_in(db, id).await? {
+                return Err(Text::new("check-in-closed").into());
+            }
+            (id, 0)
+        }
+        ScrimsAction::Revoke(id) => {
+            own_scrim(db, id, user_id).await?;
+            db::revoke_scrim_in(db, id).await?;
+            (id, 0)
+        }
+        ScrimsAction::Accept { scrim_id, other_id } => {
+            own_scrim(db, scrim_id, user_id).await?;
+            let guild_id = i.guild_id.map(|id| id.get() as i64);
+            let policy = db::get_overlap_policy(db, guild_id).await?;
+            let times = db::match_scrims_in(db, scrim_id, other_id, policy).await?;
+            warning = (!times.is_empty()).then(|| overlap_warning(&times));
+            (scrim_id, 0)
+        }
+        ScrimsAction::Propose { scrim_id, other_id } => {
+            let scrim = own_scrim(db, scrim_id, user_id).await?;
+            let other_id = match other_id {
+                Some(other_id) => other_id,
+                None => scrim.match_id.ok_or(Text::new("scrim-not-matched"))?,
+            };
+            let other = db::get_scrim_in(db, other_id).await?;
+            // The modal takes care of the response
+            return proposals::propose_time(
+                ctx,
+                data,
+                i,
+                &scrim,
+                Some(&other),
+                None,
+                other.creator_id,
+            )
+            .await;
+        }
     };
 
-    if show_time {
-        write!(content, " on <t:{}:F>", lfs.time.timestamp(),).unwrap();
-        if lfs.time - Utc::now() < chrono::Duration::days(1) {
-            write!(content, " (<t:{}:R>)", lfs.time.timestamp()).unwrap();
-        }
+    let view = scrims_msg(db, user_id, Some(id), offset, locale)
+        .await?
+        .ok_or(Text::new("scrims-none"))?;
+    i.create_response(
+        ctx,
+        CreateInteractionResponse::UpdateMessage(view.response()),
+    )
+    .await?;
+    if let Some(warning) = warning {
+        i.create_followup(
+            ctx,
+            CreateInteractionResponseFollowup::new()
+                .content(warning.render(locale))
+                .ephemeral(true),
+        )
+        .await?;
     }
+    Ok(())
 }
@@ -3,7 +3,11 @@ use std::str::FromStr;
 use chrono::Utc;
 use fuzzy_matcher::FuzzyMatcher;
 
-use crate::{db, Context, Error};
+use crate::{
+    db,
+    i18n::{self, t, Text},
+    Context, Error,
+};
 
 async fn complete_zone(_ctx: Context<'_>, partial: &str) -> Vec<String> {
     let partial = partial.trim();
@@ -18,34 +22,32 @@ async fn complete_zone(_ctx: Context<'_>, partial: &str) -> Vec<String> {
     zones
 }
 
-#[poise::command(
-    slash_command,
-    description_localized("en-US", "Set your timezone"),
-    ephemeral
-)]
+#[poise::command(slash_command, ephemeral)]
 #[tracing::instrument(err, skip(ctx), fields(user = ctx.author().id.get()))]
 pub async fn timezone(
     ctx: Context<'_>,
-    #[description = "Timezone to set. Use autocomplete to see available timezones"]
-    #[autocomplete = "complete_zone"]
-    zone: Option<String>,
+    #[autocomplete = "complete_zone"] zone: Option<String>,
 ) -> Result<(), Error> {
+    let locale = i18n::locale(ctx).await;
     if let Some(zone) = zone {
-        let tz = chrono_tz::Tz::from_str(&zone).map_err(|_| "Invalid timezone")?;
+        let tz = chrono_tz::Tz::from_str(&zone).map_err(|_| Text::new("timezone-invalid"))?;
         db::set_timezone(ctx, tz).await?;
 
-        ctx.reply(format!(
-            "Timezone set to `{}`. Current time: `{}`",
-            tz,
-            Utc::now().with_timezone(&tz).format("%d/%m/%Y %H:%M")
+        let prefs = db::get_preferences(ctx).await?;
+        ctx.reply(t!(
+            locale,
+            "timezone-set",
+            zone = tz,
+            time = prefs.short(Utc::now())
         ))
         .await?;
     } else {
-        let tz = db::get_timezone(ctx).await?;
-        ctx.reply(format!(
-            "Your Timezone is `{}`. Current time: `{}`",
-            tz,
-            Utc::now().with_timezone(&tz).format("%d/%m/%Y %H:%M")
+        let prefs = db::get_preferences(ctx).await?;
+        ctx.reply(t!(
+            locale,
+            "timezone-current",
+            zone = prefs.timezone,
+            time = prefs.short(Utc::now())
         ))
         .await?;
     }